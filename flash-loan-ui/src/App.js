@@ -18,7 +18,15 @@ import {
   WalletModalProvider,
   WalletMultiButton,
 } from '@solana/wallet-adapter-react-ui';
-import { clusterApiUrl, PublicKey, Transaction, SystemProgram, Keypair } from '@solana/web3.js';
+import {
+  clusterApiUrl,
+  PublicKey,
+  Transaction,
+  TransactionInstruction,
+  SystemProgram,
+  Keypair,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from '@solana/web3.js';
 import { Program, AnchorProvider } from '@coral-xyz/anchor';
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import BN from 'bn.js';
@@ -30,6 +38,19 @@ import { loadPersistedWallet, usePersistedWallet } from './utils/wallet';
 // Import wallet adapter styles
 import '@solana/wallet-adapter-react-ui/styles.css';
 
+// Solend's flash borrow and flash repay instruction tags
+const SOLEND_FLASH_BORROW = 19;
+const SOLEND_FLASH_REPAY = 20;
+
+// Slots a simulated quote may be executed within
+const DEADLINE_SLOTS = 150;
+
+// Borsh size of the program's SimulationReport
+const SIMULATION_REPORT_LEN = 53;
+
+// The program's DexKind variants, as the IDL names them
+const DEX_KINDS = ['whirlpool', 'whirlpoolV2', 'raydiumAmmV4', 'raydiumCpmm', 'raydiumClmm'];
+
 // Custom wallet adapter for persistent deployment wallet
 class PersistentWalletAdapter {
   constructor(keypair) {
@@ -299,6 +320,9 @@ function FlashLoanArbitrageInterface({ network, programId }) {
   const [loanTokenAccount, setLoanTokenAccount] = useState('');
   const [loanReserveAccount, setLoanReserveAccount] = useState(PORT_FINANCE_KEYS.solReserve);
   const [lendingFeeAccount, setLendingFeeAccount] = useState('');
+  const [loanMint, setLoanMint] = useState(PORT_FINANCE_KEYS.solMint);
+  const [reserveLiquiditySupply, setReserveLiquiditySupply] = useState(PORT_FINANCE_KEYS.solSupply);
+  const [lendingMarket, setLendingMarket] = useState(PORT_FINANCE_KEYS.lendingMarket);
   
   // DEX A accounts
   const [dexAProgram, setDexAProgram] = useState('');
@@ -308,6 +332,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
   const [dexAOutputTokenAccount, setDexAOutputTokenAccount] = useState('');
   const [dexATokenAAccount, setDexATokenAAccount] = useState('');
   const [dexATokenBAccount, setDexATokenBAccount] = useState('');
+  const [dexAKind, setDexAKind] = useState('raydiumCpmm');
+  const [dexARemainingAccounts, setDexARemainingAccounts] = useState('');
   
   // DEX B accounts
   const [dexBProgram, setDexBProgram] = useState('');
@@ -317,6 +343,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
   const [dexBOutputTokenAccount, setDexBOutputTokenAccount] = useState('');
   const [dexBTokenAAccount, setDexBTokenAAccount] = useState('');
   const [dexBTokenBAccount, setDexBTokenBAccount] = useState('');
+  const [dexBKind, setDexBKind] = useState('raydiumCpmm');
+  const [dexBRemainingAccounts, setDexBRemainingAccounts] = useState('');
   
   const [status, setStatus] = useState('');
   const [isLoading, setIsLoading] = useState(false);
@@ -344,12 +372,15 @@ function FlashLoanArbitrageInterface({ network, programId }) {
   const loadPortFinanceDefaults = () => {
     // Set the lending program and reserve accounts
     setLendingProgram(PORT_FINANCE_KEYS.lendingProgram);
+    setLendingMarket(PORT_FINANCE_KEYS.lendingMarket);
     
     // For arbitrage between selected token and USDC
     // Update reserve account based on selected token
     switch(selectedToken) {
       case 'SOL':
         setLoanReserveAccount(PORT_FINANCE_KEYS.solReserve);
+        setLoanMint(PORT_FINANCE_KEYS.solMint);
+        setReserveLiquiditySupply(PORT_FINANCE_KEYS.solSupply);
         
         // DEX A - SOL to USDC
         setDexAInputTokenAccount(PORT_FINANCE_KEYS.solSupply);
@@ -366,6 +397,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         
       case 'USDC':
         setLoanReserveAccount(PORT_FINANCE_KEYS.usdcReserve);
+        setLoanMint(PORT_FINANCE_KEYS.usdcMint);
+        setReserveLiquiditySupply(PORT_FINANCE_KEYS.usdcSupply);
         
         // DEX A - USDC to SOL
         setDexAInputTokenAccount(PORT_FINANCE_KEYS.usdcSupply);
@@ -382,6 +415,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         
       case 'USDT':
         setLoanReserveAccount(PORT_FINANCE_KEYS.usdtReserve);
+        setLoanMint(PORT_FINANCE_KEYS.usdtMint);
+        setReserveLiquiditySupply(PORT_FINANCE_KEYS.usdtSupply);
         
         // DEX A - USDT to USDC
         setDexAInputTokenAccount(PORT_FINANCE_KEYS.usdtSupply);
@@ -398,6 +433,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         
       case 'BTC':
         setLoanReserveAccount(PORT_FINANCE_KEYS.btcReserve);
+        setLoanMint(PORT_FINANCE_KEYS.btcMint);
+        setReserveLiquiditySupply(PORT_FINANCE_KEYS.btcSupply);
         
         // DEX A - BTC to USDC
         setDexAInputTokenAccount(PORT_FINANCE_KEYS.btcSupply);
@@ -414,6 +451,8 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         
       case 'MER':
         setLoanReserveAccount(PORT_FINANCE_KEYS.merReserve);
+        setLoanMint(PORT_FINANCE_KEYS.merMint);
+        setReserveLiquiditySupply(PORT_FINANCE_KEYS.merSupply);
         
         // DEX A - MER to USDC
         setDexAInputTokenAccount(PORT_FINANCE_KEYS.merSupply);
@@ -461,6 +500,9 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         loanTokenAccount: Keypair.generate().publicKey.toString(),
         loanReserveAccount: Keypair.generate().publicKey.toString(),
         lendingFeeAccount: Keypair.generate().publicKey.toString(),
+        loanMint: Keypair.generate().publicKey.toString(),
+        reserveLiquiditySupply: Keypair.generate().publicKey.toString(),
+        lendingMarket: Keypair.generate().publicKey.toString(),
         dexAProgram: Keypair.generate().publicKey.toString(),
         dexAPool: Keypair.generate().publicKey.toString(),
         dexAAuthority: Keypair.generate().publicKey.toString(),
@@ -485,6 +527,9 @@ function FlashLoanArbitrageInterface({ network, programId }) {
     setLoanTokenAccount(mockAccounts.loanTokenAccount);
     setLoanReserveAccount(mockAccounts.loanReserveAccount);
     setLendingFeeAccount(mockAccounts.lendingFeeAccount);
+    setLoanMint(mockAccounts.loanMint);
+    setReserveLiquiditySupply(mockAccounts.reserveLiquiditySupply);
+    setLendingMarket(mockAccounts.lendingMarket);
     setDexAProgram(mockAccounts.dexAProgram);
     setDexAPool(mockAccounts.dexAPool);
     setDexAAuthority(mockAccounts.dexAAuthority);
@@ -508,6 +553,7 @@ function FlashLoanArbitrageInterface({ network, programId }) {
     return {
       lendingProgram: isValidPublicKey(lendingProgram) ? new PublicKey(lendingProgram) : null,
       loanTokenAccount: isValidPublicKey(loanTokenAccount) ? new PublicKey(loanTokenAccount) : null,
+      loanMint: isValidPublicKey(loanMint) ? new PublicKey(loanMint) : null,
       loanReserveAccount: isValidPublicKey(loanReserveAccount) ? new PublicKey(loanReserveAccount) : null,
       lendingFeeAccount: isValidPublicKey(lendingFeeAccount) ? new PublicKey(lendingFeeAccount) : null,
       dexAProgram: isValidPublicKey(dexAProgram) ? new PublicKey(dexAProgram) : null,
@@ -541,7 +587,115 @@ function FlashLoanArbitrageInterface({ network, programId }) {
     return provider;
   };
 
-  // Simulate the arbitrage before execution
+  // Derives a PDA of the arbitrage program
+  const findProgramPda = (seeds) => {
+    return PublicKey.findProgramAddressSync(seeds, new PublicKey(programId))[0];
+  };
+
+  // Parses a whitespace or comma separated list of public keys
+  const parsePublicKeys = (text) => {
+    return text.split(/[\s,]+/).filter(Boolean).map((key) => new PublicKey(key));
+  };
+
+  // The oracle entry and feed registered for a mint, or nulls when there is none
+  const findOracle = async (program, mint) => {
+    const entry = findProgramPda([Buffer.from('oracle'), mint.toBuffer()]);
+    const oracleEntry = await program.account.oracleEntry.fetchNullable(entry);
+    return oracleEntry ? { entry, oracle: oracleEntry.oracle } : { entry: null, oracle: null };
+  };
+
+  // Builds the accounts, arguments and remaining accounts shared by simulation and execution
+  const buildArbitrage = async (program) => {
+    const accounts = getAccountsFromState();
+    const missing = Object.keys(accounts).filter((name) => !accounts[name]);
+    if (missing.length > 0) {
+      throw new Error(`Missing or invalid accounts: ${missing.join(', ')}`);
+    }
+    if (!isValidPublicKey(reserveLiquiditySupply) || !isValidPublicKey(lendingMarket)) {
+      throw new Error('Reserve liquidity supply and lending market are required');
+    }
+
+    // The loan mint's owner is the SPL Token or Token-2022 program
+    const loanMintInfo = await connection.getAccountInfo(accounts.loanMint);
+    if (!loanMintInfo) {
+      throw new Error('Loan mint not found');
+    }
+
+    // The intermediate mint is the mint of DEX A's output token account
+    const outputAccountInfo = await connection.getAccountInfo(accounts.dexAOutputTokenAccount);
+    if (!outputAccountInfo) {
+      throw new Error('DEX A output token account not found');
+    }
+    const intermediateMint = new PublicKey(outputAccountInfo.data.slice(0, 32));
+
+    const loanOracle = await findOracle(program, accounts.loanMint);
+    const intermediateOracle = await findOracle(program, intermediateMint);
+
+    const authority = wallet.publicKey;
+    const instructionAccounts = {
+      ...accounts,
+      base: {
+        authority,
+        tokenProgram: loanMintInfo.owner,
+        systemProgram: SystemProgram.programId,
+      },
+      config: findProgramPda([Buffer.from('config')]),
+      programRegistry: findProgramPda([Buffer.from('program_registry')]),
+      vault: null,
+      executorRole: findProgramPda([Buffer.from('operator'), Buffer.from('executor'), authority.toBuffer()]),
+      profitLedger: null,
+      strategy: null,
+      repaymentTokenAccount: null,
+      instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      dexAPoolEntry: findProgramPda([Buffer.from('pool'), accounts.dexAPool.toBuffer()]),
+      dexBPoolEntry: findProgramPda([Buffer.from('pool'), accounts.dexBPool.toBuffer()]),
+      loanOracleEntry: loanOracle.entry,
+      loanOracle: loanOracle.oracle,
+      intermediateOracleEntry: intermediateOracle.entry,
+      intermediateOracle: intermediateOracle.oracle,
+    };
+
+    // Each leg's DEX accounts follow the lender's, leg A first. Program accounts
+    // must stay read-only; the rest are passed writable for the DEX to use.
+    const legAAccounts = parsePublicKeys(dexARemainingAccounts);
+    const legBAccounts = parsePublicKeys(dexBRemainingAccounts);
+    const legKeys = [...legAAccounts, ...legBAccounts];
+    const legInfos = legKeys.length > 0 ? await connection.getMultipleAccountsInfo(legKeys) : [];
+    const remainingAccounts = [
+      { pubkey: new PublicKey(reserveLiquiditySupply), isSigner: false, isWritable: true },
+      { pubkey: new PublicKey(lendingMarket), isSigner: false, isWritable: false },
+      ...legKeys.map((pubkey, i) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: !legInfos[i]?.executable,
+      })),
+    ];
+
+    return {
+      accounts: instructionAccounts,
+      remainingAccounts,
+      tokenProgram: loanMintInfo.owner,
+      // The UI builds the Solend flash borrow and repay around the arbitrage
+      provider: { solend: {} },
+      legA: { dex: { [dexAKind]: {} }, remainingAccountsLen: legAAccounts.length, sqrtPriceLimit: new BN(0) },
+      legB: { dex: { [dexBKind]: {} }, remainingAccountsLen: legBAccounts.length, sqrtPriceLimit: new BN(0) },
+    };
+  };
+
+  // Reads the instruction's return data from the simulation logs. The runtime
+  // drops trailing zero bytes, so the data is padded back to the type's size.
+  const decodeReturnData = (program, logs, typeName, length) => {
+    const prefix = `Program return: ${program.programId.toBase58()} `;
+    const log = logs.find((line) => line.startsWith(prefix));
+    if (!log) {
+      throw new Error('Simulation returned no data');
+    }
+    const data = Buffer.alloc(length);
+    Buffer.from(log.slice(prefix.length), 'base64').copy(data);
+    return program.coder.types.decode(typeName, data);
+  };
+
+  // Simulate the arbitrage before execution and return its report
   const simulateArbitrage = async () => {
     setIsSimulating(true);
     setErrorMessage(null);
@@ -550,43 +704,44 @@ function FlashLoanArbitrageInterface({ network, programId }) {
     try {
       const provider = getProvider();
       const program = new Program(idl, programId, provider);
+      const arbitrage = await buildArbitrage(program);
       
-      // Get accounts from current state
-      const accounts = getAccountsFromState();
-      if (!accounts) return;
-      
-      console.log('Simulating arbitrage with accounts:', accounts);
+      console.log('Simulating arbitrage with accounts:', arbitrage.accounts);
       
       // Convert loan amount and min profit to program format (u64)
-      const loanAmount = new BN(Number(loanAmount) * 1e9); // Convert to lamports
-      const minProfitAmount = new BN(Number(minProfitAmount) * 1e9); // Convert to lamports
+      const loanAmountBN = new BN(Number(loanAmount) * 1e9); // Convert to lamports
+      const minProfitAmountBN = new BN(Number(minProfitAmount) * 1e9); // Convert to lamports
+      
+      // The slot the quote is taken at bounds how long execution may use it
+      const quoteSlot = await connection.getSlot();
       
       // Call the simulation instruction
       const result = await program.methods
-        .simulateArbitrage(loanAmount, minProfitAmount)
-        .accounts(accounts)
+        .simulateArbitrage(loanAmountBN, minProfitAmountBN, arbitrage.provider, arbitrage.legA, arbitrage.legB)
+        .accounts(arbitrage.accounts)
+        .remainingAccounts(arbitrage.remainingAccounts)
         .simulate();
       
-      // Extract the estimated profit from result
-      // Note: In Anchor, simulations return the result inside a array at returnData.returnData
-      const estimatedProfit = new BN(result.returnData.returnData);
-      const estimatedProfitSol = estimatedProfit.toNumber() / 1e9;
-      
-      setSimulationResult({
-        isProftable: estimatedProfit.gt(minProfitAmount),
-        estimatedProfit: estimatedProfitSol,
-        loanAmount: Number(loanAmount) / 1e9,
-        minProfitAmount: Number(minProfitAmount) / 1e9
-      });
-      
-      console.log('Simulation result:', {
-        estimatedProfit: estimatedProfitSol,
-        isProftable: estimatedProfit.gt(minProfitAmount)
-      });
+      const report = decodeReturnData(program, result.raw, 'SimulationReport', SIMULATION_REPORT_LEN);
+      const simulation = {
+        isProfitable: report.profitable,
+        netProfit: report.netProfit.toNumber() / 1e9,
+        grossProfit: report.grossProfit.toNumber() / 1e9,
+        lenderFee: report.lenderFee.toNumber() / 1e9,
+        legOutputs: report.legOutputs,
+        priceImpactBps: report.priceImpactBps,
+        loanAmount: report.loanAmount.toNumber() / 1e9,
+        minProfitAmount: Number(minProfitAmount),
+        quoteSlot,
+      };
+      setSimulationResult(simulation);
       
+      console.log('Simulation result:', simulation);
+      return simulation;
     } catch (error) {
       console.error('Simulation error:', error);
       setErrorMessage(`Simulation failed: ${error.message}`);
+      return null;
     } finally {
       setIsSimulating(false);
     }
@@ -600,46 +755,109 @@ function FlashLoanArbitrageInterface({ network, programId }) {
     setErrorMessage(null);
     
     try {
-      // Run simulation first
-      await simulateArbitrage();
-      
-      // Check if simulation was successful and profitable
-      if (simulationResult && !simulationResult.isProftable) {
-        setErrorMessage("Transaction would not be profitable based on simulation results. Execution aborted.");
-        setIsLoading(false);
-        return;
-      }
-      
-      const provider = getProvider();
-      const program = new Program(idl, programId, provider);
-      
-      // Get accounts from current state
-      const accounts = getAccountsFromState();
-      if (!accounts) return;
-      
-      console.log('Executing arbitrage with accounts:', accounts);
-      
       // Input validation
       if (!loanAmount || isNaN(loanAmount) || Number(loanAmount) <= 0) {
         setErrorMessage("Please enter a valid loan amount");
-        setIsLoading(false);
         return;
       }
       
       if (!minProfitAmount || isNaN(minProfitAmount) || Number(minProfitAmount) <= 0) {
         setErrorMessage("Please enter a valid minimum profit amount");
-        setIsLoading(false);
         return;
       }
       
+      // Run simulation first
+      const simulation = await simulateArbitrage();
+      if (!simulation) {
+        return;
+      }
+      
+      // Check if simulation was profitable
+      if (!simulation.isProfitable) {
+        setErrorMessage("Transaction would not be profitable based on simulation results. Execution aborted.");
+        return;
+      }
+      
+      const provider = getProvider();
+      const program = new Program(idl, programId, provider);
+      const arbitrage = await buildArbitrage(program);
+      
+      console.log('Executing arbitrage with accounts:', arbitrage.accounts);
+      
       // Convert loan amount and min profit to program format (u64)
       const loanAmountBN = new BN(Number(loanAmount) * 1e9); // Convert to lamports
       const minProfitAmountBN = new BN(Number(minProfitAmount) * 1e9); // Convert to lamports
       
+      // Each leg must return at least its simulated output less the slippage tolerance
+      const slippageBps = Math.round(slippageTolerance * 100);
+      const minOut = (output) => output.muln(10000 - slippageBps).divn(10000);
+      
+      // The quote is only good for a few slots past the one it was simulated at
+      const deadline = {
+        validUntilSlot: new BN(simulation.quoteSlot + DEADLINE_SLOTS),
+        quoteSlot: new BN(simulation.quoteSlot),
+      };
+      
+      // The arbitrage sits between the Solend flash borrow and repay, the borrow first
+      const reserve = arbitrage.accounts.loanReserveAccount;
+      const market = new PublicKey(lendingMarket);
+      const supply = new PublicKey(reserveLiquiditySupply);
+      const lendingMarketAuthority = PublicKey.findProgramAddressSync(
+        [market.toBuffer()],
+        arbitrage.accounts.lendingProgram
+      )[0];
+      const borrowInstruction = new TransactionInstruction({
+        programId: arbitrage.accounts.lendingProgram,
+        keys: [
+          { pubkey: supply, isSigner: false, isWritable: true },
+          { pubkey: arbitrage.accounts.loanTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: reserve, isSigner: false, isWritable: true },
+          { pubkey: market, isSigner: false, isWritable: false },
+          { pubkey: lendingMarketAuthority, isSigner: false, isWritable: false },
+          { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+          { pubkey: arbitrage.tokenProgram, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([SOLEND_FLASH_BORROW]), loanAmountBN.toArrayLike(Buffer, 'le', 8)]),
+      });
+      const repayInstruction = new TransactionInstruction({
+        programId: arbitrage.accounts.lendingProgram,
+        keys: [
+          { pubkey: arbitrage.accounts.loanTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: supply, isSigner: false, isWritable: true },
+          { pubkey: arbitrage.accounts.lendingFeeAccount, isSigner: false, isWritable: true },
+          // The host fee goes back to the borrower
+          { pubkey: arbitrage.accounts.loanTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: reserve, isSigner: false, isWritable: true },
+          { pubkey: market, isSigner: false, isWritable: false },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
+          { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+          { pubkey: arbitrage.tokenProgram, isSigner: false, isWritable: false },
+        ],
+        // The borrow is the transaction's first instruction
+        data: Buffer.concat([
+          Buffer.from([SOLEND_FLASH_REPAY]),
+          loanAmountBN.toArrayLike(Buffer, 'le', 8),
+          Buffer.from([0]),
+        ]),
+      });
+      
       // Send transaction
       const tx = await program.methods
-        .flashLoanAndArbitrage(loanAmountBN, minProfitAmountBN)
-        .accounts(accounts)
+        .flashLoanAndArbitrage(
+          loanAmountBN,
+          minProfitAmountBN,
+          arbitrage.provider,
+          arbitrage.legA,
+          arbitrage.legB,
+          minOut(simulation.legOutputs[0]),
+          minOut(simulation.legOutputs[1]),
+          slippageBps,
+          deadline
+        )
+        .accounts(arbitrage.accounts)
+        .remainingAccounts(arbitrage.remainingAccounts)
+        .preInstructions([borrowInstruction])
+        .postInstructions([repayInstruction])
         .rpc();
       
       setStatus(`Transaction sent to ${network}. Processing...`);
@@ -678,15 +896,24 @@ function FlashLoanArbitrageInterface({ network, programId }) {
   const renderSimulationResults = () => {
     if (!simulationResult) return null;
     
-    const { estimatedProfit, isProftable, loanAmount, minProfitAmount } = simulationResult;
+    const {
+      isProfitable,
+      netProfit,
+      grossProfit,
+      lenderFee,
+      legOutputs,
+      priceImpactBps,
+      loanAmount,
+      minProfitAmount,
+    } = simulationResult;
     
     return (
       <div style={{
         marginTop: '20px',
         padding: '15px',
         borderRadius: '8px',
-        backgroundColor: isProftable ? '#e6ffe6' : '#ffe6e6',
-        border: `1px solid ${isProftable ? '#b3ffb3' : '#ffb3b3'}`,
+        backgroundColor: isProfitable ? '#e6ffe6' : '#ffe6e6',
+        border: `1px solid ${isProfitable ? '#b3ffb3' : '#ffb3b3'}`,
         maxWidth: '500px',
         width: '100%'
       }}>
@@ -694,10 +921,14 @@ function FlashLoanArbitrageInterface({ network, programId }) {
         <div>
           <p><strong>Loan Amount:</strong> {loanAmount} SOL</p>
           <p><strong>Min Profit Required:</strong> {minProfitAmount} SOL</p>
-          <p><strong>Estimated Profit:</strong> {estimatedProfit.toFixed(6)} SOL</p>
+          <p><strong>Leg Outputs:</strong> {legOutputs[0].toString()} → {legOutputs[1].toString()}</p>
+          <p><strong>Price Impact:</strong> {priceImpactBps[0]} bps / {priceImpactBps[1]} bps</p>
+          <p><strong>Lender Fee:</strong> {lenderFee.toFixed(6)} SOL</p>
+          <p><strong>Gross Profit:</strong> {grossProfit.toFixed(6)} SOL</p>
+          <p><strong>Net Profit:</strong> {netProfit.toFixed(6)} SOL</p>
           <p>
             <strong>Status:</strong> 
-            {isProftable 
+            {isProfitable 
               ? '✅ Transaction would be profitable' 
               : '❌ Transaction would not be profitable'}
           </p>
@@ -929,6 +1160,63 @@ function FlashLoanArbitrageInterface({ network, programId }) {
             placeholder="Enter Lending Fee Account"
           />
         </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            Loan Mint:
+          </label>
+          <input
+            type="text"
+            value={loanMint}
+            onChange={(e) => setLoanMint(e.target.value)}
+            style={{ 
+              width: '100%', 
+              padding: '8px', 
+              borderRadius: '4px', 
+              border: '1px solid #ccc',
+              backgroundColor: isValidPublicKey(loanMint) ? '#f0fff0' : '#fff0f0'
+            }}
+            placeholder="Enter Loan Mint"
+          />
+        </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            Reserve Liquidity Supply:
+          </label>
+          <input
+            type="text"
+            value={reserveLiquiditySupply}
+            onChange={(e) => setReserveLiquiditySupply(e.target.value)}
+            style={{ 
+              width: '100%', 
+              padding: '8px', 
+              borderRadius: '4px', 
+              border: '1px solid #ccc',
+              backgroundColor: isValidPublicKey(reserveLiquiditySupply) ? '#f0fff0' : '#fff0f0'
+            }}
+            placeholder="Enter Reserve Liquidity Supply Account"
+          />
+        </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            Lending Market:
+          </label>
+          <input
+            type="text"
+            value={lendingMarket}
+            onChange={(e) => setLendingMarket(e.target.value)}
+            style={{ 
+              width: '100%', 
+              padding: '8px', 
+              borderRadius: '4px', 
+              border: '1px solid #ccc',
+              backgroundColor: isValidPublicKey(lendingMarket) ? '#f0fff0' : '#fff0f0'
+            }}
+            placeholder="Enter Lending Market Account"
+          />
+        </div>
       </div>
       
       {/* DEX A Accounts */}
@@ -1067,6 +1355,34 @@ function FlashLoanArbitrageInterface({ network, programId }) {
             placeholder="Enter DEX A Token B Account"
           />
         </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            DEX A Kind:
+          </label>
+          <select
+            value={dexAKind}
+            onChange={(e) => setDexAKind(e.target.value)}
+            style={{ width: '100%', padding: '8px', borderRadius: '4px', border: '1px solid #ccc' }}
+          >
+            {DEX_KINDS.map((kind) => (
+              <option key={kind} value={kind}>{kind}</option>
+            ))}
+          </select>
+        </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            DEX A Remaining Accounts (in the order the DEX adapter expects):
+          </label>
+          <textarea
+            value={dexARemainingAccounts}
+            onChange={(e) => setDexARemainingAccounts(e.target.value)}
+            rows={4}
+            style={{ width: '100%', padding: '8px', borderRadius: '4px', border: '1px solid #ccc' }}
+            placeholder="One public key per line"
+          />
+        </div>
       </div>
       
       {/* DEX B Accounts */}
@@ -1205,6 +1521,34 @@ function FlashLoanArbitrageInterface({ network, programId }) {
             placeholder="Enter DEX B Token B Account"
           />
         </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            DEX B Kind:
+          </label>
+          <select
+            value={dexBKind}
+            onChange={(e) => setDexBKind(e.target.value)}
+            style={{ width: '100%', padding: '8px', borderRadius: '4px', border: '1px solid #ccc' }}
+          >
+            {DEX_KINDS.map((kind) => (
+              <option key={kind} value={kind}>{kind}</option>
+            ))}
+          </select>
+        </div>
+        
+        <div style={{ marginBottom: '15px' }}>
+          <label style={{ display: 'block', marginBottom: '5px' }}>
+            DEX B Remaining Accounts (in the order the DEX adapter expects):
+          </label>
+          <textarea
+            value={dexBRemainingAccounts}
+            onChange={(e) => setDexBRemainingAccounts(e.target.value)}
+            rows={4}
+            style={{ width: '100%', padding: '8px', borderRadius: '4px', border: '1px solid #ccc' }}
+            placeholder="One public key per line"
+          />
+        </div>
       </div>
       
       {/* Arbitrage Summary */}
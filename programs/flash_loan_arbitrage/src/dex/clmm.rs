@@ -0,0 +1,634 @@
+//! Exact-input quotes for concentrated liquidity pools
+//!
+//! Walks the pool's initialized ticks from its current price the same way Orca's
+//! Whirlpool and Raydium's CLMM swaps do, step by step across tick arrays, and reports
+//! the output, the fee and the ending price. The two programs share the swap step math
+//! and differ only in their tick math, price bounds and tick array rules, which
+//! `ClmmProgram` selects. Only `core` is used and every step is integer math, so the
+//! same code gives the same answer on-chain and in an off-chain bot.
+
+use crate::dex::math::{self, U256};
+
+pub const MIN_TICK_INDEX: i32 = -443_636;
+pub const MAX_TICK_INDEX: i32 = 443_636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_515_401_279_992_447_579_055;
+// Raydium rounds its tick math differently, which moves the upper bound
+pub const RAYDIUM_MAX_SQRT_PRICE_X64: u128 = 79_226_673_521_066_979_257_578_248_091;
+// Fee rates are in hundredths of a basis point
+pub const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+
+/// Why a quote could not be produced
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteError {
+    /// An intermediate value overflowed or the price left its bounds
+    MathOverflow,
+    /// The swap ran past the tick arrays that were provided
+    TickArraySequence,
+    /// The price limit is on the wrong side of the current price
+    InvalidSqrtPriceLimit,
+}
+
+/// The program whose swap a quote follows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClmmProgram {
+    /// Orca Whirlpool, whose swaps pass contiguous tick arrays
+    Whirlpool,
+    /// Raydium CLMM, whose swaps pass only the tick arrays holding initialized ticks
+    RaydiumClmm,
+}
+
+impl ClmmProgram {
+    /// Square root of `1.0001^tick` as a Q64.64 number, rounded like the program
+    pub fn sqrt_price_from_tick_index(self, tick: i32) -> Result<u128, QuoteError> {
+        match self {
+            ClmmProgram::Whirlpool => sqrt_price_from_tick_index(tick),
+            ClmmProgram::RaydiumClmm => raydium_sqrt_price_from_tick_index(tick),
+        }
+    }
+
+    pub fn max_sqrt_price(self) -> u128 {
+        match self {
+            ClmmProgram::Whirlpool => MAX_SQRT_PRICE_X64,
+            ClmmProgram::RaydiumClmm => RAYDIUM_MAX_SQRT_PRICE_X64,
+        }
+    }
+
+    /// The widest price limit the program accepts in the swap direction
+    pub fn default_sqrt_price_limit(self, a_to_b: bool) -> u128 {
+        match (self, a_to_b) {
+            (ClmmProgram::Whirlpool, true) => MIN_SQRT_PRICE_X64,
+            (ClmmProgram::Whirlpool, false) => MAX_SQRT_PRICE_X64,
+            // Raydium requires the limit to lie strictly inside its bounds
+            (ClmmProgram::RaydiumClmm, true) => MIN_SQRT_PRICE_X64 + 1,
+            (ClmmProgram::RaydiumClmm, false) => RAYDIUM_MAX_SQRT_PRICE_X64 - 1,
+        }
+    }
+}
+
+/// The pricing state of a concentrated liquidity pool
+#[derive(Clone, Copy, Debug)]
+pub struct ClmmPool {
+    pub program: ClmmProgram,
+    /// Current price as a Q64.64 square root
+    pub sqrt_price: u128,
+    /// Liquidity active at the current price
+    pub liquidity: u128,
+    pub tick_current_index: i32,
+    pub tick_spacing: u16,
+    /// Fee rate in hundredths of a basis point
+    pub fee_rate: u32,
+    /// Number of ticks stored in each tick array account
+    pub ticks_per_array: i32,
+}
+
+/// Read access to one tick array, in the order the swap will cross them
+pub trait TickArrayView {
+    fn start_tick_index(&self) -> i32;
+
+    /// Liquidity net of the tick at `offset`, or `None` when it is not initialized
+    fn liquidity_net(&self, offset: usize) -> Option<i128>;
+}
+
+/// The result of an exact-input quote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Input consumed, fee included. Less than requested if the price limit was hit.
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub end_sqrt_price: u128,
+}
+
+/// Output of swapping exactly `amount` at the pool's current price, without price impact
+///
+/// Takes the fee like `quote_exact_input`, so the two differ only by the swap's price
+/// impact.
+pub fn spot_output(pool: &ClmmPool, amount: u64, a_to_b: bool) -> Result<u64, QuoteError> {
+    let fee_complement = FEE_RATE_DENOMINATOR
+        .checked_sub(pool.fee_rate as u128)
+        .ok_or(QuoteError::MathOverflow)?;
+    let amount_after_fee = math::mul_div_floor(amount as u128, fee_complement, FEE_RATE_DENOMINATOR)
+        .ok_or(QuoteError::MathOverflow)?;
+
+    // The price of A in B is sqrt_price^2 / 2^128
+    let price_x128 = U256::mul(pool.sqrt_price, pool.sqrt_price);
+    let amount_out = if a_to_b {
+        let price_x64 = (price_x128 >> 64).to_u128().ok_or(QuoteError::MathOverflow)?;
+        math::mul_div_floor(amount_after_fee, price_x64, 1 << 64)
+    } else {
+        (U256::from_u128(amount_after_fee) << 128)
+            .div_rem(price_x128)
+            .and_then(|(quotient, _)| quotient.to_u128())
+    };
+    amount_out
+        .and_then(|amount_out| u64::try_from(amount_out).ok())
+        .ok_or(QuoteError::MathOverflow)
+}
+
+/// Quotes swapping exactly `amount` through the pool
+///
+/// `tick_arrays` are the arrays the swap would pass, starting with the one holding the
+/// current tick. Raydium CLMM arrays may skip arrays without initialized ticks, whose
+/// range the swap crosses at constant liquidity. A `sqrt_price_limit` of 0 uses the
+/// widest limit in the swap direction.
+pub fn quote_exact_input<T: TickArrayView>(
+    pool: &ClmmPool,
+    tick_arrays: &[T],
+    amount: u64,
+    a_to_b: bool,
+    sqrt_price_limit: u128,
+) -> Result<SwapQuote, QuoteError> {
+    let max_sqrt_price = pool.program.max_sqrt_price();
+    let sqrt_price_limit = match sqrt_price_limit {
+        0 => pool.program.default_sqrt_price_limit(a_to_b),
+        limit => limit,
+    };
+    if !(MIN_SQRT_PRICE_X64..=max_sqrt_price).contains(&sqrt_price_limit)
+        || (a_to_b && sqrt_price_limit > pool.sqrt_price)
+        || (!a_to_b && sqrt_price_limit < pool.sqrt_price)
+    {
+        return Err(QuoteError::InvalidSqrtPriceLimit);
+    }
+
+    let mut amount_remaining = amount;
+    let mut amount_out = 0u64;
+    let mut fee_amount = 0u64;
+    let mut sqrt_price = pool.sqrt_price;
+    let mut liquidity = pool.liquidity;
+    let mut tick_index = pool.tick_current_index;
+    let mut array_index = 0;
+
+    while amount_remaining > 0 && sqrt_price != sqrt_price_limit {
+        let (next_array_index, next_tick_index) =
+            next_initialized_tick(pool, tick_arrays, tick_index, a_to_b, array_index)?;
+        array_index = next_array_index;
+
+        let next_tick_sqrt_price = pool.program.sqrt_price_from_tick_index(next_tick_index)?;
+        let sqrt_price_target = if a_to_b {
+            next_tick_sqrt_price.max(sqrt_price_limit)
+        } else {
+            next_tick_sqrt_price.min(sqrt_price_limit)
+        };
+
+        let step = compute_swap_step(
+            amount_remaining,
+            pool.fee_rate,
+            liquidity,
+            sqrt_price,
+            sqrt_price_target,
+            a_to_b,
+            max_sqrt_price,
+        )?;
+        amount_remaining = step
+            .amount_in
+            .checked_add(step.fee_amount)
+            .and_then(|spent| amount_remaining.checked_sub(spent))
+            .ok_or(QuoteError::MathOverflow)?;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or(QuoteError::MathOverflow)?;
+        fee_amount = fee_amount
+            .checked_add(step.fee_amount)
+            .ok_or(QuoteError::MathOverflow)?;
+
+        if step.next_sqrt_price == next_tick_sqrt_price {
+            let tick_array = &tick_arrays[array_index];
+            if let Some(liquidity_net) = tick_array.liquidity_net(tick_offset(
+                pool,
+                tick_array.start_tick_index(),
+                next_tick_index,
+            )?) {
+                // Crossing leftwards removes the liquidity that starts at the tick
+                let liquidity_delta = if a_to_b { -liquidity_net } else { liquidity_net };
+                liquidity = add_liquidity_delta(liquidity, liquidity_delta)?;
+            }
+            tick_index = if a_to_b { next_tick_index - 1 } else { next_tick_index };
+        }
+        // Otherwise the step used up the input or reached the limit, ending the swap
+        sqrt_price = step.next_sqrt_price;
+    }
+
+    Ok(SwapQuote {
+        amount_in: amount - amount_remaining,
+        amount_out,
+        fee_amount,
+        end_sqrt_price: sqrt_price,
+    })
+}
+
+/// Square root of `1.0001^tick` as a Q64.64 number, rounded like the Whirlpool program
+pub fn sqrt_price_from_tick_index(tick: i32) -> Result<u128, QuoteError> {
+    if !(MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick) {
+        return Err(QuoteError::MathOverflow);
+    }
+    if tick >= 0 {
+        Ok(sqrt_price_positive_tick(tick))
+    } else {
+        Ok(sqrt_price_negative_tick(tick))
+    }
+}
+
+// Q32.96 values of sqrt(1.0001)^(2^i), truncated
+const POSITIVE_TICK_FACTORS: [u128; 18] = [
+    79_236_085_330_515_764_027_303_304_731,
+    79_244_008_939_048_815_603_706_035_061,
+    79_259_858_533_276_714_757_314_932_305,
+    79_291_567_232_598_584_799_939_703_904,
+    79_355_022_692_464_371_645_785_046_466,
+    79_482_085_999_252_804_386_437_311_141,
+    79_736_823_300_114_093_921_829_183_326,
+    80_248_749_790_819_932_309_965_073_892,
+    81_282_483_887_344_747_381_513_967_011,
+    83_390_072_131_320_151_908_154_831_281,
+    87_770_609_709_833_776_024_991_924_138,
+    97_234_110_755_111_693_312_479_820_773,
+    119_332_217_159_966_728_226_237_229_890,
+    179_736_315_981_702_064_433_883_588_727,
+    407_748_233_172_238_350_107_850_275_304,
+    2_098_478_828_474_011_932_436_660_412_517,
+    55_581_415_166_113_811_149_459_800_483_533,
+    38_992_368_544_603_139_932_233_054_999_993_551,
+];
+
+// Q64.64 values of 1 / sqrt(1.0001)^(2^i), truncated
+const NEGATIVE_TICK_FACTORS: [u128; 18] = [
+    18_444_899_583_751_176_498,
+    18_443_055_278_223_354_162,
+    18_439_367_220_385_604_838,
+    18_431_993_317_065_449_817,
+    18_417_254_355_718_160_513,
+    18_387_811_781_193_591_352,
+    18_329_067_761_203_520_168,
+    18_212_142_134_806_087_854,
+    17_980_523_815_641_551_639,
+    17_526_086_738_831_147_013,
+    16_651_378_430_235_024_244,
+    15_030_750_278_693_429_944,
+    12_247_334_978_882_834_399,
+    8_131_365_268_884_726_200,
+    3_584_323_654_723_342_297,
+    696_457_651_847_595_233,
+    26_294_789_957_452_057,
+    37_481_735_321_082,
+];
+
+/// Square root of `1.0001^tick` as a Q64.64 number, rounded like the Raydium CLMM program
+pub fn raydium_sqrt_price_from_tick_index(tick: i32) -> Result<u128, QuoteError> {
+    if !(MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick) {
+        return Err(QuoteError::MathOverflow);
+    }
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        RAYDIUM_TICK_FACTORS[0]
+    } else {
+        1 << 64
+    };
+    for (bit, factor) in RAYDIUM_TICK_FACTORS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+    // The factors are for negative ticks, so a positive tick takes the reciprocal
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Ok(ratio)
+}
+
+// Q64.64 values of 1 / sqrt(1.0001)^(2^i) as Raydium truncates them
+const RAYDIUM_TICK_FACTORS: [u128; 19] = [
+    0xfffc_b933_bd6f_b800,
+    0xfff9_7272_373d_4000,
+    0xfff2_e50f_5f65_7000,
+    0xffe5_caca_7e10_f000,
+    0xffcb_9843_d60f_7000,
+    0xff97_3b41_fa98_e800,
+    0xff2e_a164_66c9_b000,
+    0xfe5d_ee04_6a9a_3800,
+    0xfcbe_86c7_900b_b000,
+    0xf987_a725_3ac6_5800,
+    0xf339_2b08_22bb_6000,
+    0xe715_9475_a2ca_f000,
+    0xd097_f3bd_fd2f_2000,
+    0xa9f7_4646_2d9f_8000,
+    0x70d8_69a1_56f3_1c00,
+    0x31be_135f_97ed_3200,
+    0x09aa_508b_5b85_a500,
+    0x005d_6af8_dedc_582c,
+    0x0000_2216_e584_f5fa,
+];
+
+fn sqrt_price_positive_tick(tick: i32) -> u128 {
+    let mut ratio: u128 = if tick & 1 != 0 {
+        79_232_123_823_359_799_118_286_999_567
+    } else {
+        1 << 96
+    };
+    for (bit, factor) in POSITIVE_TICK_FACTORS.iter().enumerate() {
+        if tick & (2 << bit) != 0 {
+            ratio = (U256::mul(ratio, *factor) >> 96).lo;
+        }
+    }
+    ratio >> 32
+}
+
+fn sqrt_price_negative_tick(tick: i32) -> u128 {
+    let abs_tick = tick.abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        18_445_821_805_675_392_311
+    } else {
+        1 << 64
+    };
+    for (bit, factor) in NEGATIVE_TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (2 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+    ratio
+}
+
+/// One step of a swap, within a single price range
+struct SwapStep {
+    amount_in: u64,
+    amount_out: u64,
+    next_sqrt_price: u128,
+    fee_amount: u64,
+}
+
+fn compute_swap_step(
+    amount_remaining: u64,
+    fee_rate: u32,
+    liquidity: u128,
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    a_to_b: bool,
+    max_sqrt_price: u128,
+) -> Result<SwapStep, QuoteError> {
+    let fee_rate = fee_rate as u128;
+    let fee_complement = FEE_RATE_DENOMINATOR
+        .checked_sub(fee_rate)
+        .ok_or(QuoteError::MathOverflow)?;
+
+    // Input needed to reach the target, which may exceed what a u64 can hold
+    let input_to_target = input_delta(sqrt_price_current, sqrt_price_target, liquidity, a_to_b)?;
+    let amount_after_fee =
+        math::mul_div_floor(amount_remaining as u128, fee_complement, FEE_RATE_DENOMINATOR)
+            .ok_or(QuoteError::MathOverflow)?;
+
+    let next_sqrt_price = match input_to_target {
+        Some(input) if input <= amount_after_fee => sqrt_price_target,
+        _ => next_sqrt_price(
+            sqrt_price_current,
+            liquidity,
+            amount_after_fee as u64,
+            a_to_b,
+            max_sqrt_price,
+        )?,
+    };
+    let is_max_swap = next_sqrt_price == sqrt_price_target;
+
+    let amount_out = output_delta(sqrt_price_current, next_sqrt_price, liquidity, a_to_b)?;
+    let amount_in = match input_to_target {
+        Some(input) if is_max_swap => input,
+        _ => input_delta(sqrt_price_current, next_sqrt_price, liquidity, a_to_b)?
+            .ok_or(QuoteError::MathOverflow)?,
+    };
+    let amount_in = u64::try_from(amount_in).map_err(|_| QuoteError::MathOverflow)?;
+
+    let fee_amount = if is_max_swap {
+        let fee = math::mul_div_ceil(amount_in as u128, fee_rate, fee_complement)
+            .ok_or(QuoteError::MathOverflow)?;
+        u64::try_from(fee).map_err(|_| QuoteError::MathOverflow)?
+    } else {
+        // The step consumed all the input, so whatever did not go in is the fee
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(QuoteError::MathOverflow)?
+    };
+
+    Ok(SwapStep {
+        amount_in,
+        amount_out,
+        next_sqrt_price,
+        fee_amount,
+    })
+}
+
+/// Input token amount between two prices, rounded up. `None` when it exceeds a u64.
+fn input_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    a_to_b: bool,
+) -> Result<Option<u128>, QuoteError> {
+    let delta = if a_to_b {
+        amount_delta_a(sqrt_price_0, sqrt_price_1, liquidity, true)?
+    } else {
+        amount_delta_b(sqrt_price_0, sqrt_price_1, liquidity, true)?
+    };
+    Ok(delta.filter(|delta| *delta <= u64::MAX as u128))
+}
+
+/// Output token amount between two prices, rounded down
+fn output_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    a_to_b: bool,
+) -> Result<u64, QuoteError> {
+    let delta = if a_to_b {
+        amount_delta_b(sqrt_price_0, sqrt_price_1, liquidity, false)?
+    } else {
+        amount_delta_a(sqrt_price_0, sqrt_price_1, liquidity, false)?
+    };
+    delta
+        .and_then(|delta| u64::try_from(delta).ok())
+        .ok_or(QuoteError::MathOverflow)
+}
+
+/// `L * (sqrt(P_upper) - sqrt(P_lower)) / (sqrt(P_upper) * sqrt(P_lower))`
+fn amount_delta_a(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<Option<u128>, QuoteError> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    let numerator = U256::mul(liquidity, upper - lower)
+        .checked_shl(64)
+        .ok_or(QuoteError::MathOverflow)?;
+    let denominator = U256::mul(lower, upper);
+    let (quotient, remainder) = numerator
+        .div_rem(denominator)
+        .ok_or(QuoteError::MathOverflow)?;
+
+    let quotient = if round_up && !remainder.is_zero() {
+        quotient.checked_add(U256::from_u128(1))
+    } else {
+        Some(quotient)
+    };
+    Ok(quotient.and_then(U256::to_u128))
+}
+
+/// `L * (sqrt(P_upper) - sqrt(P_lower))`
+fn amount_delta_b(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<Option<u128>, QuoteError> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    let product = U256::mul(liquidity, upper - lower);
+    let has_fraction = product.lo & (u64::MAX as u128) != 0;
+    let delta = (product >> 64).to_u128();
+    Ok(match delta {
+        Some(delta) if round_up && has_fraction => delta.checked_add(1),
+        delta => delta,
+    })
+}
+
+/// Price after adding `amount` of input at the current liquidity
+fn next_sqrt_price(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    a_to_b: bool,
+    max_sqrt_price: u128,
+) -> Result<u128, QuoteError> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let next = if a_to_b {
+        // sqrt(P') = L * sqrt(P) / (L + amount * sqrt(P)), rounded up
+        let product = U256::mul(sqrt_price, amount as u128);
+        let numerator = U256::mul(liquidity, sqrt_price)
+            .checked_shl(64)
+            .ok_or(QuoteError::MathOverflow)?;
+        let denominator = (U256::from_u128(liquidity) << 64)
+            .checked_add(product)
+            .ok_or(QuoteError::MathOverflow)?;
+        let (quotient, remainder) = numerator
+            .div_rem(denominator)
+            .ok_or(QuoteError::MathOverflow)?;
+        let quotient = if remainder.is_zero() {
+            Some(quotient)
+        } else {
+            quotient.checked_add(U256::from_u128(1))
+        };
+        quotient.and_then(U256::to_u128)
+    } else {
+        // sqrt(P') = sqrt(P) + amount / L, rounded down
+        let delta = ((amount as u128) << 64)
+            .checked_div(liquidity)
+            .ok_or(QuoteError::MathOverflow)?;
+        sqrt_price.checked_add(delta)
+    }
+    .ok_or(QuoteError::MathOverflow)?;
+
+    if !(MIN_SQRT_PRICE_X64..=max_sqrt_price).contains(&next) {
+        return Err(QuoteError::MathOverflow);
+    }
+    Ok(next)
+}
+
+/// Finds the next initialized tick in the swap direction, or the edge of the last tick
+/// array when none is left
+fn next_initialized_tick<T: TickArrayView>(
+    pool: &ClmmPool,
+    tick_arrays: &[T],
+    tick_index: i32,
+    a_to_b: bool,
+    start_array_index: usize,
+) -> Result<(usize, i32), QuoteError> {
+    let tick_spacing = pool.tick_spacing as i32;
+    let ticks_in_array = pool.ticks_per_array * tick_spacing;
+    let mut search_index = tick_index;
+    let mut array_index = start_array_index;
+
+    loop {
+        let tick_array = tick_arrays
+            .get(array_index)
+            .ok_or(QuoteError::TickArraySequence)?;
+        let start = tick_array.start_tick_index();
+
+        // Moving right, the tick at the current offset has already been crossed
+        let (lower, upper) = if a_to_b {
+            (start, start + ticks_in_array)
+        } else {
+            (start - tick_spacing, start + ticks_in_array - tick_spacing)
+        };
+        // Crossing an array's first tick in the swap direction leaves it for the next one
+        let passed_array = if a_to_b {
+            search_index < lower
+        } else {
+            search_index >= upper
+        };
+        if passed_array && array_index + 1 < tick_arrays.len() {
+            array_index += 1;
+            continue;
+        }
+        // A Raydium array further on in the swap direction means the arrays between
+        // hold no initialized ticks, so the search resumes at its near edge
+        let ahead = if a_to_b {
+            search_index >= upper
+        } else {
+            search_index < lower
+        };
+        if ahead && pool.program == ClmmProgram::RaydiumClmm {
+            search_index = if a_to_b { upper - 1 } else { lower };
+        }
+        if search_index < lower || search_index >= upper {
+            return Err(QuoteError::TickArraySequence);
+        }
+
+        let mut offset = (search_index - start).div_euclid(tick_spacing);
+        if !a_to_b {
+            offset += 1;
+        }
+        while (0..pool.ticks_per_array).contains(&offset) {
+            if tick_array.liquidity_net(offset as usize).is_some() {
+                return Ok((array_index, start + offset * tick_spacing));
+            }
+            offset += if a_to_b { -1 } else { 1 };
+        }
+
+        if array_index + 1 == tick_arrays.len() {
+            let edge = if a_to_b {
+                start
+            } else {
+                start + tick_spacing * (pool.ticks_per_array - 1)
+            };
+            return Ok((array_index, edge));
+        }
+        search_index = if a_to_b {
+            start - 1
+        } else {
+            start + ticks_in_array - 1
+        };
+        array_index += 1;
+    }
+}
+
+fn tick_offset(pool: &ClmmPool, start_tick_index: i32, tick_index: i32) -> Result<usize, QuoteError> {
+    usize::try_from((tick_index - start_tick_index) / pool.tick_spacing as i32)
+        .map_err(|_| QuoteError::TickArraySequence)
+}
+
+fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128, QuoteError> {
+    if delta >= 0 {
+        liquidity.checked_add(delta as u128)
+    } else {
+        liquidity.checked_sub(delta.unsigned_abs())
+    }
+    .ok_or(QuoteError::MathOverflow)
+}
+
+fn sorted(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
@@ -0,0 +1,246 @@
+//! Integer pricing math shared by the DEX adapters
+//!
+//! Everything here is plain `u128` arithmetic with 256-bit intermediates, so quotes
+//! round exactly like the pools do. Only `core` is used, so the module can be shared
+//! with off-chain code.
+
+use core::cmp::Ordering;
+use core::ops::{Shl, Shr};
+
+/// Computes `a * b / denominator` rounded down, with a 256-bit intermediate product
+///
+/// Returns `None` when the denominator is zero or the result does not fit in a `u128`.
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let (hi, lo) = full_mul(a, b);
+    if hi == 0 {
+        return Some(lo / denominator);
+    }
+    // The quotient only fits in 128 bits when the high half is below the denominator
+    if hi >= denominator {
+        return None;
+    }
+
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1 << bit;
+        }
+    }
+    Some(quotient)
+}
+
+/// Computes `a * b / denominator` rounded up
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    let quotient = mul_div_floor(a, b, denominator)?;
+    let (hi, lo) = full_mul(quotient, denominator);
+    let (product_hi, product_lo) = full_mul(a, b);
+    if hi == product_hi && lo == product_lo {
+        Some(quotient)
+    } else {
+        quotient.checked_add(1)
+    }
+}
+
+/// Output of an exact-input swap against constant-product (x * y = k) reserves
+///
+/// The fee is `fee_numerator / fee_denominator` of the input, rounded up, and taken
+/// before the swap as Raydium and Orca do. The output is rounded down.
+pub fn constant_product_output(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<u64> {
+    let fee = mul_div_ceil(amount_in as u128, fee_numerator as u128, fee_denominator as u128)?;
+    let amount_in_after_fee = (amount_in as u128).checked_sub(fee)?;
+    if amount_in_after_fee == 0 || reserve_out == 0 {
+        return Some(0);
+    }
+
+    let amount_out = mul_div_floor(
+        reserve_out,
+        amount_in_after_fee,
+        reserve_in.checked_add(amount_in_after_fee)?,
+    )?;
+    u64::try_from(amount_out).ok()
+}
+
+/// Output of an exact-input swap at the reserves' current price, without price impact
+///
+/// Takes the fee like `constant_product_output`, so the two differ only by the swap's
+/// price impact.
+pub fn constant_product_spot_output(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<u64> {
+    let fee = mul_div_ceil(amount_in as u128, fee_numerator as u128, fee_denominator as u128)?;
+    let amount_in_after_fee = (amount_in as u128).checked_sub(fee)?;
+    let amount_out = mul_div_floor(reserve_out, amount_in_after_fee, reserve_in)?;
+    u64::try_from(amount_out).ok()
+}
+
+/// How far `amount_out` falls short of `spot_amount_out`, in basis points
+pub fn price_impact_bps(spot_amount_out: u64, amount_out: u64) -> u16 {
+    if spot_amount_out == 0 {
+        return 0;
+    }
+    let shortfall = spot_amount_out.saturating_sub(amount_out) as u128;
+    (shortfall * 10_000 / spot_amount_out as u128) as u16
+}
+
+/// Multiplies two `u128`s into a 256-bit `(high, low)` pair
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (cross << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+    (hi, lo)
+}
+
+/// Unsigned 256-bit integer with just the operations the quote math needs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct U256 {
+    pub hi: u128,
+    pub lo: u128,
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256 { hi: 0, lo: 0 };
+
+    pub fn from_u128(value: u128) -> Self {
+        U256 { hi: 0, lo: value }
+    }
+
+    /// Full product of two `u128`s
+    pub fn mul(a: u128, b: u128) -> Self {
+        let (hi, lo) = full_mul(a, b);
+        U256 { hi, lo }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    /// Narrows to a `u128`, or `None` when the value does not fit
+    pub fn to_u128(self) -> Option<u128> {
+        if self.hi == 0 {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+
+    pub fn checked_add(self, other: U256) -> Option<U256> {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        let hi = self.hi.checked_add(other.hi)?.checked_add(carry as u128)?;
+        Some(U256 { hi, lo })
+    }
+
+    pub fn checked_sub(self, other: U256) -> Option<U256> {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        let hi = self.hi.checked_sub(other.hi)?.checked_sub(borrow as u128)?;
+        Some(U256 { hi, lo })
+    }
+
+    fn wrapping_sub(self, other: U256) -> U256 {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        let hi = self.hi.wrapping_sub(other.hi).wrapping_sub(borrow as u128);
+        U256 { hi, lo }
+    }
+
+    /// Shifts left by `bits` (< 256), or `None` if set bits would be shifted out
+    pub fn checked_shl(self, bits: u32) -> Option<U256> {
+        let shifted = self << bits;
+        if shifted >> bits == self {
+            Some(shifted)
+        } else {
+            None
+        }
+    }
+
+    /// Quotient and remainder, or `None` when dividing by zero
+    pub fn div_rem(self, divisor: U256) -> Option<(U256, U256)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        if self < divisor {
+            return Some((U256::ZERO, self));
+        }
+
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for bit in (0..256).rev() {
+            let carry = remainder.hi >> 127;
+            remainder = remainder << 1;
+            remainder.lo |= (self >> bit).lo & 1;
+            if carry == 1 || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient = quotient.checked_add(U256::from_u128(1) << bit)?;
+            }
+        }
+        Some((quotient, remainder))
+    }
+}
+
+impl Shl<u32> for U256 {
+    type Output = U256;
+
+    fn shl(self, bits: u32) -> U256 {
+        match bits {
+            0 => self,
+            1..=127 => U256 {
+                hi: (self.hi << bits) | (self.lo >> (128 - bits)),
+                lo: self.lo << bits,
+            },
+            128..=255 => U256 { hi: self.lo << (bits - 128), lo: 0 },
+            _ => U256::ZERO,
+        }
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = U256;
+
+    fn shr(self, bits: u32) -> U256 {
+        match bits {
+            0 => self,
+            1..=127 => U256 {
+                hi: self.hi >> bits,
+                lo: (self.lo >> bits) | (self.hi << (128 - bits)),
+            },
+            128..=255 => U256 { hi: 0, lo: self.hi >> (bits - 128) },
+            _ => U256::ZERO,
+        }
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hi.cmp(&other.hi).then(self.lo.cmp(&other.lo))
+    }
+}
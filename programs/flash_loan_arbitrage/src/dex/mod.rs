@@ -0,0 +1,497 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{Account as TokenAccountState, Mint};
+
+use crate::errors::FlashLoanArbitrageError;
+
+pub mod clmm;
+pub mod math;
+pub mod raydium;
+pub mod raydium_clmm;
+pub mod split;
+pub mod whirlpool;
+
+pub use raydium::{RaydiumAmmV4, RaydiumCpmm};
+pub use raydium_clmm::RaydiumClmm;
+pub use whirlpool::Whirlpool;
+
+// Offsets inside an SPL token account, which Token-2022 accounts share ahead of their
+// extensions
+const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_MIN_LEN: usize = 72;
+
+/// DEX swap instructions the program can build for a leg
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DexKind {
+    /// Orca Whirlpool `swap`
+    Whirlpool,
+    /// Orca Whirlpool `swap_v2`, which also supports Token-2022 mints
+    WhirlpoolV2,
+    /// Raydium AMM v4 `swap_base_in`
+    RaydiumAmmV4,
+    /// Raydium CPMM `swap_base_input`
+    RaydiumCpmm,
+    /// Raydium CLMM `swap_v2`
+    RaydiumClmm,
+}
+
+impl DexKind {
+    /// Returns the adapter that knows this DEX's accounts, encoding and pricing
+    pub fn adapter(&self) -> &'static dyn DexAdapter {
+        match self {
+            DexKind::Whirlpool => &Whirlpool { use_swap_v2: false },
+            DexKind::WhirlpoolV2 => &Whirlpool { use_swap_v2: true },
+            DexKind::RaydiumAmmV4 => &RaydiumAmmV4,
+            DexKind::RaydiumCpmm => &RaydiumCpmm,
+            DexKind::RaydiumClmm => &RaydiumClmm,
+        }
+    }
+}
+
+/// Per-leg swap arguments passed with the instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapLegParams {
+    /// The swap instruction to build for this leg
+    pub dex: DexKind,
+    /// How many accounts this leg takes from `remaining_accounts`
+    pub remaining_accounts_len: u8,
+    /// Price limit for concentrated liquidity pools, 0 to use the widest limit
+    pub sqrt_price_limit: u128,
+}
+
+/// One leg of a multi-hop route passed with `flash_loan_route`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RouteLeg {
+    /// The pools the leg's input is split across, all swapping the same pair
+    pub splits: Vec<PoolSplit>,
+    /// The least output the leg may deliver across all its pools
+    pub min_out_amount: u64,
+}
+
+/// One pool a route leg sends part of its input through
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PoolSplit {
+    /// The swap instruction to build for this pool
+    pub dex: DexKind,
+    /// Index of the pool's first account in `remaining_accounts`
+    pub accounts_start: u8,
+    /// How many accounts the pool takes, its eight leading accounts included
+    pub accounts_len: u8,
+    /// Share of the leg's input in basis points; a leg's weights add up to 10000
+    pub weight_bps: u16,
+    /// The least output this pool may deliver
+    pub min_out_amount: u64,
+    /// Price limit for concentrated liquidity pools, 0 to use the widest limit
+    pub sqrt_price_limit: u128,
+}
+
+// Leading accounts of a route pool; the DEX-specific accounts follow them
+const ROUTE_LEG_DEX_PROGRAM: usize = 0;
+const ROUTE_LEG_POOL: usize = 1;
+const ROUTE_LEG_POOL_AUTHORITY: usize = 2;
+const ROUTE_LEG_INPUT_TOKEN_ACCOUNT: usize = 3;
+const ROUTE_LEG_OUTPUT_TOKEN_ACCOUNT: usize = 4;
+const ROUTE_LEG_POOL_TOKEN_A_ACCOUNT: usize = 5;
+const ROUTE_LEG_POOL_TOKEN_B_ACCOUNT: usize = 6;
+pub const ROUTE_LEG_POOL_ENTRY: usize = 7;
+pub const ROUTE_LEG_FIXED_ACCOUNTS_LEN: usize = 8;
+
+/// The accounts of one swap leg
+///
+/// Token accounts are read straight from their data so a leg can be assembled from
+/// either the typed accounts or `remaining_accounts`. Anything specific to one DEX
+/// (tick arrays, oracles, ...) is in `remaining_accounts`.
+pub struct SwapAccounts<'a, 'info> {
+    pub dex_program: &'a AccountInfo<'info>,
+    pub pool: &'a AccountInfo<'info>,
+    pub pool_authority: &'a AccountInfo<'info>,
+    pub user_authority: &'a AccountInfo<'info>,
+    pub input_token_account: &'a AccountInfo<'info>,
+    pub output_token_account: &'a AccountInfo<'info>,
+    pub pool_token_a_account: &'a AccountInfo<'info>,
+    pub pool_token_b_account: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+/// One swap of an arbitrage route: the DEX, its direction and its accounts
+pub struct Leg<'a, 'info> {
+    pub dex: DexKind,
+    pub program_id: Pubkey,
+    /// True when the leg sells the pool's token A for token B
+    pub a_to_b: bool,
+    /// Price limit for concentrated liquidity pools, 0 to use the widest limit
+    pub sqrt_price_limit: u128,
+    pub accounts: SwapAccounts<'a, 'info>,
+}
+
+impl<'a, 'info> Leg<'a, 'info> {
+    /// Describes a leg, working out its direction from the mints
+    ///
+    /// Fails for a Token-2022 transfer fee mint on a swap that cannot account for the fee.
+    pub fn new(params: &SwapLegParams, accounts: SwapAccounts<'a, 'info>) -> Result<Self> {
+        let input_mint = token_account_mint(accounts.input_token_account)?;
+        let a_to_b = if input_mint == token_account_mint(accounts.pool_token_a_account)? {
+            true
+        } else if input_mint == token_account_mint(accounts.pool_token_b_account)? {
+            false
+        } else {
+            return err!(FlashLoanArbitrageError::TokenAccountMintMismatch);
+        };
+
+        let leg = Self {
+            dex: params.dex,
+            program_id: accounts.dex_program.key(),
+            a_to_b,
+            sqrt_price_limit: params.sqrt_price_limit,
+            accounts,
+        };
+        leg.check_transfer_fees()?;
+        Ok(leg)
+    }
+
+    /// Describes one pool of a route leg from its range of `remaining_accounts`
+    ///
+    /// The range holds `[dex_program, pool, pool_authority, input_token_account,
+    /// output_token_account, pool_token_a_account, pool_token_b_account, pool_entry]`
+    /// followed by the DEX-specific accounts. The pool's registry entry is checked by the
+    /// caller.
+    pub fn from_split(
+        split: &PoolSplit,
+        remaining_accounts: &'a [AccountInfo<'info>],
+        user_authority: &'a AccountInfo<'info>,
+        token_program: &'a AccountInfo<'info>,
+    ) -> Result<Self> {
+        let start = split.accounts_start as usize;
+        let end = start + split.accounts_len as usize;
+        require!(
+            split.accounts_len as usize >= ROUTE_LEG_FIXED_ACCOUNTS_LEN
+                && end <= remaining_accounts.len(),
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+        let accounts = &remaining_accounts[start..end];
+        require!(
+            accounts[ROUTE_LEG_DEX_PROGRAM].executable,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+
+        Self::new(
+            &SwapLegParams {
+                dex: split.dex,
+                remaining_accounts_len: split.accounts_len - ROUTE_LEG_FIXED_ACCOUNTS_LEN as u8,
+                sqrt_price_limit: split.sqrt_price_limit,
+            },
+            SwapAccounts {
+                dex_program: &accounts[ROUTE_LEG_DEX_PROGRAM],
+                pool: &accounts[ROUTE_LEG_POOL],
+                pool_authority: &accounts[ROUTE_LEG_POOL_AUTHORITY],
+                user_authority,
+                input_token_account: &accounts[ROUTE_LEG_INPUT_TOKEN_ACCOUNT],
+                output_token_account: &accounts[ROUTE_LEG_OUTPUT_TOKEN_ACCOUNT],
+                pool_token_a_account: &accounts[ROUTE_LEG_POOL_TOKEN_A_ACCOUNT],
+                pool_token_b_account: &accounts[ROUTE_LEG_POOL_TOKEN_B_ACCOUNT],
+                token_program,
+                remaining_accounts: &accounts[ROUTE_LEG_FIXED_ACCOUNTS_LEN..],
+            },
+        )
+    }
+
+    pub fn adapter(&self) -> &'static dyn DexAdapter {
+        self.dex.adapter()
+    }
+
+    /// The pool vaults ordered as (input side, output side)
+    pub fn vaults(&self) -> (&'a AccountInfo<'info>, &'a AccountInfo<'info>) {
+        if self.a_to_b {
+            (self.accounts.pool_token_a_account, self.accounts.pool_token_b_account)
+        } else {
+            (self.accounts.pool_token_b_account, self.accounts.pool_token_a_account)
+        }
+    }
+
+    /// Checks the pool account belongs to the leg's DEX program
+    pub fn check_pool_owner(&self) -> Result<()> {
+        require!(
+            *self.accounts.pool.owner == self.program_id,
+            FlashLoanArbitrageError::DexPoolOwnerMismatch
+        );
+        Ok(())
+    }
+
+    /// Estimates the output of swapping `amount_in` through this leg
+    ///
+    /// Token-2022 transfer fees are taken off the input the pool receives and off the
+    /// output the user receives.
+    pub fn quote(&self, amount_in: u64) -> Result<u64> {
+        self.quote_after_transfer_fees(amount_in, |amount_in| self.adapter().quote(self, amount_in))
+    }
+
+    /// Estimates the output of swapping `amount_in` through this leg without price impact
+    pub fn spot_quote(&self, amount_in: u64) -> Result<u64> {
+        self.quote_after_transfer_fees(amount_in, |amount_in| self.adapter().spot_quote(self, amount_in))
+    }
+
+    fn quote_after_transfer_fees(&self, amount_in: u64, quote: impl Fn(u64) -> Result<u64>) -> Result<u64> {
+        let Some((input_mint, output_mint)) = self.mints()? else {
+            return quote(amount_in);
+        };
+        let pool_amount_in = amount_in
+            .checked_sub(transfer_fee(input_mint, amount_in)?)
+            .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        let pool_amount_out = quote(pool_amount_in)?;
+        pool_amount_out
+            .checked_sub(transfer_fee(output_mint, pool_amount_out)?)
+            .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+
+    /// Rejects transfer fee mints on swaps that take no mint accounts
+    ///
+    /// Those swaps cannot read the fee, so their quotes would overstate the output.
+    /// Token-2022 accounts of a transfer fee mint carry the `TransferFeeAmount`
+    /// extension, which gives the mint away without its account.
+    fn check_transfer_fees(&self) -> Result<()> {
+        if self.adapter().pool_mints(self).is_some() {
+            return Ok(());
+        }
+        for token_account in [self.accounts.input_token_account, self.accounts.output_token_account] {
+            require!(
+                !has_transfer_fee(token_account)?,
+                FlashLoanArbitrageError::TransferFeeMintNotSupported
+            );
+        }
+        Ok(())
+    }
+
+    /// The (input, output) mint accounts, for DEXes whose swap takes them
+    fn mints(&self) -> Result<Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>> {
+        let Some((mint_a, mint_b)) = self.adapter().pool_mints(self) else {
+            return Ok(None);
+        };
+        let (input_mint, output_mint) = if self.a_to_b {
+            (mint_a, mint_b)
+        } else {
+            (mint_b, mint_a)
+        };
+        require!(
+            input_mint.key() == token_account_mint(self.accounts.input_token_account)?
+                && output_mint.key() == token_account_mint(self.accounts.output_token_account)?,
+            FlashLoanArbitrageError::TokenAccountMintMismatch
+        );
+        Ok(Some((input_mint, output_mint)))
+    }
+
+    /// Every account a swap on this leg can reference
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut infos = self.accounts.remaining_accounts.to_vec();
+        infos.extend_from_slice(&[
+            self.accounts.dex_program.clone(),
+            self.accounts.pool.clone(),
+            self.accounts.pool_authority.clone(),
+            self.accounts.user_authority.clone(),
+            self.accounts.input_token_account.clone(),
+            self.accounts.output_token_account.clone(),
+            self.accounts.pool_token_a_account.clone(),
+            self.accounts.pool_token_b_account.clone(),
+            self.accounts.token_program.clone(),
+        ]);
+        infos
+    }
+}
+
+/// A DEX the arbitrage engine can route through
+///
+/// Implementations know the DEX's account layout, swap encoding and pricing, so a new
+/// venue only needs an adapter and a `DexKind` variant.
+pub trait DexAdapter {
+    /// Checks the leg carries the accounts this DEX needs
+    fn validate_accounts(&self, leg: &Leg) -> Result<()>;
+
+    /// Estimates the output of swapping `amount_in` through the leg from the pool's
+    /// current state
+    fn quote(&self, leg: &Leg, amount_in: u64) -> Result<u64>;
+
+    /// Estimates the output of swapping `amount_in` at the pool's current price after
+    /// fees, as if the swap moved no price. The gap to `quote` is the price impact.
+    fn spot_quote(&self, leg: &Leg, amount_in: u64) -> Result<u64>;
+
+    /// Reads the pool's token A and B vaults from its state
+    fn pool_vaults(&self, pool: &AccountInfo) -> Result<(Pubkey, Pubkey)>;
+
+    /// The leg's token A and B mint accounts, when its swap takes them. Only those
+    /// swaps support Token-2022 mints, whose transfer fees quotes must then account for.
+    fn pool_mints<'a, 'info>(&self, _leg: &Leg<'a, 'info>) -> Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)> {
+        None
+    }
+
+    /// Builds the exact-input swap instruction for the leg
+    fn build_swap_ix(&self, leg: &Leg, amount_in: u64, min_out_amount: u64) -> Result<Instruction>;
+}
+
+/// Runs one leg and returns how many output tokens it delivered
+///
+/// `signer_seeds` lets a PDA user authority, such as the vault, sign the swap; it is
+/// empty when the authority signed the transaction.
+pub fn execute_leg(
+    leg: &Leg,
+    amount_in: u64,
+    min_out_amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    // Validate accounts
+    leg.check_pool_owner()?;
+    let adapter = leg.adapter();
+    adapter.validate_accounts(leg)?;
+
+    let swap_ix = adapter.build_swap_ix(leg, amount_in, min_out_amount)?;
+    let output_before = token_account_amount(leg.accounts.output_token_account)?;
+
+    invoke_signed(&swap_ix, &leg.account_infos(), signer_seeds).map_err(|e| {
+        msg!("{:?} swap failed with error: {:?}", leg.dex, e);
+        FlashLoanArbitrageError::DexSwapFailed
+    })?;
+
+    // Read from the account data, so the balance reflects the swap
+    let output_after = token_account_amount(leg.accounts.output_token_account)?;
+    Ok(output_after.saturating_sub(output_before))
+}
+
+/// Reads the mint of a token account from its data
+pub fn token_account_mint(account: &AccountInfo) -> Result<Pubkey> {
+    let data = token_account_data(account)?;
+    let mut mint = [0u8; 32];
+    mint.copy_from_slice(&data[TOKEN_ACCOUNT_MINT_OFFSET..TOKEN_ACCOUNT_MINT_OFFSET + 32]);
+    Ok(Pubkey::new_from_array(mint))
+}
+
+/// Reads the owner of a token account from its data
+pub fn token_account_owner(account: &AccountInfo) -> Result<Pubkey> {
+    let data = token_account_data(account)?;
+    let mut owner = [0u8; 32];
+    owner.copy_from_slice(&data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32]);
+    Ok(Pubkey::new_from_array(owner))
+}
+
+/// Reads the balance of a token account from its data
+pub fn token_account_amount(account: &AccountInfo) -> Result<u64> {
+    let data = token_account_data(account)?;
+    let mut amount = [0u8; 8];
+    amount.copy_from_slice(&data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8]);
+    Ok(u64::from_le_bytes(amount))
+}
+
+/// The Token-2022 transfer fee a transfer of `amount` pays, zero for mints without one
+///
+/// The fee is withheld from what the recipient receives, so the sender still sends
+/// `amount`.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    require!(
+        is_token_program(mint.owner),
+        FlashLoanArbitrageError::InvalidTokenAccount
+    );
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow)),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Whether a token account belongs to a mint with a Token-2022 transfer fee
+fn has_transfer_fee(account: &AccountInfo) -> Result<bool> {
+    if *account.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+    let data = account.try_borrow_data()?;
+    let account = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+    Ok(account.get_extension::<TransferFeeAmount>().is_ok())
+}
+
+/// Whether a mint has the Token-2022 transfer fee extension, whatever its current rate
+pub fn mint_has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().is_ok())
+}
+
+/// Whether `program_id` is the SPL Token or the Token-2022 program
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == anchor_spl::token::ID || *program_id == anchor_spl::token_2022::ID
+}
+
+/// Copies the leading fields of a token account, which hold its mint, owner and amount
+fn token_account_data(account: &AccountInfo) -> Result<[u8; TOKEN_ACCOUNT_MIN_LEN]> {
+    require!(
+        is_token_program(account.owner),
+        FlashLoanArbitrageError::InvalidTokenAccount
+    );
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= TOKEN_ACCOUNT_MIN_LEN,
+        FlashLoanArbitrageError::InvalidTokenAccount
+    );
+
+    let mut fields = [0u8; TOKEN_ACCOUNT_MIN_LEN];
+    fields.copy_from_slice(&data[..TOKEN_ACCOUNT_MIN_LEN]);
+    Ok(fields)
+}
+
+/// Reads a little-endian `u16` from pool state
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = pool_field(data, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a little-endian `i32` from pool state
+pub(crate) fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(pool_field(data, offset, 4)?);
+    Ok(i32::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian `u32` from pool state
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(pool_field(data, offset, 4)?);
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian `u64` from pool state
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(pool_field(data, offset, 8)?);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian `u128` from pool state
+pub(crate) fn read_u128(data: &[u8], offset: usize) -> Result<u128> {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(pool_field(data, offset, 16)?);
+    Ok(u128::from_le_bytes(bytes))
+}
+
+/// Reads a `Pubkey` from pool state
+pub(crate) fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(pool_field(data, offset, 32)?);
+    Ok(Pubkey::new_from_array(bytes))
+}
+
+fn pool_field(data: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    data.get(offset..offset + len)
+        .ok_or_else(|| error!(FlashLoanArbitrageError::InvalidPoolAccount))
+}
@@ -0,0 +1,486 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::dex::{math, read_pubkey, read_u64, token_account_amount, DexAdapter, Leg};
+use crate::errors::FlashLoanArbitrageError;
+
+// Raydium AMM v4 `swap_base_in` instruction tag
+pub const AMM_V4_SWAP_BASE_IN: u8 = 9;
+// Raydium CPMM `swap_base_input` discriminator
+pub const CPMM_SWAP_BASE_INPUT_DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+
+// AMM v4 `AmmInfo` layout
+const AMM_V4_SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const AMM_V4_SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const AMM_V4_NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const AMM_V4_NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const AMM_V4_COIN_VAULT_OFFSET: usize = 336;
+const AMM_V4_PC_VAULT_OFFSET: usize = 368;
+
+// CPMM `PoolState` and `AmmConfig` layouts
+pub const CPMM_POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const CPMM_AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+const CPMM_POOL_AMM_CONFIG_OFFSET: usize = 8;
+const CPMM_POOL_TOKEN_0_VAULT_OFFSET: usize = 72;
+const CPMM_POOL_TOKEN_1_VAULT_OFFSET: usize = 104;
+const CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET: usize = 341;
+const CPMM_POOL_PROTOCOL_FEES_TOKEN_1_OFFSET: usize = 349;
+const CPMM_POOL_FUND_FEES_TOKEN_0_OFFSET: usize = 357;
+const CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET: usize = 365;
+const CPMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 12;
+// CPMM fee rates are in hundredths of a basis point
+pub const CPMM_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// AMM v4 accounts expected in the leg's `remaining_accounts`. The 18-account layout
+// of the swap adds `amm_target_orders` right after `amm_open_orders`.
+const AMM_V4_REMAINING_ACCOUNTS_LEN: usize = 9;
+const AMM_V4_WITH_TARGET_ORDERS_REMAINING_ACCOUNTS_LEN: usize = 10;
+
+// CPMM accounts expected in the leg's `remaining_accounts`
+const CPMM_AMM_CONFIG: usize = 0;
+const CPMM_OBSERVATION_STATE: usize = 1;
+const CPMM_TOKEN_0_MINT: usize = 2;
+const CPMM_TOKEN_1_MINT: usize = 3;
+const CPMM_TOKEN_0_PROGRAM: usize = 4;
+const CPMM_TOKEN_1_PROGRAM: usize = 5;
+const CPMM_REMAINING_ACCOUNTS_LEN: usize = 6;
+
+/// The OpenBook market accounts an AMM v4 swap settles against
+pub struct AmmV4MarketKeys {
+    pub amm_open_orders: Pubkey,
+    pub amm_target_orders: Option<Pubkey>,
+    pub serum_program: Pubkey,
+    pub serum_market: Pubkey,
+    pub serum_bids: Pubkey,
+    pub serum_asks: Pubkey,
+    pub serum_event_queue: Pubkey,
+    pub serum_coin_vault: Pubkey,
+    pub serum_pc_vault: Pubkey,
+    pub serum_vault_signer: Pubkey,
+}
+
+/// Builds a Raydium AMM v4 `swap_base_in` instruction
+///
+/// Produces the 18-account layout when `amm_target_orders` is set and the 17-account
+/// layout otherwise.
+#[allow(clippy::too_many_arguments)]
+pub fn amm_v4_swap_base_in(
+    program_id: Pubkey,
+    token_program: Pubkey,
+    amm: Pubkey,
+    amm_authority: Pubkey,
+    pool_coin_token_account: Pubkey,
+    pool_pc_token_account: Pubkey,
+    market: &AmmV4MarketKeys,
+    user_source_token_account: Pubkey,
+    user_destination_token_account: Pubkey,
+    user_source_owner: Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(AMM_V4_SWAP_BASE_IN);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new(amm, false),
+        AccountMeta::new_readonly(amm_authority, false),
+        AccountMeta::new(market.amm_open_orders, false),
+    ];
+    if let Some(amm_target_orders) = market.amm_target_orders {
+        accounts.push(AccountMeta::new(amm_target_orders, false));
+    }
+    accounts.extend_from_slice(&[
+        AccountMeta::new(pool_coin_token_account, false),
+        AccountMeta::new(pool_pc_token_account, false),
+        AccountMeta::new_readonly(market.serum_program, false),
+        AccountMeta::new(market.serum_market, false),
+        AccountMeta::new(market.serum_bids, false),
+        AccountMeta::new(market.serum_asks, false),
+        AccountMeta::new(market.serum_event_queue, false),
+        AccountMeta::new(market.serum_coin_vault, false),
+        AccountMeta::new(market.serum_pc_vault, false),
+        AccountMeta::new_readonly(market.serum_vault_signer, false),
+        AccountMeta::new(user_source_token_account, false),
+        AccountMeta::new(user_destination_token_account, false),
+        AccountMeta::new_readonly(user_source_owner, true),
+    ]);
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+/// The pricing state of a Raydium AMM v4 pool
+pub struct AmmV4State {
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+}
+
+impl AmmV4State {
+    /// Reads the pricing state from an `AmmInfo` account
+    pub fn load(amm: &AccountInfo) -> Result<Self> {
+        let data = amm.try_borrow_data()?;
+        Ok(Self {
+            swap_fee_numerator: read_u64(&data, AMM_V4_SWAP_FEE_NUMERATOR_OFFSET)?,
+            swap_fee_denominator: read_u64(&data, AMM_V4_SWAP_FEE_DENOMINATOR_OFFSET)?,
+            need_take_pnl_coin: read_u64(&data, AMM_V4_NEED_TAKE_PNL_COIN_OFFSET)?,
+            need_take_pnl_pc: read_u64(&data, AMM_V4_NEED_TAKE_PNL_PC_OFFSET)?,
+            coin_vault: read_pubkey(&data, AMM_V4_COIN_VAULT_OFFSET)?,
+            pc_vault: read_pubkey(&data, AMM_V4_PC_VAULT_OFFSET)?,
+        })
+    }
+}
+
+/// Constant-product reserves of a pool, ordered by the swap direction
+struct PoolReserves {
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+}
+
+impl PoolReserves {
+    fn new(a_to_b: bool, reserve_a: u64, reserve_b: u64, fee_numerator: u64, fee_denominator: u64) -> Self {
+        let (reserve_in, reserve_out) = if a_to_b {
+            (reserve_a, reserve_b)
+        } else {
+            (reserve_b, reserve_a)
+        };
+        PoolReserves {
+            reserve_in: reserve_in as u128,
+            reserve_out: reserve_out as u128,
+            fee_numerator,
+            fee_denominator,
+        }
+    }
+
+    fn output(&self, amount_in: u64) -> Result<u64> {
+        math::constant_product_output(
+            amount_in,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee_numerator,
+            self.fee_denominator,
+        )
+        .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+
+    fn spot_output(&self, amount_in: u64) -> Result<u64> {
+        math::constant_product_spot_output(
+            amount_in,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee_numerator,
+            self.fee_denominator,
+        )
+        .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+}
+
+/// Raydium AMM v4 adapter
+///
+/// Leg remaining accounts: `[amm_open_orders, (amm_target_orders,) serum_program,
+/// serum_market, serum_bids, serum_asks, serum_event_queue, serum_coin_vault,
+/// serum_pc_vault, serum_vault_signer]`. The pool's token A and B accounts are its coin
+/// and pc vaults, and `pool_authority` is the AMM authority.
+pub struct RaydiumAmmV4;
+
+impl RaydiumAmmV4 {
+    /// Reads the vault balances, less the PnL the AMM has yet to take. Liquidity the
+    /// AMM keeps on the OpenBook market is not counted.
+    fn reserves(&self, leg: &Leg) -> Result<PoolReserves> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = AmmV4State::load(leg.accounts.pool)?;
+
+        let coin_reserve = token_account_amount(leg.accounts.pool_token_a_account)?
+            .saturating_sub(state.need_take_pnl_coin);
+        let pc_reserve = token_account_amount(leg.accounts.pool_token_b_account)?
+            .saturating_sub(state.need_take_pnl_pc);
+
+        Ok(PoolReserves::new(
+            leg.a_to_b,
+            coin_reserve,
+            pc_reserve,
+            state.swap_fee_numerator,
+            state.swap_fee_denominator,
+        ))
+    }
+}
+
+impl DexAdapter for RaydiumAmmV4 {
+    fn validate_accounts(&self, leg: &Leg) -> Result<()> {
+        require!(
+            matches!(
+                leg.accounts.remaining_accounts.len(),
+                AMM_V4_REMAINING_ACCOUNTS_LEN | AMM_V4_WITH_TARGET_ORDERS_REMAINING_ACCOUNTS_LEN
+            ),
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+
+        let state = AmmV4State::load(leg.accounts.pool)?;
+        require!(
+            state.coin_vault == leg.accounts.pool_token_a_account.key()
+                && state.pc_vault == leg.accounts.pool_token_b_account.key(),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+        Ok(())
+    }
+
+    fn quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        self.reserves(leg)?.output(amount_in)
+    }
+
+    fn spot_quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        self.reserves(leg)?.spot_output(amount_in)
+    }
+
+    fn pool_vaults(&self, pool: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+        let state = AmmV4State::load(pool)?;
+        Ok((state.coin_vault, state.pc_vault))
+    }
+
+    fn build_swap_ix(&self, leg: &Leg, amount_in: u64, min_out_amount: u64) -> Result<Instruction> {
+        // The AMM infers the direction from the source account
+        let accounts = &leg.accounts;
+        let remaining = accounts.remaining_accounts;
+        let with_target_orders = remaining.len() == AMM_V4_WITH_TARGET_ORDERS_REMAINING_ACCOUNTS_LEN;
+
+        let keys: Vec<Pubkey> = remaining.iter().map(|account| account.key()).collect();
+        let (amm_open_orders, amm_target_orders, market_keys) = if with_target_orders {
+            (keys[0], Some(keys[1]), &keys[2..])
+        } else {
+            (keys[0], None, &keys[1..])
+        };
+        let market = AmmV4MarketKeys {
+            amm_open_orders,
+            amm_target_orders,
+            serum_program: market_keys[0],
+            serum_market: market_keys[1],
+            serum_bids: market_keys[2],
+            serum_asks: market_keys[3],
+            serum_event_queue: market_keys[4],
+            serum_coin_vault: market_keys[5],
+            serum_pc_vault: market_keys[6],
+            serum_vault_signer: market_keys[7],
+        };
+
+        Ok(amm_v4_swap_base_in(
+            leg.program_id,
+            accounts.token_program.key(),
+            accounts.pool.key(),
+            accounts.pool_authority.key(),
+            accounts.pool_token_a_account.key(),
+            accounts.pool_token_b_account.key(),
+            &market,
+            accounts.input_token_account.key(),
+            accounts.output_token_account.key(),
+            accounts.user_authority.key(),
+            amount_in,
+            min_out_amount,
+        ))
+    }
+}
+
+/// The accounts of a Raydium CPMM swap, ordered by swap direction
+pub struct CpmmSwapKeys {
+    pub payer: Pubkey,
+    pub authority: Pubkey,
+    pub amm_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub input_vault: Pubkey,
+    pub output_vault: Pubkey,
+    pub input_token_program: Pubkey,
+    pub output_token_program: Pubkey,
+    pub input_token_mint: Pubkey,
+    pub output_token_mint: Pubkey,
+    pub observation_state: Pubkey,
+}
+
+/// Builds a Raydium CPMM `swap_base_input` instruction
+pub fn cpmm_swap_base_input(
+    program_id: Pubkey,
+    keys: &CpmmSwapKeys,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(&CPMM_SWAP_BASE_INPUT_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(keys.payer, true),
+            AccountMeta::new_readonly(keys.authority, false),
+            AccountMeta::new_readonly(keys.amm_config, false),
+            AccountMeta::new(keys.pool_state, false),
+            AccountMeta::new(keys.input_token_account, false),
+            AccountMeta::new(keys.output_token_account, false),
+            AccountMeta::new(keys.input_vault, false),
+            AccountMeta::new(keys.output_vault, false),
+            AccountMeta::new_readonly(keys.input_token_program, false),
+            AccountMeta::new_readonly(keys.output_token_program, false),
+            AccountMeta::new_readonly(keys.input_token_mint, false),
+            AccountMeta::new_readonly(keys.output_token_mint, false),
+            AccountMeta::new(keys.observation_state, false),
+        ],
+        data,
+    }
+}
+
+/// The pricing state of a Raydium CPMM pool
+pub struct CpmmState {
+    pub amm_config: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    /// Protocol and fund fees held in the token 0 vault that are not pool liquidity
+    pub token_0_fees: u64,
+    /// Protocol and fund fees held in the token 1 vault that are not pool liquidity
+    pub token_1_fees: u64,
+}
+
+impl CpmmState {
+    /// Reads the pricing state from a `PoolState` account
+    pub fn load(pool_state: &AccountInfo) -> Result<Self> {
+        let data = pool_state.try_borrow_data()?;
+        require!(
+            data.get(..8) == Some(&CPMM_POOL_STATE_DISCRIMINATOR[..]),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+
+        let fees = |protocol_offset, fund_offset| -> Result<u64> {
+            Ok(read_u64(&data, protocol_offset)?.saturating_add(read_u64(&data, fund_offset)?))
+        };
+        Ok(Self {
+            amm_config: read_pubkey(&data, CPMM_POOL_AMM_CONFIG_OFFSET)?,
+            token_0_vault: read_pubkey(&data, CPMM_POOL_TOKEN_0_VAULT_OFFSET)?,
+            token_1_vault: read_pubkey(&data, CPMM_POOL_TOKEN_1_VAULT_OFFSET)?,
+            token_0_fees: fees(
+                CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET,
+                CPMM_POOL_FUND_FEES_TOKEN_0_OFFSET,
+            )?,
+            token_1_fees: fees(
+                CPMM_POOL_PROTOCOL_FEES_TOKEN_1_OFFSET,
+                CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET,
+            )?,
+        })
+    }
+}
+
+/// Reads the trade fee rate from a CPMM `AmmConfig` account
+pub fn cpmm_trade_fee_rate(amm_config: &AccountInfo) -> Result<u64> {
+    let data = amm_config.try_borrow_data()?;
+    require!(
+        data.get(..8) == Some(&CPMM_AMM_CONFIG_DISCRIMINATOR[..]),
+        FlashLoanArbitrageError::InvalidPoolAccount
+    );
+    read_u64(&data, CPMM_CONFIG_TRADE_FEE_RATE_OFFSET)
+}
+
+/// Raydium CPMM adapter
+///
+/// Leg remaining accounts: `[amm_config, observation_state, token_0_mint, token_1_mint,
+/// token_0_program, token_1_program]`. The pool's token A and B accounts are its token 0
+/// and token 1 vaults, and `pool_authority` is the CPMM vault authority.
+pub struct RaydiumCpmm;
+
+impl RaydiumCpmm {
+    /// Reads the vault balances, less the protocol and fund fees they hold, with the
+    /// trade fee of the pool's config
+    fn reserves(&self, leg: &Leg) -> Result<PoolReserves> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = CpmmState::load(leg.accounts.pool)?;
+        let trade_fee_rate = cpmm_trade_fee_rate(&leg.accounts.remaining_accounts[CPMM_AMM_CONFIG])?;
+
+        let token_0_reserve = token_account_amount(leg.accounts.pool_token_a_account)?
+            .saturating_sub(state.token_0_fees);
+        let token_1_reserve = token_account_amount(leg.accounts.pool_token_b_account)?
+            .saturating_sub(state.token_1_fees);
+
+        Ok(PoolReserves::new(
+            leg.a_to_b,
+            token_0_reserve,
+            token_1_reserve,
+            trade_fee_rate,
+            CPMM_FEE_RATE_DENOMINATOR,
+        ))
+    }
+}
+
+impl DexAdapter for RaydiumCpmm {
+    fn validate_accounts(&self, leg: &Leg) -> Result<()> {
+        require!(
+            leg.accounts.remaining_accounts.len() == CPMM_REMAINING_ACCOUNTS_LEN,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+
+        let state = CpmmState::load(leg.accounts.pool)?;
+        require!(
+            state.token_0_vault == leg.accounts.pool_token_a_account.key()
+                && state.token_1_vault == leg.accounts.pool_token_b_account.key()
+                && state.amm_config == leg.accounts.remaining_accounts[CPMM_AMM_CONFIG].key(),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+        Ok(())
+    }
+
+    fn quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        self.reserves(leg)?.output(amount_in)
+    }
+
+    fn spot_quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        self.reserves(leg)?.spot_output(amount_in)
+    }
+
+    fn pool_vaults(&self, pool: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+        let state = CpmmState::load(pool)?;
+        Ok((state.token_0_vault, state.token_1_vault))
+    }
+
+    fn pool_mints<'a, 'info>(&self, leg: &Leg<'a, 'info>) -> Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)> {
+        let remaining = leg.accounts.remaining_accounts;
+        Some((remaining.get(CPMM_TOKEN_0_MINT)?, remaining.get(CPMM_TOKEN_1_MINT)?))
+    }
+
+    fn build_swap_ix(&self, leg: &Leg, amount_in: u64, min_out_amount: u64) -> Result<Instruction> {
+        let accounts = &leg.accounts;
+        let remaining = accounts.remaining_accounts;
+        let (input_vault, output_vault) = leg.vaults();
+        let (input_side, output_side) = if leg.a_to_b {
+            ((CPMM_TOKEN_0_MINT, CPMM_TOKEN_0_PROGRAM), (CPMM_TOKEN_1_MINT, CPMM_TOKEN_1_PROGRAM))
+        } else {
+            ((CPMM_TOKEN_1_MINT, CPMM_TOKEN_1_PROGRAM), (CPMM_TOKEN_0_MINT, CPMM_TOKEN_0_PROGRAM))
+        };
+
+        let keys = CpmmSwapKeys {
+            payer: accounts.user_authority.key(),
+            authority: accounts.pool_authority.key(),
+            amm_config: remaining[CPMM_AMM_CONFIG].key(),
+            pool_state: accounts.pool.key(),
+            input_token_account: accounts.input_token_account.key(),
+            output_token_account: accounts.output_token_account.key(),
+            input_vault: input_vault.key(),
+            output_vault: output_vault.key(),
+            input_token_program: remaining[input_side.1].key(),
+            output_token_program: remaining[output_side.1].key(),
+            input_token_mint: remaining[input_side.0].key(),
+            output_token_mint: remaining[output_side.0].key(),
+            observation_state: remaining[CPMM_OBSERVATION_STATE].key(),
+        };
+        Ok(cpmm_swap_base_input(leg.program_id, &keys, amount_in, min_out_amount))
+    }
+}
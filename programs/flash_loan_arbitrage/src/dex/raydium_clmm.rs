@@ -0,0 +1,350 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::dex::clmm::{self, ClmmPool, ClmmProgram, QuoteError, SwapQuote, TickArrayView};
+use crate::dex::{read_i32, read_pubkey, read_u128, read_u16, read_u32, DexAdapter, Leg};
+use crate::errors::FlashLoanArbitrageError;
+
+// Raydium CLMM `swap_v2` discriminator
+pub const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+// `PoolState` layout
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+const POOL_AMM_CONFIG_OFFSET: usize = 9;
+const POOL_TOKEN_MINT_0_OFFSET: usize = 73;
+const POOL_TOKEN_MINT_1_OFFSET: usize = 105;
+const POOL_TOKEN_VAULT_0_OFFSET: usize = 137;
+const POOL_TOKEN_VAULT_1_OFFSET: usize = 169;
+const POOL_OBSERVATION_KEY_OFFSET: usize = 201;
+const POOL_TICK_SPACING_OFFSET: usize = 235;
+const POOL_LIQUIDITY_OFFSET: usize = 237;
+const POOL_SQRT_PRICE_OFFSET: usize = 253;
+const POOL_TICK_CURRENT_OFFSET: usize = 269;
+const POOL_STATUS_OFFSET: usize = 389;
+// Bit of `status` that disables swaps
+const POOL_STATUS_SWAP_DISABLED: u8 = 1 << 4;
+
+// `AmmConfig` layout
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 47;
+
+// `TickArrayState` layout
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+pub const TICK_ARRAY_SIZE: usize = 60;
+const TICK_ARRAY_POOL_OFFSET: usize = 8;
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 40;
+const TICK_ARRAY_TICKS_OFFSET: usize = 44;
+const TICK_LEN: usize = 168;
+const TICK_LIQUIDITY_NET_OFFSET: usize = 4;
+const TICK_LIQUIDITY_GROSS_OFFSET: usize = 20;
+
+// `TickArrayBitmapExtension`, which swaps reaching far from the current price take
+// ahead of their tick arrays
+pub const TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR: [u8; 8] = [60, 150, 36, 219, 97, 128, 139, 153];
+
+// Raydium CLMM accounts expected in the leg's `remaining_accounts`
+const AMM_CONFIG: usize = 0;
+const OBSERVATION_STATE: usize = 1;
+const TOKEN_MINT_0: usize = 2;
+const TOKEN_MINT_1: usize = 3;
+const TOKEN_PROGRAM: usize = 4;
+const TOKEN_PROGRAM_2022: usize = 5;
+const MEMO_PROGRAM: usize = 6;
+const TICK_ARRAYS: usize = 7;
+const REMAINING_ACCOUNTS_MIN_LEN: usize = 8;
+
+/// The accounts of a Raydium CLMM swap, ordered by swap direction
+pub struct RaydiumClmmSwapKeys {
+    pub payer: Pubkey,
+    pub amm_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub input_vault: Pubkey,
+    pub output_vault: Pubkey,
+    pub observation_state: Pubkey,
+    pub token_program: Pubkey,
+    pub token_program_2022: Pubkey,
+    pub memo_program: Pubkey,
+    pub input_vault_mint: Pubkey,
+    pub output_vault_mint: Pubkey,
+    /// The optional bitmap extension, then the tick arrays in swap order
+    pub tick_arrays: Vec<Pubkey>,
+}
+
+/// Builds a Raydium CLMM exact-input `swap_v2` instruction
+pub fn swap_v2(
+    program_id: Pubkey,
+    keys: &RaydiumClmmSwapKeys,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+) -> Instruction {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&SWAP_V2_DISCRIMINATOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&other_amount_threshold.to_le_bytes());
+    data.extend_from_slice(&sqrt_price_limit_x64.to_le_bytes());
+    // is_base_input
+    data.push(1);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(keys.payer, true),
+        AccountMeta::new_readonly(keys.amm_config, false),
+        AccountMeta::new(keys.pool_state, false),
+        AccountMeta::new(keys.input_token_account, false),
+        AccountMeta::new(keys.output_token_account, false),
+        AccountMeta::new(keys.input_vault, false),
+        AccountMeta::new(keys.output_vault, false),
+        AccountMeta::new(keys.observation_state, false),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(keys.token_program_2022, false),
+        AccountMeta::new_readonly(keys.memo_program, false),
+        AccountMeta::new_readonly(keys.input_vault_mint, false),
+        AccountMeta::new_readonly(keys.output_vault_mint, false),
+    ];
+    accounts.extend(keys.tick_arrays.iter().map(|tick_array| AccountMeta::new(*tick_array, false)));
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+/// The pricing state of a Raydium CLMM pool
+pub struct RaydiumClmmState {
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+    pub status: u8,
+}
+
+impl RaydiumClmmState {
+    /// Reads the pricing state from a `PoolState` account
+    pub fn load(pool_state: &AccountInfo) -> Result<Self> {
+        let data = pool_state.try_borrow_data()?;
+        require!(
+            data.get(..8) == Some(&POOL_STATE_DISCRIMINATOR[..]),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+
+        Ok(Self {
+            amm_config: read_pubkey(&data, POOL_AMM_CONFIG_OFFSET)?,
+            token_mint_0: read_pubkey(&data, POOL_TOKEN_MINT_0_OFFSET)?,
+            token_mint_1: read_pubkey(&data, POOL_TOKEN_MINT_1_OFFSET)?,
+            token_vault_0: read_pubkey(&data, POOL_TOKEN_VAULT_0_OFFSET)?,
+            token_vault_1: read_pubkey(&data, POOL_TOKEN_VAULT_1_OFFSET)?,
+            observation_key: read_pubkey(&data, POOL_OBSERVATION_KEY_OFFSET)?,
+            tick_spacing: read_u16(&data, POOL_TICK_SPACING_OFFSET)?,
+            liquidity: read_u128(&data, POOL_LIQUIDITY_OFFSET)?,
+            sqrt_price: read_u128(&data, POOL_SQRT_PRICE_OFFSET)?,
+            tick_current: read_i32(&data, POOL_TICK_CURRENT_OFFSET)?,
+            status: *data
+                .get(POOL_STATUS_OFFSET)
+                .ok_or(FlashLoanArbitrageError::InvalidPoolAccount)?,
+        })
+    }
+
+    pub fn swap_disabled(&self) -> bool {
+        self.status & POOL_STATUS_SWAP_DISABLED != 0
+    }
+
+    /// The pool as the tick walk sees it, with the trade fee of its `AmmConfig`
+    pub fn clmm_pool(&self, trade_fee_rate: u32) -> ClmmPool {
+        ClmmPool {
+            program: ClmmProgram::RaydiumClmm,
+            sqrt_price: self.sqrt_price,
+            liquidity: self.liquidity,
+            tick_current_index: self.tick_current,
+            tick_spacing: self.tick_spacing,
+            fee_rate: trade_fee_rate,
+            ticks_per_array: TICK_ARRAY_SIZE as i32,
+        }
+    }
+}
+
+/// Reads the trade fee rate from a Raydium CLMM `AmmConfig` account
+pub fn amm_config_trade_fee_rate(amm_config: &AccountInfo) -> Result<u32> {
+    let data = amm_config.try_borrow_data()?;
+    require!(
+        data.get(..8) == Some(&AMM_CONFIG_DISCRIMINATOR[..]),
+        FlashLoanArbitrageError::InvalidPoolAccount
+    );
+    read_u32(&data, AMM_CONFIG_TRADE_FEE_RATE_OFFSET)
+}
+
+/// A Raydium CLMM tick array read in place from its account data
+pub struct RaydiumClmmTickArray<'a> {
+    data: &'a [u8],
+    start_tick_index: i32,
+}
+
+impl<'a> RaydiumClmmTickArray<'a> {
+    /// Checks the account is a tick array of `pool_state`
+    pub fn new(data: &'a [u8], pool_state: &Pubkey) -> Result<Self> {
+        require!(
+            data.get(..8) == Some(&TICK_ARRAY_DISCRIMINATOR[..])
+                && data.len() >= TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE * TICK_LEN
+                && read_pubkey(data, TICK_ARRAY_POOL_OFFSET)? == *pool_state,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+        Ok(Self {
+            data,
+            start_tick_index: read_i32(data, TICK_ARRAY_START_TICK_INDEX_OFFSET)?,
+        })
+    }
+}
+
+impl TickArrayView for RaydiumClmmTickArray<'_> {
+    fn start_tick_index(&self) -> i32 {
+        self.start_tick_index
+    }
+
+    fn liquidity_net(&self, offset: usize) -> Option<i128> {
+        if offset >= TICK_ARRAY_SIZE {
+            return None;
+        }
+        let tick = TICK_ARRAY_TICKS_OFFSET + offset * TICK_LEN;
+        // A tick is initialized while it holds any liquidity
+        let liquidity_gross = read_u128(self.data, tick + TICK_LIQUIDITY_GROSS_OFFSET).ok()?;
+        if liquidity_gross == 0 {
+            return None;
+        }
+        read_u128(self.data, tick + TICK_LIQUIDITY_NET_OFFSET)
+            .ok()
+            .map(|liquidity_net| liquidity_net as i128)
+    }
+}
+
+impl RaydiumClmm {
+    /// Quotes the leg by walking the pool's tick arrays from the current price
+    pub fn quote_swap(&self, leg: &Leg, amount_in: u64) -> Result<SwapQuote> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = RaydiumClmmState::load(leg.accounts.pool)?;
+        let remaining = leg.accounts.remaining_accounts;
+        let trade_fee_rate = amm_config_trade_fee_rate(&remaining[AMM_CONFIG])?;
+        let pool_key = leg.accounts.pool.key();
+
+        // The bitmap extension only tells the program where the tick arrays are
+        let data = remaining[TICK_ARRAYS..]
+            .iter()
+            .map(|account| account.try_borrow_data())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let tick_arrays = data
+            .iter()
+            .filter(|data| data.get(..8) != Some(&TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR[..]))
+            .map(|data| RaydiumClmmTickArray::new(data, &pool_key))
+            .collect::<Result<Vec<_>>>()?;
+
+        clmm::quote_exact_input(
+            &state.clmm_pool(trade_fee_rate),
+            &tick_arrays,
+            amount_in,
+            leg.a_to_b,
+            leg.sqrt_price_limit,
+        )
+        .map_err(|e| match e {
+            QuoteError::MathOverflow => error!(FlashLoanArbitrageError::MathOverflow),
+            QuoteError::TickArraySequence => error!(FlashLoanArbitrageError::InvalidDexAccounts),
+            QuoteError::InvalidSqrtPriceLimit => error!(FlashLoanArbitrageError::SlippageToleranceExceeded),
+        })
+    }
+}
+
+/// Raydium CLMM adapter
+///
+/// Leg remaining accounts: `[amm_config, observation_state, token_mint_0, token_mint_1,
+/// token_program, token_program_2022, memo_program]`, then the tick arrays the swap
+/// crosses in order, led by the pool's `TickArrayBitmapExtension` when the swap needs
+/// it. Only tick arrays holding initialized ticks exist, so the arrays may skip ranges.
+/// The pool's token A and B accounts are its token 0 and token 1 vaults; `pool_authority`
+/// is not used.
+pub struct RaydiumClmm;
+
+impl DexAdapter for RaydiumClmm {
+    fn validate_accounts(&self, leg: &Leg) -> Result<()> {
+        let remaining = leg.accounts.remaining_accounts;
+        require!(
+            remaining.len() >= REMAINING_ACCOUNTS_MIN_LEN,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+
+        let state = RaydiumClmmState::load(leg.accounts.pool)?;
+        require!(
+            state.token_vault_0 == leg.accounts.pool_token_a_account.key()
+                && state.token_vault_1 == leg.accounts.pool_token_b_account.key()
+                && state.amm_config == remaining[AMM_CONFIG].key()
+                && state.observation_key == remaining[OBSERVATION_STATE].key()
+                && state.token_mint_0 == remaining[TOKEN_MINT_0].key()
+                && state.token_mint_1 == remaining[TOKEN_MINT_1].key()
+                && !state.swap_disabled(),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+        Ok(())
+    }
+
+    fn quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        Ok(self.quote_swap(leg, amount_in)?.amount_out)
+    }
+
+    fn spot_quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = RaydiumClmmState::load(leg.accounts.pool)?;
+        let trade_fee_rate = amm_config_trade_fee_rate(&leg.accounts.remaining_accounts[AMM_CONFIG])?;
+        clmm::spot_output(&state.clmm_pool(trade_fee_rate), amount_in, leg.a_to_b)
+            .map_err(|_| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+
+    fn pool_vaults(&self, pool: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+        let state = RaydiumClmmState::load(pool)?;
+        Ok((state.token_vault_0, state.token_vault_1))
+    }
+
+    fn pool_mints<'a, 'info>(&self, leg: &Leg<'a, 'info>) -> Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)> {
+        let remaining = leg.accounts.remaining_accounts;
+        Some((remaining.get(TOKEN_MINT_0)?, remaining.get(TOKEN_MINT_1)?))
+    }
+
+    fn build_swap_ix(&self, leg: &Leg, amount_in: u64, min_out_amount: u64) -> Result<Instruction> {
+        let accounts = &leg.accounts;
+        let remaining = accounts.remaining_accounts;
+        let (input_vault, output_vault) = leg.vaults();
+        let (input_mint, output_mint) = if leg.a_to_b {
+            (TOKEN_MINT_0, TOKEN_MINT_1)
+        } else {
+            (TOKEN_MINT_1, TOKEN_MINT_0)
+        };
+
+        let keys = RaydiumClmmSwapKeys {
+            payer: accounts.user_authority.key(),
+            amm_config: remaining[AMM_CONFIG].key(),
+            pool_state: accounts.pool.key(),
+            input_token_account: accounts.input_token_account.key(),
+            output_token_account: accounts.output_token_account.key(),
+            input_vault: input_vault.key(),
+            output_vault: output_vault.key(),
+            observation_state: remaining[OBSERVATION_STATE].key(),
+            token_program: remaining[TOKEN_PROGRAM].key(),
+            token_program_2022: remaining[TOKEN_PROGRAM_2022].key(),
+            memo_program: remaining[MEMO_PROGRAM].key(),
+            input_vault_mint: remaining[input_mint].key(),
+            output_vault_mint: remaining[output_mint].key(),
+            tick_arrays: remaining[TICK_ARRAYS..].iter().map(|account| account.key()).collect(),
+        };
+        let sqrt_price_limit = match leg.sqrt_price_limit {
+            0 => ClmmProgram::RaydiumClmm.default_sqrt_price_limit(leg.a_to_b),
+            limit => limit,
+        };
+        Ok(swap_v2(leg.program_id, &keys, amount_in, min_out_amount, sqrt_price_limit))
+    }
+}
@@ -0,0 +1,76 @@
+//! Splitting one leg's input across several pools
+//!
+//! `split_amount` is how the program divides a leg's input by weight, and
+//! `optimize_split_weights` picks the weights off-chain from the pools' quotes. Only
+//! `core` is used, so a bot can include this module alongside `clmm` and `math`.
+
+/// Split weights are in basis points and must add up to this
+pub const WEIGHT_DENOMINATOR: u64 = 10_000;
+
+/// The part of `amount` sent to the pool at `index`
+///
+/// Every pool gets its weight's share rounded down, and the last pool also takes the
+/// rounding leftovers so that the parts add up to `amount`.
+pub fn split_amount(amount: u64, weights_bps: &[u16], index: usize) -> Option<u64> {
+    if index + 1 == weights_bps.len() {
+        let others = (0..index).try_fold(0u64, |total, other| {
+            total.checked_add(split_amount(amount, weights_bps, other)?)
+        })?;
+        return amount.checked_sub(others);
+    }
+
+    let weight = *weights_bps.get(index)? as u128;
+    u64::try_from(amount as u128 * weight / WEIGHT_DENOMINATOR as u128).ok()
+}
+
+/// Picks split weights that maximize the total output of a leg
+///
+/// Hands out the weight in `steps` equal increments, each to the pool whose output grows
+/// the most from it. As pool outputs are concave in their input, this converges on the
+/// best split at the chosen resolution. `quote(pool_index, amount_in)` prices one pool,
+/// returning `None` when it cannot take that input.
+///
+/// Writes the weights into `weights_bps`, one per pool, and returns the expected output.
+pub fn optimize_split_weights<F>(
+    amount: u64,
+    weights_bps: &mut [u16],
+    steps: u16,
+    mut quote: F,
+) -> Option<u64>
+where
+    F: FnMut(usize, u64) -> Option<u64>,
+{
+    if weights_bps.is_empty() || steps == 0 {
+        return None;
+    }
+    weights_bps.iter_mut().for_each(|weight| *weight = 0);
+
+    let steps = steps as u64;
+    for step in 0..steps {
+        let increment = ((step + 1) * WEIGHT_DENOMINATOR / steps - step * WEIGHT_DENOMINATOR / steps) as u16;
+
+        let mut best: Option<(usize, u64)> = None;
+        for (pool, weight) in weights_bps.iter().enumerate() {
+            let current_in = amount as u128 * *weight as u128 / WEIGHT_DENOMINATOR as u128;
+            let next_in = amount as u128 * (*weight + increment) as u128 / WEIGHT_DENOMINATOR as u128;
+            let current_out = if current_in == 0 { 0 } else { quote(pool, current_in as u64)? };
+            let gain = match quote(pool, next_in as u64) {
+                Some(next_out) => next_out.saturating_sub(current_out),
+                None => continue,
+            };
+            match best {
+                Some((_, best_gain)) if best_gain >= gain => {}
+                _ => best = Some((pool, gain)),
+            }
+        }
+
+        let (pool, _) = best?;
+        weights_bps[pool] += increment;
+    }
+
+    (0..weights_bps.len()).try_fold(0u64, |total, pool| {
+        let amount_in = split_amount(amount, weights_bps, pool)?;
+        let amount_out = if amount_in == 0 { 0 } else { quote(pool, amount_in)? };
+        total.checked_add(amount_out)
+    })
+}
@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::dex::clmm::{self, ClmmPool, ClmmProgram, QuoteError, SwapQuote, TickArrayView};
+use crate::dex::{read_i32, read_pubkey, read_u128, read_u16, DexAdapter, Leg};
+use crate::errors::FlashLoanArbitrageError;
+
+// Orca Whirlpool instruction discriminators
+pub const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+pub const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+// Whirlpool account layout
+pub const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+const WHIRLPOOL_TICK_SPACING_OFFSET: usize = 41;
+const WHIRLPOOL_FEE_RATE_OFFSET: usize = 45;
+const WHIRLPOOL_LIQUIDITY_OFFSET: usize = 49;
+const WHIRLPOOL_SQRT_PRICE_OFFSET: usize = 65;
+const WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const WHIRLPOOL_TOKEN_VAULT_A_OFFSET: usize = 133;
+const WHIRLPOOL_TOKEN_VAULT_B_OFFSET: usize = 213;
+
+// Tick array account layout
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+pub const TICK_ARRAY_SIZE: usize = 88;
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 8;
+const TICK_ARRAY_TICKS_OFFSET: usize = 12;
+const TICK_LEN: usize = 113;
+const TICK_LIQUIDITY_NET_OFFSET: usize = 1;
+const TICK_ARRAY_WHIRLPOOL_OFFSET: usize = TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE * TICK_LEN;
+
+// Whirlpool accounts expected in the leg's `remaining_accounts`
+const TICK_ARRAY_0: usize = 0;
+const TICK_ARRAY_1: usize = 1;
+const TICK_ARRAY_2: usize = 2;
+const ORACLE: usize = 3;
+const SWAP_REMAINING_ACCOUNTS_LEN: usize = 4;
+// `swap_v2` additionally takes the mints, their token programs and the memo program
+const TOKEN_MINT_A: usize = 4;
+const TOKEN_MINT_B: usize = 5;
+const TOKEN_PROGRAM_A: usize = 6;
+const TOKEN_PROGRAM_B: usize = 7;
+const MEMO_PROGRAM: usize = 8;
+const SWAP_V2_REMAINING_ACCOUNTS_LEN: usize = 9;
+
+/// Arguments of the Whirlpool `swap` and `swap_v2` instructions
+pub struct WhirlpoolSwapArgs {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    pub a_to_b: bool,
+}
+
+impl WhirlpoolSwapArgs {
+    fn data(&self, discriminator: &[u8; 8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(43);
+        data.extend_from_slice(discriminator);
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.extend_from_slice(&self.other_amount_threshold.to_le_bytes());
+        data.extend_from_slice(&self.sqrt_price_limit.to_le_bytes());
+        data.push(self.amount_specified_is_input as u8);
+        data.push(self.a_to_b as u8);
+        data
+    }
+}
+
+/// The pool-side accounts shared by `swap` and `swap_v2`
+pub struct WhirlpoolKeys {
+    pub whirlpool: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub tick_arrays: [Pubkey; 3],
+    pub oracle: Pubkey,
+}
+
+/// Builds a Whirlpool `swap` instruction
+pub fn swap(
+    program_id: Pubkey,
+    args: &WhirlpoolSwapArgs,
+    pool: &WhirlpoolKeys,
+    token_program: Pubkey,
+    token_authority: Pubkey,
+    token_owner_account_a: Pubkey,
+    token_owner_account_b: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(token_authority, true),
+            AccountMeta::new(pool.whirlpool, false),
+            AccountMeta::new(token_owner_account_a, false),
+            AccountMeta::new(pool.token_vault_a, false),
+            AccountMeta::new(token_owner_account_b, false),
+            AccountMeta::new(pool.token_vault_b, false),
+            AccountMeta::new(pool.tick_arrays[0], false),
+            AccountMeta::new(pool.tick_arrays[1], false),
+            AccountMeta::new(pool.tick_arrays[2], false),
+            AccountMeta::new(pool.oracle, false),
+        ],
+        data: args.data(&SWAP_DISCRIMINATOR),
+    }
+}
+
+/// The token-side accounts `swap_v2` adds on top of `swap`
+pub struct WhirlpoolV2Keys {
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+    pub memo_program: Pubkey,
+}
+
+/// Builds a Whirlpool `swap_v2` instruction
+pub fn swap_v2(
+    program_id: Pubkey,
+    args: &WhirlpoolSwapArgs,
+    pool: &WhirlpoolKeys,
+    tokens: &WhirlpoolV2Keys,
+    token_authority: Pubkey,
+    token_owner_account_a: Pubkey,
+    token_owner_account_b: Pubkey,
+) -> Instruction {
+    let mut data = args.data(&SWAP_V2_DISCRIMINATOR);
+    // remaining_accounts_info: None, no transfer hook accounts are passed
+    data.push(0);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(tokens.token_program_a, false),
+            AccountMeta::new_readonly(tokens.token_program_b, false),
+            AccountMeta::new_readonly(tokens.memo_program, false),
+            AccountMeta::new_readonly(token_authority, true),
+            AccountMeta::new(pool.whirlpool, false),
+            AccountMeta::new_readonly(tokens.token_mint_a, false),
+            AccountMeta::new_readonly(tokens.token_mint_b, false),
+            AccountMeta::new(token_owner_account_a, false),
+            AccountMeta::new(pool.token_vault_a, false),
+            AccountMeta::new(token_owner_account_b, false),
+            AccountMeta::new(pool.token_vault_b, false),
+            AccountMeta::new(pool.tick_arrays[0], false),
+            AccountMeta::new(pool.tick_arrays[1], false),
+            AccountMeta::new(pool.tick_arrays[2], false),
+            AccountMeta::new(pool.oracle, false),
+        ],
+        data,
+    }
+}
+
+/// The pricing state of a Whirlpool
+pub struct WhirlpoolState {
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+}
+
+impl WhirlpoolState {
+    /// Reads the pricing state from a Whirlpool account
+    pub fn load(pool: &AccountInfo) -> Result<Self> {
+        let data = pool.try_borrow_data()?;
+        require!(
+            data.get(..8) == Some(&WHIRLPOOL_DISCRIMINATOR[..]),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+
+        Ok(Self {
+            tick_spacing: read_u16(&data, WHIRLPOOL_TICK_SPACING_OFFSET)?,
+            fee_rate: read_u16(&data, WHIRLPOOL_FEE_RATE_OFFSET)?,
+            liquidity: read_u128(&data, WHIRLPOOL_LIQUIDITY_OFFSET)?,
+            sqrt_price: read_u128(&data, WHIRLPOOL_SQRT_PRICE_OFFSET)?,
+            tick_current_index: read_i32(&data, WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET)?,
+            token_vault_a: read_pubkey(&data, WHIRLPOOL_TOKEN_VAULT_A_OFFSET)?,
+            token_vault_b: read_pubkey(&data, WHIRLPOOL_TOKEN_VAULT_B_OFFSET)?,
+        })
+    }
+
+    pub fn clmm_pool(&self) -> ClmmPool {
+        ClmmPool {
+            program: ClmmProgram::Whirlpool,
+            sqrt_price: self.sqrt_price,
+            liquidity: self.liquidity,
+            tick_current_index: self.tick_current_index,
+            tick_spacing: self.tick_spacing,
+            fee_rate: self.fee_rate as u32,
+            ticks_per_array: TICK_ARRAY_SIZE as i32,
+        }
+    }
+}
+
+/// A Whirlpool tick array read in place from its account data
+pub struct WhirlpoolTickArray<'a> {
+    data: &'a [u8],
+    start_tick_index: i32,
+}
+
+impl<'a> WhirlpoolTickArray<'a> {
+    /// Checks the account is a tick array of `whirlpool`
+    pub fn new(data: &'a [u8], whirlpool: &Pubkey) -> Result<Self> {
+        require!(
+            data.get(..8) == Some(&TICK_ARRAY_DISCRIMINATOR[..])
+                && read_pubkey(data, TICK_ARRAY_WHIRLPOOL_OFFSET)? == *whirlpool,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+        Ok(Self {
+            data,
+            start_tick_index: read_i32(data, TICK_ARRAY_START_TICK_INDEX_OFFSET)?,
+        })
+    }
+}
+
+impl TickArrayView for WhirlpoolTickArray<'_> {
+    fn start_tick_index(&self) -> i32 {
+        self.start_tick_index
+    }
+
+    fn liquidity_net(&self, offset: usize) -> Option<i128> {
+        if offset >= TICK_ARRAY_SIZE {
+            return None;
+        }
+        let tick = TICK_ARRAY_TICKS_OFFSET + offset * TICK_LEN;
+        if self.data[tick] == 0 {
+            return None;
+        }
+        let mut liquidity_net = [0u8; 16];
+        liquidity_net.copy_from_slice(
+            &self.data[tick + TICK_LIQUIDITY_NET_OFFSET..tick + TICK_LIQUIDITY_NET_OFFSET + 16],
+        );
+        Some(i128::from_le_bytes(liquidity_net))
+    }
+}
+
+impl Whirlpool {
+    /// Quotes the leg by walking the Whirlpool's tick arrays from the current price
+    pub fn quote_swap(&self, leg: &Leg, amount_in: u64) -> Result<SwapQuote> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = WhirlpoolState::load(leg.accounts.pool)?;
+        let pool_key = leg.accounts.pool.key();
+
+        let remaining = leg.accounts.remaining_accounts;
+        let data = [
+            remaining[TICK_ARRAY_0].try_borrow_data()?,
+            remaining[TICK_ARRAY_1].try_borrow_data()?,
+            remaining[TICK_ARRAY_2].try_borrow_data()?,
+        ];
+        let tick_arrays = [
+            WhirlpoolTickArray::new(&data[0], &pool_key)?,
+            WhirlpoolTickArray::new(&data[1], &pool_key)?,
+            WhirlpoolTickArray::new(&data[2], &pool_key)?,
+        ];
+
+        clmm::quote_exact_input(
+            &state.clmm_pool(),
+            &tick_arrays,
+            amount_in,
+            leg.a_to_b,
+            leg.sqrt_price_limit,
+        )
+        .map_err(|e| match e {
+            QuoteError::MathOverflow => error!(FlashLoanArbitrageError::MathOverflow),
+            QuoteError::TickArraySequence => error!(FlashLoanArbitrageError::InvalidDexAccounts),
+            QuoteError::InvalidSqrtPriceLimit => error!(FlashLoanArbitrageError::SlippageToleranceExceeded),
+        })
+    }
+}
+
+/// Orca Whirlpool adapter
+///
+/// Leg remaining accounts: `[tick_array_0, tick_array_1, tick_array_2, oracle]`, followed
+/// for `swap_v2` by `[token_mint_a, token_mint_b, token_program_a, token_program_b, memo_program]`.
+/// The pool's token A and B accounts are its two vaults; `pool_authority` is not used.
+pub struct Whirlpool {
+    pub use_swap_v2: bool,
+}
+
+impl DexAdapter for Whirlpool {
+    fn validate_accounts(&self, leg: &Leg) -> Result<()> {
+        let expected_len = if self.use_swap_v2 {
+            SWAP_V2_REMAINING_ACCOUNTS_LEN
+        } else {
+            SWAP_REMAINING_ACCOUNTS_LEN
+        };
+        require!(
+            leg.accounts.remaining_accounts.len() == expected_len,
+            FlashLoanArbitrageError::InvalidDexAccounts
+        );
+
+        let state = WhirlpoolState::load(leg.accounts.pool)?;
+        require!(
+            state.token_vault_a == leg.accounts.pool_token_a_account.key()
+                && state.token_vault_b == leg.accounts.pool_token_b_account.key(),
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+        Ok(())
+    }
+
+    fn quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        Ok(self.quote_swap(leg, amount_in)?.amount_out)
+    }
+
+    fn spot_quote(&self, leg: &Leg, amount_in: u64) -> Result<u64> {
+        leg.check_pool_owner()?;
+        self.validate_accounts(leg)?;
+        let state = WhirlpoolState::load(leg.accounts.pool)?;
+        clmm::spot_output(&state.clmm_pool(), amount_in, leg.a_to_b)
+            .map_err(|_| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+
+    fn pool_vaults(&self, pool: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+        let state = WhirlpoolState::load(pool)?;
+        Ok((state.token_vault_a, state.token_vault_b))
+    }
+
+    fn pool_mints<'a, 'info>(&self, leg: &Leg<'a, 'info>) -> Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)> {
+        if !self.use_swap_v2 {
+            return None;
+        }
+        let remaining = leg.accounts.remaining_accounts;
+        Some((remaining.get(TOKEN_MINT_A)?, remaining.get(TOKEN_MINT_B)?))
+    }
+
+    fn build_swap_ix(&self, leg: &Leg, amount_in: u64, min_out_amount: u64) -> Result<Instruction> {
+        let accounts = &leg.accounts;
+        let remaining = accounts.remaining_accounts;
+        let a_to_b = leg.a_to_b;
+        let args = WhirlpoolSwapArgs {
+            amount: amount_in,
+            other_amount_threshold: min_out_amount,
+            sqrt_price_limit: match leg.sqrt_price_limit {
+                0 => ClmmProgram::Whirlpool.default_sqrt_price_limit(a_to_b),
+                limit => limit,
+            },
+            amount_specified_is_input: true,
+            a_to_b,
+        };
+
+        // The owner accounts follow the pool's A/B order, not the swap direction
+        let (owner_account_a, owner_account_b) = if a_to_b {
+            (accounts.input_token_account, accounts.output_token_account)
+        } else {
+            (accounts.output_token_account, accounts.input_token_account)
+        };
+
+        let pool = WhirlpoolKeys {
+            whirlpool: accounts.pool.key(),
+            token_vault_a: accounts.pool_token_a_account.key(),
+            token_vault_b: accounts.pool_token_b_account.key(),
+            tick_arrays: [
+                remaining[TICK_ARRAY_0].key(),
+                remaining[TICK_ARRAY_1].key(),
+                remaining[TICK_ARRAY_2].key(),
+            ],
+            oracle: remaining[ORACLE].key(),
+        };
+
+        let swap_ix = if self.use_swap_v2 {
+            let tokens = WhirlpoolV2Keys {
+                token_mint_a: remaining[TOKEN_MINT_A].key(),
+                token_mint_b: remaining[TOKEN_MINT_B].key(),
+                token_program_a: remaining[TOKEN_PROGRAM_A].key(),
+                token_program_b: remaining[TOKEN_PROGRAM_B].key(),
+                memo_program: remaining[MEMO_PROGRAM].key(),
+            };
+            swap_v2(
+                leg.program_id,
+                &args,
+                &pool,
+                &tokens,
+                accounts.user_authority.key(),
+                owner_account_a.key(),
+                owner_account_b.key(),
+            )
+        } else {
+            swap(
+                leg.program_id,
+                &args,
+                &pool,
+                accounts.token_program.key(),
+                accounts.user_authority.key(),
+                owner_account_a.key(),
+                owner_account_b.key(),
+            )
+        };
+        Ok(swap_ix)
+    }
+}
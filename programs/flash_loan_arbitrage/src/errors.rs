@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum FlashLoanArbitrageError {
+    #[msg("Flash loan initialization failed")]
+    FlashLoanInitFailed,
+    
+    #[msg("Flash loan repayment failed")]
+    FlashLoanRepaymentFailed,
+    
+    #[msg("DEX swap failed")]
+    DexSwapFailed,
+    
+    #[msg("Insufficient profit from arbitrage")]
+    InsufficientProfit,
+    
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    
+    #[msg("Invalid pool account")]
+    InvalidPoolAccount,
+    
+    #[msg("Invalid loan amount")]
+    InvalidLoanAmount,
+    
+    #[msg("Math operation overflow")]
+    MathOverflow,
+    
+    #[msg("Unauthorized access")]
+    Unauthorized,
+
+    #[msg("Token account mint mismatch")]
+    TokenAccountMintMismatch,
+    
+    #[msg("Token account owner mismatch")]
+    TokenAccountOwnerMismatch,
+    
+    #[msg("DEX pool account owner mismatch")]
+    DexPoolOwnerMismatch,
+    
+    #[msg("Lending pool account owner mismatch")]
+    LendingPoolOwnerMismatch,
+    
+    #[msg("The current slot is past the trade's deadline")]
+    DeadlineExceeded,
+    
+    #[msg("Slippage tolerance exceeded")]
+    SlippageToleranceExceeded,
+    
+    #[msg("First swap failed: insufficient output")]
+    FirstSwapInsufficientOutput,
+    
+    #[msg("Second swap failed: insufficient output")]
+    SecondSwapInsufficientOutput,
+    
+    #[msg("Loan amount too small")]
+    LoanAmountTooSmall,
+    
+    #[msg("Loan amount too large")]
+    LoanAmountTooLarge,
+    
+    #[msg("DEX A and DEX B cannot be the same")]
+    SameDexError,
+
+    #[msg("No matching flash borrow instruction before this instruction")]
+    MissingFlashBorrowInstruction,
+
+    #[msg("No matching flash repay instruction after this instruction")]
+    MissingFlashRepayInstruction,
+
+    #[msg("Lending protocol accounts missing from remaining accounts")]
+    InvalidLendingAccounts,
+
+    #[msg("Instruction must run between MarginFi start and end flashloan instructions")]
+    OutsideMarginFiFlashloan,
+
+    #[msg("DEX accounts missing or invalid for the selected swap")]
+    InvalidDexAccounts,
+
+    #[msg("Route must have between 2 and 5 legs")]
+    InvalidRouteLength,
+
+    #[msg("Route legs must form a closed cycle through the loan token account")]
+    RouteNotClosed,
+
+    #[msg("A route leg needs 1 to 4 pools whose weights add up to 10000 bps")]
+    InvalidSplitWeights,
+
+    #[msg("Loan search needs between 2 and 64 quote evaluations")]
+    InvalidSearchBudget,
+
+    #[msg("Config limits are inconsistent")]
+    InvalidConfig,
+
+    #[msg("The program is paused")]
+    ProgramPaused,
+
+    #[msg("Program is not approved for this lender or DEX")]
+    ProgramNotApproved,
+
+    #[msg("The program registry is full")]
+    ProgramRegistryFull,
+
+    #[msg("The vault token account holds less than the requested amount")]
+    InsufficientVaultBalance,
+
+    #[msg("Vault mode needs a repayment token account of the loan mint owned by the authority")]
+    MissingRepaymentAccount,
+
+    #[msg("Vault mode without a strategy needs the loan mint's profit ledger")]
+    MissingProfitLedger,
+
+    #[msg("The withdrawal exceeds the profit the vault has accrued in this mint")]
+    WithdrawalExceedsProfit,
+
+    #[msg("The amount is too small to mint or redeem any shares")]
+    StrategyAmountTooSmall,
+
+    #[msg("The loan token account is not the strategy's assets account")]
+    StrategyAccountMismatch,
+
+    #[msg("An admin cannot revoke their own admin role")]
+    CannotRemoveOwnAdminRole,
+
+    #[msg("An instruction before the arbitrage calls a program the transaction guard does not allow")]
+    UnexpectedInstructionBefore,
+
+    #[msg("An instruction after the arbitrage calls a program the transaction guard does not allow")]
+    UnexpectedInstructionAfter,
+
+    #[msg("A system program instruction is not a transfer to an approved tip account")]
+    UnapprovedTipTransfer,
+
+    #[msg("The arbitrage may not be invoked through CPI")]
+    CpiNotAllowed,
+
+    #[msg("The transaction may hold only one instruction of this program")]
+    MultipleArbitrageInstructions,
+
+    #[msg("A token program instruction sits between the flash borrow and repay")]
+    TokenInstructionInFlashLoan,
+
+    #[msg("The quote is older than the configured maximum, or from a later slot")]
+    StaleQuote,
+
+    #[msg("The requested slippage is above the configured maximum")]
+    SlippageAboveMaximum,
+
+    #[msg("A route leg or one of its pools delivered less than its minimum output")]
+    RouteLegInsufficientOutput,
+
+    #[msg("The oracle account is not a supported price feed with a positive price")]
+    InvalidOracleAccount,
+
+    #[msg("The oracle account is not the one registered for its mint")]
+    OracleAccountMismatch,
+
+    #[msg("Oracle accounts must be passed for both the loan and intermediate mints, or neither")]
+    MissingOracleAccount,
+
+    #[msg("The oracle price is older than the configured maximum")]
+    StaleOraclePrice,
+
+    #[msg("The oracle's confidence interval is wider than the configured maximum")]
+    OracleConfidenceTooWide,
+
+    #[msg("A leg's execution price deviates from the oracle prices by more than the configured band")]
+    OraclePriceDeviation,
+
+    #[msg("A Token-2022 mint with a transfer fee needs a swap that takes the mints, such as Whirlpool swap_v2 or Raydium CPMM")]
+    TransferFeeMintNotSupported,
+
+    #[msg("The loan mint may not have a Token-2022 transfer fee, which the lender's repay would not cover")]
+    LoanMintTransferFeeNotSupported,
+} 
\ No newline at end of file
@@ -0,0 +1,102 @@
+//! Events emitted by the program
+
+use anchor_lang::prelude::*;
+
+use crate::dex::DexKind;
+use crate::oracle::OracleKind;
+use crate::state::{ProgramKind, Role};
+
+/// Emitted whenever the config is created or changed, with the values now in force
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub min_profit_threshold: u64,
+    pub default_slippage_bps: u16,
+    pub max_slippage_bps: u16,
+    pub max_quote_age_slots: u64,
+    pub transaction_guard: bool,
+    pub allow_cpi: bool,
+    pub oracle_max_deviation_bps: u16,
+    pub oracle_max_confidence_bps: u16,
+    pub oracle_max_age_secs: u64,
+}
+
+/// Emitted when the admin approves or revokes a program
+#[event]
+pub struct ProgramRegistryUpdated {
+    pub program_id: Pubkey,
+    pub kind: ProgramKind,
+    pub approved: bool,
+}
+
+/// Emitted when the admin registers or removes a pool
+#[event]
+pub struct PoolRegistryUpdated {
+    pub pool: Pubkey,
+    pub dex: DexKind,
+    pub registered: bool,
+}
+
+/// Emitted when an admin registers or removes a mint's oracle
+#[event]
+pub struct OracleRegistryUpdated {
+    pub mint: Pubkey,
+    pub oracle: Pubkey,
+    pub kind: OracleKind,
+    pub registered: bool,
+}
+
+/// Emitted when an admin grants or revokes a role
+#[event]
+pub struct OperatorUpdated {
+    pub operator: Pubkey,
+    pub role: Role,
+    pub granted: bool,
+}
+
+/// Emitted when a withdrawer withdraws from a vault token account
+#[event]
+pub struct ProfitWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+}
+
+/// Emitted when a depositor adds assets to a strategy
+#[event]
+pub struct StrategyDeposited {
+    pub strategy: Pubkey,
+    pub depositor: Pubkey,
+    /// The assets credited, after any transfer fee
+    pub amount: u64,
+    pub shares: u64,
+}
+
+/// Emitted when a depositor redeems shares for a strategy's assets
+#[event]
+pub struct StrategyWithdrawn {
+    pub strategy: Pubkey,
+    pub depositor: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+}
+
+/// Emitted when an arbitrage adds its profit to a strategy
+#[event]
+pub struct StrategyProfitCredited {
+    pub strategy: Pubkey,
+    pub profit: u64,
+    pub performance_fee: u64,
+    pub total_assets: u64,
+}
+
+/// Emitted when a withdrawer sends a strategy's accrued fees to its treasury
+#[event]
+pub struct StrategyFeesCollected {
+    pub strategy: Pubkey,
+    pub amount: u64,
+    pub treasury: Pubkey,
+}
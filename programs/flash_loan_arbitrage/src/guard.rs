@@ -0,0 +1,137 @@
+//! An optional guard on the rest of the arbitrage's transaction
+//!
+//! Instructions placed around the arbitrage could move the pools it trades against,
+//! or wrap it to take the profit. With the config's `transaction_guard` set, or in
+//! vault mode, the arbitrage must be the transaction's only instruction of this
+//! program, and every other top-level instruction must call the Compute Budget program
+//! or the lender, or be a system transfer to an approved tip account. Clearing
+//! `allow_cpi` also stops other programs from invoking the arbitrage.
+//!
+//! Whatever the config, no SPL Token or Token-2022 instruction may sit between the
+//! lender's borrow and repay.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::{
+    instruction::{get_stack_height, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    pubkey, system_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::dex;
+use crate::errors::FlashLoanArbitrageError;
+use crate::instruction;
+use crate::lending::account_at;
+use crate::state::{Config, ProgramKind, ProgramRegistry};
+
+/// The Compute Budget program, whose instructions only set the transaction's limits and fees
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
+
+// `SystemInstruction::Transfer` is a four byte tag followed by the lamports
+const SYSTEM_TRANSFER_TAG: u32 = 2;
+const SYSTEM_TRANSFER_DATA_LEN: usize = 12;
+// Index of the recipient in a system transfer's accounts
+const SYSTEM_TRANSFER_RECIPIENT_INDEX: usize = 1;
+
+/// Applies the guards the config enables to the current transaction
+///
+/// `required` runs the instruction scan even when the config leaves it off.
+pub fn check_transaction(
+    config: &Config,
+    program_registry: &ProgramRegistry,
+    instructions_sysvar: &AccountInfo,
+    lending_program: &Pubkey,
+    required: bool,
+) -> Result<()> {
+    if !config.allow_cpi {
+        require!(
+            get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+            FlashLoanArbitrageError::CpiNotAllowed
+        );
+    }
+    if !config.transaction_guard && !required {
+        return Ok(());
+    }
+
+    // The arbitrage itself must be the current top-level instruction, so no other
+    // program can invoke it more than once
+    let current_index = load_current_index_checked(instructions_sysvar)? as usize;
+    let current_ix = load_instruction_at_checked(current_index, instructions_sysvar)?;
+    require!(is_arbitrage(&current_ix), FlashLoanArbitrageError::CpiNotAllowed);
+
+    let mut index = 0;
+    // Loading past the last instruction fails, which ends the scan
+    while let Ok(ix) = load_instruction_at_checked(index, instructions_sysvar) {
+        if index != current_index {
+            check_instruction(&ix, program_registry, lending_program, index < current_index)?;
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Checks one instruction that sits before or after the arbitrage
+fn check_instruction(
+    ix: &Instruction,
+    program_registry: &ProgramRegistry,
+    lending_program: &Pubkey,
+    before: bool,
+) -> Result<()> {
+    if ix.program_id == crate::ID {
+        msg!("The transaction holds another instruction of this program");
+        return err!(FlashLoanArbitrageError::MultipleArbitrageInstructions);
+    }
+    if ix.program_id == COMPUTE_BUDGET_PROGRAM_ID || ix.program_id == *lending_program {
+        return Ok(());
+    }
+
+    if ix.program_id == system_program::ID {
+        let is_transfer = ix.data.len() == SYSTEM_TRANSFER_DATA_LEN
+            && ix.data[..4] == SYSTEM_TRANSFER_TAG.to_le_bytes();
+        let pays_tip = match account_at(ix, SYSTEM_TRANSFER_RECIPIENT_INDEX) {
+            Some(recipient) => program_registry.is_approved(recipient, ProgramKind::Tip),
+            None => false,
+        };
+        if !(is_transfer && pays_tip) {
+            msg!("System instruction is not a transfer to an approved tip account");
+            return err!(FlashLoanArbitrageError::UnapprovedTipTransfer);
+        }
+        return Ok(());
+    }
+
+    msg!("Program {} is not allowed in the transaction", ix.program_id);
+    if before {
+        err!(FlashLoanArbitrageError::UnexpectedInstructionBefore)
+    } else {
+        err!(FlashLoanArbitrageError::UnexpectedInstructionAfter)
+    }
+}
+
+/// Rejects SPL Token and Token-2022 instructions between the lender's borrow and repay
+///
+/// The loan and the arbitrage's proceeds sit in token accounts for the length of the
+/// flash loan, and nothing but the arbitrage may move them.
+pub fn check_loan_window(
+    instructions_sysvar: &AccountInfo,
+    borrow_index: usize,
+    repay_index: usize,
+) -> Result<()> {
+    for index in borrow_index + 1..repay_index {
+        let ix = load_instruction_at_checked(index, instructions_sysvar)?;
+        if dex::is_token_program(&ix.program_id) {
+            msg!("Instruction {} calls the token program {}", index, ix.program_id);
+            return err!(FlashLoanArbitrageError::TokenInstructionInFlashLoan);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `ix` calls one of this program's arbitrage instructions
+fn is_arbitrage(ix: &Instruction) -> bool {
+    ix.program_id == crate::ID
+        && matches!(
+            ix.data.get(..8),
+            Some(discriminator) if discriminator == instruction::FlashLoanAndArbitrage::DISCRIMINATOR
+                || discriminator == instruction::FlashLoanRoute::DISCRIMINATOR
+        )
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+use crate::events::{ConfigUpdated, OperatorUpdated};
+use crate::state::accounts::{InitializeConfig, UpdateConfig};
+use crate::state::{Config, ConfigParams, Role};
+
+pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+    params.validate()?;
+
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.admin.key();
+    config.paused = false;
+    config.set_params(&params);
+    config.bump = *ctx.bumps.get("config").unwrap();
+
+    let admin_role = &mut ctx.accounts.admin_role;
+    admin_role.operator = ctx.accounts.admin.key();
+    admin_role.role = Role::Admin;
+    admin_role.bump = *ctx.bumps.get("admin_role").unwrap();
+
+    msg!("Config initialized, admin: {}", config.admin);
+    emit_config_updated(config);
+    emit!(OperatorUpdated {
+        operator: config.admin,
+        role: Role::Admin,
+        granted: true,
+    });
+    Ok(())
+}
+
+pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams, paused: bool) -> Result<()> {
+    params.validate()?;
+
+    let config = &mut ctx.accounts.config;
+    config.set_params(&params);
+    if config.paused != paused {
+        msg!("Program {}", if paused { "paused" } else { "unpaused" });
+    }
+    config.paused = paused;
+
+    emit_config_updated(config);
+    Ok(())
+}
+
+fn emit_config_updated(config: &Config) {
+    emit!(ConfigUpdated {
+        admin: config.admin,
+        paused: config.paused,
+        min_loan_amount: config.min_loan_amount,
+        max_loan_amount: config.max_loan_amount,
+        min_profit_threshold: config.min_profit_threshold,
+        default_slippage_bps: config.default_slippage_bps,
+        max_slippage_bps: config.max_slippage_bps,
+        max_quote_age_slots: config.max_quote_age_slots,
+        transaction_guard: config.transaction_guard,
+        allow_cpi: config.allow_cpi,
+        oracle_max_deviation_bps: config.oracle_max_deviation_bps,
+        oracle_max_confidence_bps: config.oracle_max_confidence_bps,
+        oracle_max_age_secs: config.oracle_max_age_secs,
+    });
+}
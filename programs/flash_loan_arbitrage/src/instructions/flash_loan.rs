@@ -1,199 +1,653 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{
-    instruction::{AccountMeta, Instruction},
-    program::invoke,
-};
-use anchor_spl::token::{self, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
+use crate::dex::{self, Leg, SwapAccounts, SwapLegParams};
+use crate::errors::FlashLoanArbitrageError;
+use crate::events::StrategyProfitCredited;
+use crate::guard;
+use crate::lending::{LendingAccounts, LendingAdapter, LendingProvider};
+use crate::oracle::{self, TokenPrice};
 use crate::state::accounts::FlashLoanAndArbitrage;
-use crate::state::errors::FlashLoanArbitrageError;
+use crate::state::{Config, OracleEntry, ProfitLedger, ProgramKind, TradeDeadline, Vault};
 
-// Constants for minimum profit threshold and fees
-const MIN_PROFIT_THRESHOLD: u64 = 1000; // Minimum profit in base units
-const FLASH_LOAN_FEE_BPS: u64 = 30; // 0.3% in basis points
+// Loan, profit, slippage and quote age limits are set in the global config
 const BPS_DIVISOR: u64 = 10000;
+// Bounds on the loan sizes `find_optimal_loan` may quote, which sets its compute cost
+const MIN_SEARCH_EVALUATIONS: u8 = 2;
+const MAX_SEARCH_EVALUATIONS: u8 = 64;
+// 1 / golden ratio in parts per million
+const INV_GOLDEN_RATIO_PPM: u128 = 618_034;
 
-pub fn process_flash_loan_and_arbitrage(
-    ctx: Context<FlashLoanAndArbitrage>,
+pub struct SwapConfig {
+    pub expected_out_amount: u64,
+    pub min_out_amount: u64,
+    pub slippage_bps: u64,
+}
+
+/// What `simulate_arbitrage` expects the arbitrage to yield
+///
+/// Returned through the instruction's return data, so clients can decode it from
+/// `simulateTransaction` instead of parsing logs.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub loan_amount: u64,
+    /// Quoted output of leg A, then of leg B
+    pub leg_outputs: [u64; 2],
+    /// How far each leg's output falls short of a swap at the pool's current price, in bps
+    pub price_impact_bps: [u16; 2],
+    pub lender_fee: u64,
+    /// Final amount less the loan, before the lender fee
+    pub gross_profit: i64,
+    /// Gross profit less the lender fee
+    pub net_profit: i64,
+    /// Whether the net profit reaches the requested minimum
+    pub profitable: bool,
+}
+
+/// The most profitable loan size found by `find_optimal_loan`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OptimalLoan {
+    pub loan_amount: u64,
+    pub expected_profit: u64,
+    /// Quoted output of leg A, then of leg B
+    pub leg_outputs: [u64; 2],
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn flash_loan_and_arbitrage<'info>(
+    ctx: Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
     loan_amount: u64,
     min_profit_amount: u64,
+    provider: LendingProvider,
+    leg_a: SwapLegParams,
+    leg_b: SwapLegParams,
+    min_out_leg_a: u64,
+    min_out_leg_b: u64,
+    slippage_bps: Option<u16>,
+    deadline: TradeDeadline,
 ) -> Result<()> {
+    // Nothing runs while the program is paused
+    let config = &ctx.accounts.config;
+    config.check_not_paused()?;
+
+    // Reject unexpected instructions around this one, as far as the config asks. The
+    // repayment leaves the vault in vault mode, so the scan always runs there.
+    guard::check_transaction(
+        config,
+        &ctx.accounts.program_registry,
+        &ctx.accounts.instructions_sysvar,
+        ctx.accounts.lending_program.key,
+        ctx.accounts.vault.is_some(),
+    )?;
+
     // Validate inputs
-    require!(loan_amount > 0, FlashLoanArbitrageError::InvalidLoanAmount);
+    config.check_trade(loan_amount, min_profit_amount)?;
+    config.check_deadline(Clock::get()?.slot, &deadline)?;
+    let slippage_bps = config.slippage_bps(slippage_bps)?;
+    let oracle_prices = oracle_prices(ctx.accounts)?;
+
+    // Ensure DEX A and DEX B are not the same pool. Both legs may use the same
+    // program, e.g. two Whirlpools quoting the pair at different prices.
     require!(
-        min_profit_amount >= MIN_PROFIT_THRESHOLD,
-        FlashLoanArbitrageError::InsufficientProfit
+        ctx.accounts.dex_a_pool.key() != ctx.accounts.dex_b_pool.key(),
+        FlashLoanArbitrageError::SameDexError
     );
 
-    // Record initial balance
+    // Resolve the lender
+    let lender = provider.adapter();
+
+    // In vault mode the strategy being traded, or else the vault, owns the token
+    // accounts and signs the swaps
+    let owner_seeds: Option<Vec<&[u8]>> = match (&ctx.accounts.strategy, &ctx.accounts.vault) {
+        (Some(strategy), _) => Some(strategy.signer_seeds().to_vec()),
+        (None, Some(vault)) => Some(vault.signer_seeds().to_vec()),
+        (None, None) => None,
+    };
+    let signer_seeds: Vec<&[&[u8]]> = owner_seeds.iter().map(|seeds| &seeds[..]).collect();
+
+    // 1. Verify the flash loan that funds this instruction
+    msg!("Verifying flash loan of {} tokens", loan_amount);
+    let borrow_instruction_index =
+        lender.verify_borrow(&lending_accounts(&ctx, provider)?, loan_amount)?;
+
+    // Get initial balance to compare at the end. The verified flash borrow runs
+    // before this instruction, so the balance must already include the loan.
     let initial_balance = ctx.accounts.loan_token_account.amount;
     msg!("Initial balance: {}", initial_balance);
 
-    // Step 1: Initiate flash loan
-    initiate_flash_loan(&ctx, loan_amount)?;
+    let balance_before_loan = initial_balance
+        .checked_sub(loan_amount)
+        .ok_or(FlashLoanArbitrageError::FlashLoanInitFailed)?;
+
+    let [first_leg, second_leg] = arbitrage_legs(&ctx, lender, &leg_a, &leg_b)?;
+
+    // 2. Execute first swap (DEX A)
+    let swap_a_config = swap_config(&first_leg, loan_amount, slippage_bps, min_out_leg_a)?;
+    // Fail with this leg's own error rather than the DEX's when the quote already misses
+    require!(
+        swap_a_config.expected_out_amount >= swap_a_config.min_out_amount,
+        FlashLoanArbitrageError::FirstSwapInsufficientOutput
+    );
+    msg!("Executing swap on DEX A with min output: {}", swap_a_config.min_out_amount);
+    let intermediate_amount = dex::execute_leg(&first_leg, loan_amount, swap_a_config.min_out_amount, &signer_seeds)?;
+    msg!("Intermediate tokens received from first swap: {}", intermediate_amount);
+    
+    // Validate we got enough tokens from the first swap
+    require!(
+        intermediate_amount >= swap_a_config.min_out_amount,
+        FlashLoanArbitrageError::FirstSwapInsufficientOutput
+    );
+    if let Some([loan_price, intermediate_price]) = &oracle_prices {
+        oracle::check_leg(config, loan_price, loan_amount, intermediate_price, intermediate_amount)?;
+    }
+
+    // 3. Execute second swap (DEX B)
+    let swap_b_config = swap_config(&second_leg, intermediate_amount, slippage_bps, min_out_leg_b)?;
+    require!(
+        swap_b_config.expected_out_amount >= swap_b_config.min_out_amount,
+        FlashLoanArbitrageError::SecondSwapInsufficientOutput
+    );
+    msg!("Executing swap on DEX B with min output: {}", swap_b_config.min_out_amount);
+    let output_amount = dex::execute_leg(&second_leg, intermediate_amount, swap_b_config.min_out_amount, &signer_seeds)?;
+    msg!("Loan tokens received from second swap: {}", output_amount);
+    
+    // Validate we got enough tokens from the second swap
+    require!(
+        output_amount >= swap_b_config.min_out_amount,
+        FlashLoanArbitrageError::SecondSwapInsufficientOutput
+    );
+    if let Some([loan_price, intermediate_price]) = &oracle_prices {
+        oracle::check_leg(config, intermediate_price, intermediate_amount, loan_price, output_amount)?;
+    }
+
+    // Get final loan token balance after second swap
+    // Read from the account data, as Anchor's cached balance predates the swaps
+    let final_balance = dex::token_account_amount(ctx.accounts.loan_token_account.as_ref())?;
+    msg!("Final loan token balance: {}", final_balance);
 
-    // Step 2: Execute swap on DEX A
-    execute_dex_a_swap(&ctx, loan_amount)?;
+    // 4. Calculate and validate repayment amount
+    let lending_accounts = lending_accounts(&ctx, provider)?;
+    let repayment_amount = calculate_loan_repayment(
+        loan_amount,
+        lender.flash_loan_fee(&lending_accounts, loan_amount)?,
+    )?;
+    msg!("Repaying flash loan, amount: {}", repayment_amount);
+    
+    // Ensure we have enough tokens for repayment
+    require!(
+        final_balance >= repayment_amount,
+        FlashLoanArbitrageError::FlashLoanRepaymentFailed
+    );
 
-    // Step 3: Execute swap on DEX B
-    let intermediate_amount = ctx.accounts.dex_a_output_token_account.amount;
-    execute_dex_b_swap(&ctx, intermediate_amount)?;
+    // 5. Make sure the lender's flash repay follows this instruction, with no token
+    // instruction between the borrow and the repay
+    let repay_instruction_index =
+        lender.verify_repay(&lending_accounts, loan_amount, borrow_instruction_index)?;
+    guard::check_loan_window(
+        &ctx.accounts.instructions_sysvar,
+        borrow_instruction_index as usize,
+        repay_instruction_index,
+    )?;
 
-    // Step 4: Repay flash loan
-    let repayment_amount = calculate_loan_repayment(loan_amount)?;
-    repay_flash_loan(&ctx, repayment_amount)?;
+    // The verified repay draws from the repayment token account when one is passed
+    if let Some(repayment_token_account) = &ctx.accounts.repayment_token_account {
+        transfer_repayment(
+            arbitrage_swap_authority(ctx.accounts),
+            &signer_seeds,
+            &ctx.accounts.loan_token_account,
+            &ctx.accounts.loan_mint,
+            repayment_token_account,
+            &ctx.accounts.base.token_program,
+            repayment_amount,
+        )?;
+    }
 
-    // Step 5: Verify profit
-    let final_balance = ctx.accounts.loan_token_account.amount;
+    // 6. Calculate profit left over once the repayment has been taken. A trade that
+    // ends below the starting balance made a loss, which is no profit at all.
     let profit = final_balance
-        .checked_sub(initial_balance)
-        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        .checked_sub(repayment_amount)
+        .and_then(|balance| balance.checked_sub(balance_before_loan))
+        .ok_or(FlashLoanArbitrageError::InsufficientProfit)?;
     
+    msg!("Arbitrage profit: {}", profit);
+
+    // Ensure minimum profit is achieved
     require!(
         profit >= min_profit_amount,
         FlashLoanArbitrageError::InsufficientProfit
     );
 
+    // 7. Add the profit to the strategy traded for, less its performance fee
+    if let Some(strategy) = &mut ctx.accounts.strategy {
+        let performance_fee = strategy
+            .credit_profit(profit)
+            .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        msg!("Strategy credited {} profit, performance fee: {}", profit, performance_fee);
+        emit!(StrategyProfitCredited {
+            strategy: strategy.key(),
+            profit,
+            performance_fee,
+            total_assets: strategy.total_assets,
+        });
+    } else if ctx.accounts.vault.is_some() {
+        // Or else to the vault's ledger, which caps what withdrawers may take out
+        credit_vault_profit(&mut ctx.accounts.profit_ledger, profit)?;
+    }
+
     Ok(())
 }
 
-/// Helper function to initiate a flash loan from the lending protocol
-fn initiate_flash_loan(ctx: &Context<FlashLoanAndArbitrage>, amount: u64) -> Result<()> {
-    // Example instruction data for flash loan initialization
-    let mut data = Vec::with_capacity(9);
-    data.push(0); // Instruction code for flash loan
-    data.extend_from_slice(&amount.to_le_bytes());
-    
-    // Create accounts list for the instruction
-    let accounts = &[
-        ctx.accounts.loan_reserve_account.to_account_info(),
-        ctx.accounts.loan_token_account.to_account_info(),
-        ctx.accounts.base.token_program.to_account_info(),
-        ctx.accounts.lending_fee_account.to_account_info(),
-        ctx.accounts.base.authority.to_account_info(),
-    ];
-    
-    // Create the instruction
-    let ix = Instruction {
-        program_id: ctx.accounts.lending_program.key(),
-        accounts: vec![
-            AccountMeta::new(ctx.accounts.loan_reserve_account.key(), false),
-            AccountMeta::new(ctx.accounts.loan_token_account.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.token_program.key(), false),
-            AccountMeta::new(ctx.accounts.lending_fee_account.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.authority.key(), true),
-        ],
-        data,
-    };
-    
-    // Invoke the instruction
-    invoke(&ix, accounts).map_err(|_| FlashLoanArbitrageError::FlashLoanInitFailed.into())
+/// Credits vault-mode profit to the loan mint's profit ledger
+pub(crate) fn credit_vault_profit(profit_ledger: &mut Option<Account<ProfitLedger>>, profit: u64) -> Result<()> {
+    let profit_ledger = profit_ledger
+        .as_mut()
+        .ok_or(FlashLoanArbitrageError::MissingProfitLedger)?;
+    profit_ledger.accrued_profit = profit_ledger
+        .accrued_profit
+        .checked_add(profit)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    msg!("Vault credited {} profit, accrued: {}", profit, profit_ledger.accrued_profit);
+    Ok(())
 }
 
-/// Helper function to execute a swap on DEX A
-fn execute_dex_a_swap(ctx: &Context<FlashLoanAndArbitrage>, amount: u64) -> Result<()> {
-    // Example instruction data for DEX A swap
-    let mut data = Vec::with_capacity(9);
-    data.push(1); // Instruction code for swap
-    data.extend_from_slice(&amount.to_le_bytes());
-    
-    // Create accounts list for the instruction
-    let accounts = &[
-        ctx.accounts.dex_a_pool.to_account_info(),
-        ctx.accounts.dex_a_authority.to_account_info(),
-        ctx.accounts.base.authority.to_account_info(),
-        ctx.accounts.dex_a_input_token_account.to_account_info(),
-        ctx.accounts.dex_a_output_token_account.to_account_info(),
-        ctx.accounts.dex_a_token_a_account.to_account_info(),
-        ctx.accounts.dex_a_token_b_account.to_account_info(),
-        ctx.accounts.base.token_program.to_account_info(),
-    ];
-    
-    // Create the instruction
-    let ix = Instruction {
-        program_id: ctx.accounts.dex_a_program.key(),
-        accounts: vec![
-            AccountMeta::new(ctx.accounts.dex_a_pool.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.dex_a_authority.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.authority.key(), true),
-            AccountMeta::new(ctx.accounts.dex_a_input_token_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_a_output_token_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_a_token_a_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_a_token_b_account.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.token_program.key(), false),
-        ],
-        data,
-    };
-    
-    // Invoke the instruction
-    invoke(&ix, accounts).map_err(|_| FlashLoanArbitrageError::DexSwapFailed.into())
+/// The loan and intermediate mints' oracle prices, when the trade passes their oracles
+///
+/// The prices must be fresh and confident enough for the config before any swap runs.
+fn oracle_prices(accounts: &FlashLoanAndArbitrage) -> Result<Option<[TokenPrice; 2]>> {
+    match (
+        &accounts.loan_oracle_entry,
+        &accounts.loan_oracle,
+        &accounts.intermediate_oracle_entry,
+        &accounts.intermediate_oracle,
+    ) {
+        (None, None, None, None) => Ok(None),
+        (Some(loan_entry), Some(loan_oracle), Some(intermediate_entry), Some(intermediate_oracle)) => {
+            let now = Clock::get()?.unix_timestamp;
+            Ok(Some([
+                token_price(&accounts.config, loan_entry, loan_oracle, now)?,
+                token_price(&accounts.config, intermediate_entry, intermediate_oracle, now)?,
+            ]))
+        }
+        _ => err!(FlashLoanArbitrageError::MissingOracleAccount),
+    }
 }
 
-/// Helper function to execute a swap on DEX B
-fn execute_dex_b_swap(ctx: &Context<FlashLoanAndArbitrage>, amount: u64) -> Result<()> {
-    // Example instruction data for DEX B swap
-    let mut data = Vec::with_capacity(9);
-    data.push(1); // Instruction code for swap
-    data.extend_from_slice(&amount.to_le_bytes());
-    
-    // Create accounts list for the instruction
-    let accounts = &[
-        ctx.accounts.dex_b_pool.to_account_info(),
-        ctx.accounts.dex_b_authority.to_account_info(),
-        ctx.accounts.base.authority.to_account_info(),
-        ctx.accounts.dex_b_input_token_account.to_account_info(),
-        ctx.accounts.dex_b_output_token_account.to_account_info(),
-        ctx.accounts.dex_b_token_a_account.to_account_info(),
-        ctx.accounts.dex_b_token_b_account.to_account_info(),
-        ctx.accounts.base.token_program.to_account_info(),
-    ];
-    
-    // Create the instruction
-    let ix = Instruction {
-        program_id: ctx.accounts.dex_b_program.key(),
-        accounts: vec![
-            AccountMeta::new(ctx.accounts.dex_b_pool.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.dex_b_authority.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.authority.key(), true),
-            AccountMeta::new(ctx.accounts.dex_b_input_token_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_b_output_token_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_b_token_a_account.key(), false),
-            AccountMeta::new(ctx.accounts.dex_b_token_b_account.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.base.token_program.key(), false),
-        ],
-        data,
-    };
-    
-    // Invoke the instruction
-    invoke(&ix, accounts).map_err(|_| FlashLoanArbitrageError::DexSwapFailed.into())
+/// Reads and checks a mint's price from the oracle its entry registers
+fn token_price(config: &Config, entry: &OracleEntry, oracle: &AccountInfo, now: i64) -> Result<TokenPrice> {
+    require!(
+        oracle.key() == entry.oracle,
+        FlashLoanArbitrageError::OracleAccountMismatch
+    );
+    let price = entry.kind.load(oracle)?;
+    price.check(config, now)?;
+    msg!("Oracle price of {}: {} (18 decimals)", entry.mint, price.price);
+    Ok(TokenPrice {
+        price,
+        decimals: entry.decimals,
+    })
 }
 
-/// Helper function to repay the flash loan
-fn repay_flash_loan(ctx: &Context<FlashLoanAndArbitrage>, amount: u64) -> Result<()> {
-    // Transfer from loan token account to reserve account
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.loan_token_account.to_account_info(),
-        to: ctx.accounts.loan_reserve_account.to_account_info(),
-        authority: ctx.accounts.base.authority.to_account_info(),
-    };
-    
-    let cpi_program = ctx.accounts.base.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    
-    token::transfer(cpi_ctx, amount).map_err(|_| FlashLoanArbitrageError::FlashLoanRepaymentFailed.into())
+/// The account that signs the swaps: the vault in vault mode, otherwise the authority
+pub(crate) fn swap_authority<'a, 'info>(
+    authority: &'a Signer<'info>,
+    vault: &'a Option<Account<'info, Vault>>,
+) -> &'a AccountInfo<'info> {
+    match vault {
+        Some(vault) => vault.as_ref(),
+        None => authority.as_ref(),
+    }
 }
 
-/// Helper function to calculate the flash loan repayment amount including fees
-fn calculate_loan_repayment(principal: u64) -> Result<u64> {
-    // Calculate fee: principal * (FLASH_LOAN_FEE_BPS / BPS_DIVISOR)
-    let fee = principal
-        .checked_mul(FLASH_LOAN_FEE_BPS)
+/// The account that signs the arbitrage's swaps: the strategy when one is traded,
+/// otherwise as for `swap_authority`
+fn arbitrage_swap_authority<'a, 'info>(accounts: &'a FlashLoanAndArbitrage<'info>) -> &'a AccountInfo<'info> {
+    match &accounts.strategy {
+        Some(strategy) => strategy.as_ref().as_ref(),
+        None => swap_authority(&accounts.base.authority, &accounts.vault),
+    }
+}
+
+/// Moves exactly the repayment from the loan token account to the account the
+/// lender's repay draws from
+///
+/// The lender's repay is a top-level instruction a PDA cannot sign, so in vault mode
+/// it draws from a token account of the authority instead. Nothing is delegated, so
+/// the authority never gets more of the PDA's tokens than the verified repay takes.
+pub(crate) fn transfer_repayment<'info>(
+    owner: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    loan_token_account: &InterfaceAccount<'info, TokenAccount>,
+    loan_mint: &InterfaceAccount<'info, Mint>,
+    repayment_token_account: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    repayment_amount: u64,
+) -> Result<()> {
+    msg!("Moving repayment of {} from {}", repayment_amount, owner.key());
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: loan_token_account.to_account_info(),
+                mint: loan_mint.to_account_info(),
+                to: repayment_token_account.to_account_info(),
+                authority: owner.clone(),
+            },
+            signer_seeds,
+        ),
+        repayment_amount,
+        loan_mint.decimals,
+    )
+}
+
+/// Calculates the minimum amount with slippage tolerance
+fn calculate_min_amount_with_slippage(amount: u64, slippage_bps: u64) -> u64 {
+    let slippage = amount
+        .checked_mul(slippage_bps)
         .unwrap_or(0)
         .checked_div(BPS_DIVISOR)
         .unwrap_or(0);
     
+    amount.checked_sub(slippage).unwrap_or(amount)
+}
+
+/// Derives the minimum output of a leg from its quote and the caller's bound
+///
+/// The minimum is the caller's `min_out_amount` or the quote less the slippage,
+/// whichever is higher, and goes into the DEX instruction as its output threshold.
+pub(crate) fn swap_config(leg: &Leg, amount_in: u64, slippage_bps: u16, min_out_amount: u64) -> Result<SwapConfig> {
+    let expected_out_amount = leg.quote(amount_in)?;
+    let slippage_bps = slippage_bps as u64;
+    Ok(SwapConfig {
+        expected_out_amount,
+        min_out_amount: calculate_min_amount_with_slippage(expected_out_amount, slippage_bps)
+            .max(min_out_amount),
+        slippage_bps,
+    })
+}
+
+/// Collects the accounts the lending adapter needs, taking its lender-specific
+/// accounts from the front of `remaining_accounts`
+///
+/// Fails unless the lending program is approved for `provider`.
+fn lending_accounts<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+    provider: LendingProvider,
+) -> Result<LendingAccounts<'a, 'info>> {
+    ctx.accounts
+        .program_registry
+        .check_approved(&ctx.accounts.lending_program.key(), ProgramKind::Lender(provider))?;
+
+    let count = provider.adapter().remaining_accounts_len();
+    require!(
+        ctx.remaining_accounts.len() >= count,
+        FlashLoanArbitrageError::InvalidLendingAccounts
+    );
+
+    Ok(LendingAccounts {
+        lending_program: &ctx.accounts.lending_program,
+        reserve: &ctx.accounts.loan_reserve_account,
+        fee_receiver: &ctx.accounts.lending_fee_account,
+        loan_token_account: ctx.accounts.loan_token_account.as_ref(),
+        repay_source: repay_source(&ctx.accounts.loan_token_account, &ctx.accounts.repayment_token_account),
+        instructions_sysvar: &ctx.accounts.instructions_sysvar,
+        remaining_accounts: &ctx.remaining_accounts[..count],
+    })
+}
+
+/// The account the lender's repay must draw from: the repayment token account when one
+/// is passed, otherwise the loan token account
+pub(crate) fn repay_source<'a, 'info>(
+    loan_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    repayment_token_account: &'a Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+) -> &'a AccountInfo<'info> {
+    match repayment_token_account {
+        Some(repayment_token_account) => repayment_token_account.as_ref().as_ref(),
+        None => loan_token_account.as_ref(),
+    }
+}
+
+/// Splits the leg accounts that follow the lender's in `remaining_accounts`
+fn leg_accounts<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+    lender: &dyn LendingAdapter,
+    leg_a: &SwapLegParams,
+    leg_b: &SwapLegParams,
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let leg_a_start = lender.remaining_accounts_len();
+    let leg_b_start = leg_a_start + leg_a.remaining_accounts_len as usize;
+    let leg_b_end = leg_b_start + leg_b.remaining_accounts_len as usize;
+    require!(
+        ctx.remaining_accounts.len() >= leg_b_end,
+        FlashLoanArbitrageError::InvalidDexAccounts
+    );
+
+    Ok((
+        &ctx.remaining_accounts[leg_a_start..leg_b_start],
+        &ctx.remaining_accounts[leg_b_start..leg_b_end],
+    ))
+}
+
+/// Describes the two legs of the arbitrage: DEX A then DEX B
+///
+/// Fails unless both DEX programs are approved for their leg's DEX and both pools match
+/// their registry entries.
+fn arbitrage_legs<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+    lender: &dyn LendingAdapter,
+    leg_a: &SwapLegParams,
+    leg_b: &SwapLegParams,
+) -> Result<[Leg<'a, 'info>; 2]> {
+    let (leg_a_accounts, leg_b_accounts) = leg_accounts(ctx, lender, leg_a, leg_b)?;
+    let accounts = &ctx.accounts;
+    let user_authority = arbitrage_swap_authority(accounts);
+
+    let first_leg = Leg::new(
+        leg_a,
+        SwapAccounts {
+            dex_program: &accounts.dex_a_program,
+            pool: &accounts.dex_a_pool,
+            pool_authority: &accounts.dex_a_authority,
+            user_authority,
+            input_token_account: accounts.dex_a_input_token_account.as_ref(),
+            output_token_account: accounts.dex_a_output_token_account.as_ref(),
+            pool_token_a_account: accounts.dex_a_token_a_account.as_ref(),
+            pool_token_b_account: accounts.dex_a_token_b_account.as_ref(),
+            token_program: accounts.base.token_program.as_ref(),
+            remaining_accounts: leg_a_accounts,
+        },
+    )?;
+    let second_leg = Leg::new(
+        leg_b,
+        SwapAccounts {
+            dex_program: &accounts.dex_b_program,
+            pool: &accounts.dex_b_pool,
+            pool_authority: &accounts.dex_b_authority,
+            user_authority,
+            input_token_account: accounts.dex_b_input_token_account.as_ref(),
+            output_token_account: accounts.dex_b_output_token_account.as_ref(),
+            pool_token_a_account: accounts.dex_b_token_a_account.as_ref(),
+            pool_token_b_account: accounts.dex_b_token_b_account.as_ref(),
+            token_program: accounts.base.token_program.as_ref(),
+            remaining_accounts: leg_b_accounts,
+        },
+    )?;
+
+    for leg in [&first_leg, &second_leg] {
+        accounts
+            .program_registry
+            .check_approved(&leg.program_id, ProgramKind::Dex(leg.dex))?;
+    }
+    accounts.dex_a_pool_entry.check_leg(&first_leg)?;
+    accounts.dex_b_pool_entry.check_leg(&second_leg)?;
+
+    Ok([first_leg, second_leg])
+}
+
+/// Helper function to calculate the flash loan repayment amount including fees
+pub(crate) fn calculate_loan_repayment(principal: u64, fee: u64) -> Result<u64> {
     // Calculate total repayment: principal + fee
-    let repayment = principal.checked_add(fee).unwrap_or(principal);
+    principal
+        .checked_add(fee)
+        .ok_or(FlashLoanArbitrageError::MathOverflow.into())
+}
+
+/// Public function to simulate an arbitrage transaction and report how profitable it would be
+///
+/// Unprofitable trades are reported rather than rejected; errors mean the legs could
+/// not be quoted.
+pub fn simulate_arbitrage<'info>(
+    ctx: &Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+    loan_amount: u64,
+    min_profit_amount: u64,
+    provider: LendingProvider,
+    leg_a: SwapLegParams,
+    leg_b: SwapLegParams,
+) -> Result<SimulationReport> {
+    // Simulate flash loan fee
+    let lender = provider.adapter();
+    let lending_accounts = lending_accounts(ctx, provider)?;
+    let lender_fee = lender.flash_loan_fee(&lending_accounts, loan_amount)?;
+    
+    let [first_leg, second_leg] = arbitrage_legs(ctx, lender, &leg_a, &leg_b)?;
+
+    // Simulate DEX A swap
+    let intermediate_amount = first_leg.quote(loan_amount)?;
+    let impact_a = dex::math::price_impact_bps(first_leg.spot_quote(loan_amount)?, intermediate_amount);
     
-    Ok(repayment)
-} 
\ No newline at end of file
+    // Simulate DEX B swap
+    let final_amount = second_leg.quote(intermediate_amount)?;
+    let impact_b = dex::math::price_impact_bps(second_leg.spot_quote(intermediate_amount)?, final_amount);
+    
+    // Calculate estimated profit, which may be a loss
+    let gross_profit = final_amount as i128 - loan_amount as i128;
+    let net_profit = gross_profit - lender_fee as i128;
+    
+    Ok(SimulationReport {
+        loan_amount,
+        leg_outputs: [intermediate_amount, final_amount],
+        price_impact_bps: [impact_a, impact_b],
+        lender_fee,
+        gross_profit: i64::try_from(gross_profit).map_err(|_| FlashLoanArbitrageError::MathOverflow)?,
+        net_profit: i64::try_from(net_profit).map_err(|_| FlashLoanArbitrageError::MathOverflow)?,
+        profitable: net_profit >= min_profit_amount as i128,
+    })
+}
+
+/// Searches for the loan size that maximizes the simulated arbitrage profit
+///
+/// Runs a golden-section search between the config's minimum and maximum loan, quoting
+/// both legs at each candidate size. Each evaluation costs two pool quotes, so
+/// `max_evaluations` bounds the compute used. Sizes the pools cannot quote, such as a
+/// Whirlpool swap running past its tick arrays, count as unprofitable.
+pub fn find_optimal_loan<'info>(
+    ctx: &Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+    min_profit_amount: u64,
+    provider: LendingProvider,
+    leg_a: SwapLegParams,
+    leg_b: SwapLegParams,
+    max_evaluations: u8,
+) -> Result<OptimalLoan> {
+    require!(
+        (MIN_SEARCH_EVALUATIONS..=MAX_SEARCH_EVALUATIONS).contains(&max_evaluations),
+        FlashLoanArbitrageError::InvalidSearchBudget
+    );
+
+    let lender = provider.adapter();
+    let lending_accounts = lending_accounts(ctx, provider)?;
+    let [first_leg, second_leg] = arbitrage_legs(ctx, lender, &leg_a, &leg_b)?;
+
+    let mut best: Option<(i128, OptimalLoan)> = None;
+    let config = &ctx.accounts.config;
+    golden_section_max(config.min_loan_amount, config.max_loan_amount, max_evaluations, |loan_amount| {
+        // A size that cannot be quoted, or whose fee cannot be, scores below the rest
+        let quote = || -> Result<([u64; 2], u64)> {
+            let intermediate_amount = first_leg.quote(loan_amount)?;
+            let leg_outputs = [intermediate_amount, second_leg.quote(intermediate_amount)?];
+            let fee = lender.flash_loan_fee(&lending_accounts, loan_amount)?;
+            Ok((leg_outputs, calculate_loan_repayment(loan_amount, fee)?))
+        };
+        let (leg_outputs, repayment_amount) = match quote() {
+            Ok(quoted) => quoted,
+            Err(_) => return Ok(None),
+        };
+
+        // Losses are kept so the search can still tell which way profit grows
+        let profit = leg_outputs[1] as i128 - repayment_amount as i128;
+        let improves = match best {
+            Some((best_profit, _)) => profit > best_profit,
+            None => true,
+        };
+        if improves {
+            best = Some((
+                profit,
+                OptimalLoan {
+                    loan_amount,
+                    expected_profit: u64::try_from(profit).unwrap_or(0),
+                    leg_outputs,
+                },
+            ));
+        }
+        Ok(Some(profit))
+    })?;
+
+    let (_, optimal_loan) = best.ok_or(FlashLoanArbitrageError::InsufficientProfit)?;
+    msg!(
+        "Optimal loan: {}, expected profit: {}",
+        optimal_loan.loan_amount,
+        optimal_loan.expected_profit
+    );
+
+    require!(
+        optimal_loan.expected_profit >= min_profit_amount,
+        FlashLoanArbitrageError::InsufficientProfit
+    );
+
+    Ok(optimal_loan)
+}
+
+/// Part of the interval `[low, high]` that golden-section search keeps per step
+fn golden_span(low: u64, high: u64) -> u64 {
+    ((high - low) as u128 * INV_GOLDEN_RATIO_PPM / 1_000_000) as u64
+}
+
+/// Golden-section search for the maximum of `score` over `[low, high]`
+///
+/// Assumes `score` is unimodal, as arbitrage profit is: it rises with the loan until
+/// price impact eats the spread. `None` scores below every quoted size. Stops after
+/// `max_evaluations` calls or once the interval can no longer be narrowed.
+fn golden_section_max<F>(mut low: u64, mut high: u64, max_evaluations: u8, mut score: F) -> Result<()>
+where
+    F: FnMut(u64) -> Result<Option<i128>>,
+{
+    let mut left = high - golden_span(low, high);
+    let mut right = low + golden_span(low, high);
+    let mut left_score = score(left)?;
+    let mut right_score = score(right)?;
+
+    let mut evaluations = 2;
+    while evaluations < max_evaluations && left < right {
+        if left_score < right_score {
+            // The maximum lies right of `left`
+            low = left;
+            left = right;
+            left_score = right_score;
+            right = low + golden_span(low, high);
+            if right <= left {
+                break;
+            }
+            right_score = score(right)?;
+        } else {
+            // The maximum lies left of `right`
+            high = right;
+            right = left;
+            right_score = left_score;
+            left = high - golden_span(low, high);
+            if left >= right {
+                break;
+            }
+            left_score = score(left)?;
+        }
+        evaluations += 1;
+    }
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::{self, split, Leg, RouteLeg};
+use crate::errors::FlashLoanArbitrageError;
+use crate::guard;
+use crate::instructions::flash_loan::{
+    calculate_loan_repayment, credit_vault_profit, repay_source, swap_authority, swap_config,
+    transfer_repayment,
+};
+use crate::lending::{LendingAccounts, LendingAdapter, LendingProvider};
+use crate::state::accounts::{token_owner, FlashLoanRoute};
+use crate::state::{PoolEntry, ProgramKind, TradeDeadline};
+
+// Bounds on the number of swaps in a route
+const MIN_ROUTE_LEGS: usize = 2;
+const MAX_ROUTE_LEGS: usize = 5;
+// Bound on the number of pools one leg can split its input across
+const MAX_SPLITS_PER_LEG: usize = 4;
+
+pub fn flash_loan_route<'info>(
+    ctx: Context<'_, '_, '_, 'info, FlashLoanRoute<'info>>,
+    loan_amount: u64,
+    min_profit_amount: u64,
+    provider: LendingProvider,
+    route: Vec<RouteLeg>,
+    slippage_bps: Option<u16>,
+    deadline: TradeDeadline,
+) -> Result<()> {
+    // Nothing runs while the program is paused
+    let config = &ctx.accounts.config;
+    config.check_not_paused()?;
+
+    // Reject unexpected instructions around this one, as far as the config asks. The
+    // repayment leaves the vault in vault mode, so the scan always runs there.
+    guard::check_transaction(
+        config,
+        &ctx.accounts.program_registry,
+        &ctx.accounts.instructions_sysvar,
+        ctx.accounts.lending_program.key,
+        ctx.accounts.vault.is_some(),
+    )?;
+
+    // Validate inputs
+    config.check_trade(loan_amount, min_profit_amount)?;
+    config.check_deadline(Clock::get()?.slot, &deadline)?;
+    let slippage_bps = config.slippage_bps(slippage_bps)?;
+    require!(
+        (MIN_ROUTE_LEGS..=MAX_ROUTE_LEGS).contains(&route.len()),
+        FlashLoanArbitrageError::InvalidRouteLength
+    );
+
+    // Resolve the lender
+    let lender = provider.adapter();
+
+    // In vault mode the vault owns the token accounts and signs the swaps
+    let vault_seeds = ctx.accounts.vault.as_ref().map(|vault| vault.signer_seeds());
+    let signer_seeds: Vec<&[&[u8]]> = vault_seeds.iter().map(|seeds| &seeds[..]).collect();
+
+    // 1. Verify the flash loan that funds this instruction
+    msg!("Verifying flash loan of {} tokens", loan_amount);
+    let lending_accounts = lending_accounts(&ctx, provider)?;
+    let borrow_instruction_index = lender.verify_borrow(&lending_accounts, loan_amount)?;
+
+    // The verified flash borrow runs before this instruction, so the balance
+    // must already include the loan
+    let initial_balance = ctx.accounts.loan_token_account.amount;
+    msg!("Initial balance: {}", initial_balance);
+
+    let balance_before_loan = initial_balance
+        .checked_sub(loan_amount)
+        .ok_or(FlashLoanArbitrageError::FlashLoanInitFailed)?;
+
+    // 2. Check the route is a closed cycle before moving any funds
+    let legs = route_legs(&ctx, lender, &route)?;
+    check_closed_cycle(&ctx, &legs)?;
+
+    // 3. Execute every leg, feeding each output into the next leg
+    let mut amount = loan_amount;
+    for (index, (pools, route_leg)) in legs.iter().zip(route.iter()).enumerate() {
+        let weights: Vec<u16> = route_leg.splits.iter().map(|pool| pool.weight_bps).collect();
+        let mut out_amount: u64 = 0;
+
+        for (pool_index, (pool, pool_split)) in pools.iter().zip(route_leg.splits.iter()).enumerate() {
+            let pool_amount = split::split_amount(amount, &weights, pool_index)
+                .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+            if pool_amount == 0 {
+                continue;
+            }
+
+            // Each pool must deliver its quote less the slippage, or the caller's
+            // minimum when that is higher
+            let pool_config = swap_config(pool, pool_amount, slippage_bps, pool_split.min_out_amount)?;
+            require!(
+                pool_config.expected_out_amount >= pool_config.min_out_amount,
+                FlashLoanArbitrageError::RouteLegInsufficientOutput
+            );
+            msg!(
+                "Executing leg {} on {:?} with {} tokens, min output: {}",
+                index,
+                pool.dex,
+                pool_amount,
+                pool_config.min_out_amount
+            );
+            let pool_out = dex::execute_leg(pool, pool_amount, pool_config.min_out_amount, &signer_seeds)?;
+            require!(
+                pool_out >= pool_config.min_out_amount,
+                FlashLoanArbitrageError::RouteLegInsufficientOutput
+            );
+            out_amount = out_amount
+                .checked_add(pool_out)
+                .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        }
+        msg!("Tokens received from leg {}: {}", index, out_amount);
+
+        // The leg as a whole must also meet its minimum
+        require!(
+            out_amount >= route_leg.min_out_amount,
+            FlashLoanArbitrageError::RouteLegInsufficientOutput
+        );
+        amount = out_amount;
+    }
+
+    // Read from the account data, as Anchor's cached balance predates the swaps
+    let final_balance = dex::token_account_amount(ctx.accounts.loan_token_account.as_ref())?;
+    msg!("Final loan token balance: {}", final_balance);
+
+    // 4. Calculate and validate repayment amount
+    let repayment_amount = calculate_loan_repayment(
+        loan_amount,
+        lender.flash_loan_fee(&lending_accounts, loan_amount)?,
+    )?;
+    msg!("Repaying flash loan, amount: {}", repayment_amount);
+
+    require!(
+        final_balance >= repayment_amount,
+        FlashLoanArbitrageError::FlashLoanRepaymentFailed
+    );
+
+    // 5. Make sure the lender's flash repay follows this instruction, with no token
+    // instruction between the borrow and the repay
+    let repay_instruction_index =
+        lender.verify_repay(&lending_accounts, loan_amount, borrow_instruction_index)?;
+    guard::check_loan_window(
+        &ctx.accounts.instructions_sysvar,
+        borrow_instruction_index as usize,
+        repay_instruction_index,
+    )?;
+
+    // The verified repay draws from the repayment token account when one is passed
+    if let Some(repayment_token_account) = &ctx.accounts.repayment_token_account {
+        transfer_repayment(
+            swap_authority(&ctx.accounts.base.authority, &ctx.accounts.vault),
+            &signer_seeds,
+            &ctx.accounts.loan_token_account,
+            &ctx.accounts.loan_mint,
+            repayment_token_account,
+            &ctx.accounts.base.token_program,
+            repayment_amount,
+        )?;
+    }
+
+    // 6. Calculate profit left over once the repayment has been taken. A trade that
+    // ends below the starting balance made a loss, which is no profit at all.
+    let profit = final_balance
+        .checked_sub(repayment_amount)
+        .and_then(|balance| balance.checked_sub(balance_before_loan))
+        .ok_or(FlashLoanArbitrageError::InsufficientProfit)?;
+
+    msg!("Route profit: {}", profit);
+
+    require!(
+        profit >= min_profit_amount,
+        FlashLoanArbitrageError::InsufficientProfit
+    );
+
+    // 7. Credit vault-mode profit to the loan mint's ledger
+    if ctx.accounts.vault.is_some() {
+        credit_vault_profit(&mut ctx.accounts.profit_ledger, profit)?;
+    }
+
+    Ok(())
+}
+
+/// Collects the accounts the lending adapter needs, taking its lender-specific
+/// accounts from the front of `remaining_accounts`
+///
+/// Fails unless the lending program is approved for `provider`.
+fn lending_accounts<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, FlashLoanRoute<'info>>,
+    provider: LendingProvider,
+) -> Result<LendingAccounts<'a, 'info>> {
+    ctx.accounts
+        .program_registry
+        .check_approved(&ctx.accounts.lending_program.key(), ProgramKind::Lender(provider))?;
+
+    let count = provider.adapter().remaining_accounts_len();
+    require!(
+        ctx.remaining_accounts.len() >= count,
+        FlashLoanArbitrageError::InvalidLendingAccounts
+    );
+
+    Ok(LendingAccounts {
+        lending_program: &ctx.accounts.lending_program,
+        reserve: &ctx.accounts.loan_reserve_account,
+        fee_receiver: &ctx.accounts.lending_fee_account,
+        loan_token_account: ctx.accounts.loan_token_account.as_ref(),
+        repay_source: repay_source(&ctx.accounts.loan_token_account, &ctx.accounts.repayment_token_account),
+        instructions_sysvar: &ctx.accounts.instructions_sysvar,
+        remaining_accounts: &ctx.remaining_accounts[..count],
+    })
+}
+
+/// Describes the pools of each leg of the route from their ranges of `remaining_accounts`
+///
+/// Fails unless every pool's program is approved for its DEX and every pool matches
+/// its registry entry.
+fn route_legs<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, FlashLoanRoute<'info>>,
+    lender: &dyn LendingAdapter,
+    route: &[RouteLeg],
+) -> Result<Vec<Vec<Leg<'a, 'info>>>> {
+    route
+        .iter()
+        .map(|route_leg| {
+            let total_weight: u64 = route_leg.splits.iter().map(|pool| pool.weight_bps as u64).sum();
+            require!(
+                (1..=MAX_SPLITS_PER_LEG).contains(&route_leg.splits.len())
+                    && total_weight == split::WEIGHT_DENOMINATOR,
+                FlashLoanArbitrageError::InvalidSplitWeights
+            );
+
+            route_leg
+                .splits
+                .iter()
+                .map(|pool_split| {
+                    // Leg accounts may not reuse the lender's accounts
+                    require!(
+                        pool_split.accounts_start as usize >= lender.remaining_accounts_len(),
+                        FlashLoanArbitrageError::InvalidDexAccounts
+                    );
+                    let leg = Leg::from_split(
+                        pool_split,
+                        ctx.remaining_accounts,
+                        swap_authority(&ctx.accounts.base.authority, &ctx.accounts.vault),
+                        ctx.accounts.base.token_program.as_ref(),
+                    )?;
+                    ctx.accounts
+                        .program_registry
+                        .check_approved(&leg.program_id, ProgramKind::Dex(leg.dex))?;
+
+                    // Only this program can create pool entries, so the entry's own
+                    // pool field ties it to the leg
+                    let pool_entry = Account::<PoolEntry>::try_from(
+                        &ctx.remaining_accounts[pool_split.accounts_start as usize + dex::ROUTE_LEG_POOL_ENTRY],
+                    )?;
+                    pool_entry.check_leg(&leg)?;
+                    Ok(leg)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Checks the route starts and ends in the loan token account, each leg spends from
+/// the token account the previous one paid into, and every user token account belongs
+/// to the authority, or to the vault in vault mode
+///
+/// All pools of a leg must swap between the same pair of user token accounts.
+fn check_closed_cycle(ctx: &Context<FlashLoanRoute>, legs: &[Vec<Leg>]) -> Result<()> {
+    let loan_token_account = ctx.accounts.loan_token_account.key();
+    let token_owner = token_owner(&ctx.accounts.base.authority, &ctx.accounts.vault);
+
+    for pools in legs {
+        let first_pool = pools.first().ok_or(FlashLoanArbitrageError::InvalidSplitWeights)?;
+        for pool in pools {
+            require!(
+                pool.accounts.input_token_account.key() == first_pool.accounts.input_token_account.key()
+                    && pool.accounts.output_token_account.key()
+                        == first_pool.accounts.output_token_account.key(),
+                FlashLoanArbitrageError::RouteNotClosed
+            );
+        }
+    }
+    // Every leg has at least one pool, so each leg is represented by its first
+    let legs: Vec<&Leg> = legs.iter().filter_map(|pools| pools.first()).collect();
+
+    let (first_leg, last_leg) = match (legs.first(), legs.last()) {
+        (Some(first_leg), Some(last_leg)) => (first_leg, last_leg),
+        _ => return err!(FlashLoanArbitrageError::InvalidRouteLength),
+    };
+    require!(
+        first_leg.accounts.input_token_account.key() == loan_token_account
+            && last_leg.accounts.output_token_account.key() == loan_token_account,
+        FlashLoanArbitrageError::RouteNotClosed
+    );
+
+    // Each leg spends the very account the previous leg paid into, so no output can
+    // land in an account the route then leaves alone
+    for pair in legs.windows(2) {
+        require!(
+            pair[0].accounts.output_token_account.key() == pair[1].accounts.input_token_account.key(),
+            FlashLoanArbitrageError::RouteNotClosed
+        );
+    }
+
+    for leg in legs {
+        for token_account in [leg.accounts.input_token_account, leg.accounts.output_token_account] {
+            require!(
+                dex::token_account_owner(token_account)? == token_owner,
+                FlashLoanArbitrageError::TokenAccountOwnerMismatch
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,19 @@
+pub mod config;
 pub mod flash_loan;
+pub mod flash_loan_route;
+pub mod operator;
+pub mod oracle_registry;
+pub mod pool_registry;
+pub mod program_registry;
+pub mod strategy;
+pub mod vault;
 
-pub use flash_loan::*; 
\ No newline at end of file
+pub use config::*;
+pub use flash_loan::*;
+pub use flash_loan_route::*;
+pub use operator::*;
+pub use oracle_registry::*;
+pub use pool_registry::*;
+pub use program_registry::*;
+pub use strategy::*;
+pub use vault::*;
\ No newline at end of file
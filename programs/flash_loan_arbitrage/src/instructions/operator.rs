@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::FlashLoanArbitrageError;
+use crate::events::OperatorUpdated;
+use crate::state::accounts::{AddOperator, RemoveOperator};
+use crate::state::Role;
+
+pub fn add_operator(ctx: Context<AddOperator>, operator: Pubkey, role: Role) -> Result<()> {
+    let operator_role = &mut ctx.accounts.operator_role;
+    operator_role.operator = operator;
+    operator_role.role = role;
+    operator_role.bump = *ctx.bumps.get("operator_role").unwrap();
+
+    msg!("Granted {:?} to {}", role, operator);
+    emit!(OperatorUpdated {
+        operator,
+        role,
+        granted: true,
+    });
+    Ok(())
+}
+
+pub fn remove_operator(ctx: Context<RemoveOperator>, operator: Pubkey, role: Role) -> Result<()> {
+    // Keeps an admin from locking everyone out by mistake
+    require!(
+        !(role == Role::Admin && operator == ctx.accounts.admin.key()),
+        FlashLoanArbitrageError::CannotRemoveOwnAdminRole
+    );
+
+    msg!("Revoked {:?} from {}", role, operator);
+    emit!(OperatorUpdated {
+        operator,
+        role,
+        granted: false,
+    });
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::events::OracleRegistryUpdated;
+use crate::oracle::OracleKind;
+use crate::state::accounts::{DeregisterOracle, RegisterOracle};
+
+pub fn register_oracle(ctx: Context<RegisterOracle>, kind: OracleKind) -> Result<()> {
+    // The feed must hold a price of its kind
+    let price = kind.load(&ctx.accounts.oracle)?;
+    msg!("Oracle price: {} (18 decimals)", price.price);
+
+    let mint = ctx.accounts.mint.key();
+    let oracle = ctx.accounts.oracle.key();
+    let oracle_entry = &mut ctx.accounts.oracle_entry;
+    oracle_entry.mint = mint;
+    oracle_entry.kind = kind;
+    oracle_entry.oracle = oracle;
+    oracle_entry.decimals = ctx.accounts.mint.decimals;
+    oracle_entry.bump = *ctx.bumps.get("oracle_entry").unwrap();
+
+    msg!("Registered {:?} oracle {} for mint {}", kind, oracle, mint);
+    emit!(OracleRegistryUpdated {
+        mint,
+        oracle,
+        kind,
+        registered: true,
+    });
+    Ok(())
+}
+
+pub fn deregister_oracle(ctx: Context<DeregisterOracle>) -> Result<()> {
+    let oracle_entry = &ctx.accounts.oracle_entry;
+
+    msg!("Deregistered {:?} oracle {} for mint {}", oracle_entry.kind, oracle_entry.oracle, oracle_entry.mint);
+    emit!(OracleRegistryUpdated {
+        mint: oracle_entry.mint,
+        oracle: oracle_entry.oracle,
+        kind: oracle_entry.kind,
+        registered: false,
+    });
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::{self, DexKind};
+use crate::errors::FlashLoanArbitrageError;
+use crate::events::PoolRegistryUpdated;
+use crate::state::accounts::{DeregisterPool, RegisterPool};
+use crate::state::ProgramKind;
+
+pub fn register_pool(ctx: Context<RegisterPool>, dex: DexKind, fee_rate: u32) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let dex_program = accounts.dex_program.key();
+    accounts
+        .program_registry
+        .check_approved(&dex_program, ProgramKind::Dex(dex))?;
+    require!(
+        *accounts.pool.owner == dex_program,
+        FlashLoanArbitrageError::DexPoolOwnerMismatch
+    );
+
+    // The vaults must be the ones the pool itself records
+    let (vault_a, vault_b) = dex.adapter().pool_vaults(&accounts.pool)?;
+    require!(
+        vault_a == accounts.pool_token_a_account.key() && vault_b == accounts.pool_token_b_account.key(),
+        FlashLoanArbitrageError::InvalidPoolAccount
+    );
+    let mint_a = dex::token_account_mint(&accounts.pool_token_a_account)?;
+    let mint_b = dex::token_account_mint(&accounts.pool_token_b_account)?;
+
+    let pool = accounts.pool.key();
+    let pool_entry = &mut ctx.accounts.pool_entry;
+    pool_entry.pool = pool;
+    pool_entry.dex = dex;
+    pool_entry.vault_a = vault_a;
+    pool_entry.vault_b = vault_b;
+    pool_entry.mint_a = mint_a;
+    pool_entry.mint_b = mint_b;
+    pool_entry.fee_rate = fee_rate;
+    pool_entry.bump = *ctx.bumps.get("pool_entry").unwrap();
+
+    msg!("Registered {:?} pool {}", dex, pool);
+    emit!(PoolRegistryUpdated {
+        pool,
+        dex,
+        registered: true,
+    });
+    Ok(())
+}
+
+pub fn deregister_pool(ctx: Context<DeregisterPool>) -> Result<()> {
+    let pool_entry = &ctx.accounts.pool_entry;
+
+    msg!("Deregistered {:?} pool {}", pool_entry.dex, pool_entry.pool);
+    emit!(PoolRegistryUpdated {
+        pool: pool_entry.pool,
+        dex: pool_entry.dex,
+        registered: false,
+    });
+    Ok(())
+}
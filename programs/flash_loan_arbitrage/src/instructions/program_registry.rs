@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::events::ProgramRegistryUpdated;
+use crate::state::accounts::{InitializeProgramRegistry, UpdateProgramRegistry};
+use crate::state::{ApprovedProgram, ProgramKind};
+
+pub fn initialize_program_registry(ctx: Context<InitializeProgramRegistry>) -> Result<()> {
+    let program_registry = &mut ctx.accounts.program_registry;
+    program_registry.programs = Vec::new();
+    program_registry.bump = *ctx.bumps.get("program_registry").unwrap();
+
+    msg!("Program registry initialized");
+    Ok(())
+}
+
+pub fn approve_program(ctx: Context<UpdateProgramRegistry>, program_id: Pubkey, kind: ProgramKind) -> Result<()> {
+    ctx.accounts
+        .program_registry
+        .approve(ApprovedProgram { program_id, kind })?;
+
+    msg!("Approved program {} for {:?}", program_id, kind);
+    emit!(ProgramRegistryUpdated {
+        program_id,
+        kind,
+        approved: true,
+    });
+    Ok(())
+}
+
+pub fn revoke_program(ctx: Context<UpdateProgramRegistry>, program_id: Pubkey, kind: ProgramKind) -> Result<()> {
+    ctx.accounts
+        .program_registry
+        .revoke(ApprovedProgram { program_id, kind })?;
+
+    msg!("Revoked program {} for {:?}", program_id, kind);
+    emit!(ProgramRegistryUpdated {
+        program_id,
+        kind,
+        approved: false,
+    });
+    Ok(())
+}
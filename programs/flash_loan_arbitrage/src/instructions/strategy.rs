@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, MintTo, TransferChecked};
+
+use crate::dex;
+use crate::errors::FlashLoanArbitrageError;
+use crate::events::{StrategyDeposited, StrategyFeesCollected, StrategyWithdrawn};
+use crate::state::accounts::{CollectFees, Deposit, InitializeStrategy, Withdraw};
+
+pub fn initialize_strategy(ctx: Context<InitializeStrategy>, performance_fee_bps: u16) -> Result<()> {
+    require!(performance_fee_bps <= 10_000, FlashLoanArbitrageError::InvalidConfig);
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.mint = ctx.accounts.mint.key();
+    strategy.assets_token_account = ctx.accounts.assets_token_account.key();
+    strategy.share_mint = ctx.accounts.share_mint.key();
+    strategy.treasury_token_account = ctx.accounts.treasury_token_account.key();
+    strategy.performance_fee_bps = performance_fee_bps;
+    strategy.total_assets = 0;
+    strategy.accrued_fees = 0;
+    strategy.bump = *ctx.bumps.get("strategy").unwrap();
+
+    msg!("Strategy initialized for mint {}", strategy.mint);
+    Ok(())
+}
+
+pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    ctx.accounts.config.check_not_paused()?;
+
+    let accounts = &ctx.accounts;
+    // A Token-2022 transfer fee is withheld from the assets account, so shares are
+    // minted for what it receives
+    let received = amount
+        .checked_sub(dex::transfer_fee(accounts.mint.as_ref(), amount)?)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    let shares = accounts
+        .strategy
+        .shares_for_deposit(received, accounts.share_mint.supply)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    require!(shares > 0, FlashLoanArbitrageError::StrategyAmountTooSmall);
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.depositor_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.assets_token_account.to_account_info(),
+                authority: accounts.depositor.to_account_info(),
+            },
+        ),
+        amount,
+        accounts.mint.decimals,
+    )?;
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: accounts.share_mint.to_account_info(),
+                to: accounts.depositor_share_account.to_account_info(),
+                authority: accounts.strategy.to_account_info(),
+            },
+            &[&accounts.strategy.signer_seeds()],
+        ),
+        shares,
+    )?;
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.total_assets = strategy
+        .total_assets
+        .checked_add(received)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+
+    msg!("Deposited {} for {} shares", received, shares);
+    emit!(StrategyDeposited {
+        strategy: strategy.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount: received,
+        shares,
+    });
+    Ok(())
+}
+
+pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let amount = accounts
+        .strategy
+        .assets_for_shares(shares, accounts.share_mint.supply)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    require!(amount > 0, FlashLoanArbitrageError::StrategyAmountTooSmall);
+
+    token_interface::burn(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Burn {
+                mint: accounts.share_mint.to_account_info(),
+                from: accounts.depositor_share_account.to_account_info(),
+                authority: accounts.depositor.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.assets_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.depositor_token_account.to_account_info(),
+                authority: accounts.strategy.to_account_info(),
+            },
+            &[&accounts.strategy.signer_seeds()],
+        ),
+        amount,
+        accounts.mint.decimals,
+    )?;
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.total_assets = strategy
+        .total_assets
+        .checked_sub(amount)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+
+    msg!("Redeemed {} shares for {}", shares, amount);
+    emit!(StrategyWithdrawn {
+        strategy: strategy.key(),
+        depositor: ctx.accounts.depositor.key(),
+        shares,
+        amount,
+    });
+    Ok(())
+}
+
+pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let amount = accounts.strategy.accrued_fees;
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.assets_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.treasury_token_account.to_account_info(),
+                authority: accounts.strategy.to_account_info(),
+            },
+            &[&accounts.strategy.signer_seeds()],
+        ),
+        amount,
+        accounts.mint.decimals,
+    )?;
+    ctx.accounts.strategy.accrued_fees = 0;
+
+    msg!("Collected {} in performance fees", amount);
+    emit!(StrategyFeesCollected {
+        strategy: ctx.accounts.strategy.key(),
+        amount,
+        treasury: ctx.accounts.treasury_token_account.key(),
+    });
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, TransferChecked};
+
+use crate::errors::FlashLoanArbitrageError;
+use crate::events::ProfitWithdrawn;
+use crate::state::accounts::{InitializeProfitLedger, InitializeVault, WithdrawProfit};
+
+pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+    ctx.accounts.vault.bump = *ctx.bumps.get("vault").unwrap();
+
+    msg!("Vault initialized at {}", ctx.accounts.vault.key());
+    Ok(())
+}
+
+pub fn initialize_profit_ledger(ctx: Context<InitializeProfitLedger>) -> Result<()> {
+    let profit_ledger = &mut ctx.accounts.profit_ledger;
+    profit_ledger.mint = ctx.accounts.mint.key();
+    profit_ledger.accrued_profit = 0;
+    profit_ledger.bump = *ctx.bumps.get("profit_ledger").unwrap();
+
+    msg!("Profit ledger initialized for {}", profit_ledger.mint);
+    Ok(())
+}
+
+pub fn withdraw_profit(ctx: Context<WithdrawProfit>, amount: u64) -> Result<()> {
+    // Only profit leaves the vault; the rest of the balance is trading capital
+    let profit_ledger = &mut ctx.accounts.profit_ledger;
+    profit_ledger.accrued_profit = profit_ledger
+        .accrued_profit
+        .checked_sub(amount)
+        .ok_or(FlashLoanArbitrageError::WithdrawalExceedsProfit)?;
+
+    let accounts = &ctx.accounts;
+    require!(
+        amount <= accounts.vault_token_account.amount,
+        FlashLoanArbitrageError::InsufficientVaultBalance
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.vault_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to: accounts.destination_token_account.to_account_info(),
+                authority: accounts.vault.to_account_info(),
+            },
+            &[&accounts.vault.signer_seeds()],
+        ),
+        amount,
+        accounts.mint.decimals,
+    )?;
+
+    msg!("Withdrew {} from the vault", amount);
+    emit!(ProfitWithdrawn {
+        mint: accounts.vault_token_account.mint,
+        amount,
+        destination: accounts.destination_token_account.key(),
+    });
+    Ok(())
+}
@@ -0,0 +1,337 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    sysvar::{
+        self,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+
+use crate::errors::FlashLoanArbitrageError;
+use crate::lending::{account_at, read_u64, LendingAccounts, LendingAdapter};
+
+// MarginFi v2 instruction discriminators
+pub const START_FLASHLOAN_DISCRIMINATOR: [u8; 8] = [14, 131, 33, 220, 81, 186, 180, 107];
+pub const END_FLASHLOAN_DISCRIMINATOR: [u8; 8] = [105, 124, 201, 106, 153, 2, 8, 156];
+pub const BORROW_DISCRIMINATOR: [u8; 8] = [4, 126, 116, 53, 48, 5, 212, 31];
+pub const REPAY_DISCRIMINATOR: [u8; 8] = [79, 209, 172, 177, 222, 51, 173, 151];
+
+const LIQUIDITY_VAULT_AUTHORITY_SEED: &[u8] = b"liquidity_vault_auth";
+
+// Account positions inside the MarginFi instructions
+const FLASHLOAN_MARGINFI_ACCOUNT_INDEX: usize = 0;
+const GROUP_INDEX: usize = 0;
+const MARGINFI_ACCOUNT_INDEX: usize = 1;
+const BANK_INDEX: usize = 3;
+const BORROW_DESTINATION_INDEX: usize = 4;
+const BORROW_LIQUIDITY_VAULT_INDEX: usize = 6;
+const REPAY_SOURCE_INDEX: usize = 4;
+const REPAY_LIQUIDITY_VAULT_INDEX: usize = 5;
+
+// MarginFi accounts expected at the front of `remaining_accounts`
+const MARGINFI_GROUP: usize = 0;
+const MARGINFI_ACCOUNT: usize = 1;
+const REMAINING_ACCOUNTS_LEN: usize = 2;
+
+/// MarginFi flash loans
+///
+/// A MarginFi flash loan is a borrow and a repay wrapped between
+/// `lending_account_start_flashloan` and `lending_account_end_flashloan`, so the
+/// transaction looks like:
+///
+/// `start_flashloan(end_index)`, `borrow`, `flash_loan_and_arbitrage`, `repay`, `end_flashloan`
+///
+/// `loan_reserve_account` is the bank and `lending_fee_account` is the bank's liquidity
+/// vault. Remaining accounts: `[marginfi_group, marginfi_account]`
+pub struct MarginFi;
+
+/// Derives the PDA that signs transfers out of a bank's liquidity vault
+pub fn liquidity_vault_authority(program_id: &Pubkey, bank: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[LIQUIDITY_VAULT_AUTHORITY_SEED, bank.as_ref()], program_id).0
+}
+
+/// Builds a `lending_account_start_flashloan` instruction
+pub fn start_flashloan(
+    program_id: Pubkey,
+    marginfi_account: Pubkey,
+    signer: Pubkey,
+    end_index: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(&START_FLASHLOAN_DISCRIMINATOR);
+    data.extend_from_slice(&end_index.to_le_bytes());
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(marginfi_account, false),
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Builds a `lending_account_end_flashloan` instruction
+///
+/// `health_accounts` are the bank and oracle pairs of every active balance, which
+/// MarginFi needs for the health check that closes the flash loan.
+pub fn end_flashloan(
+    program_id: Pubkey,
+    marginfi_account: Pubkey,
+    signer: Pubkey,
+    health_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(marginfi_account, false),
+        AccountMeta::new_readonly(signer, true),
+    ];
+    accounts.extend_from_slice(health_accounts);
+
+    Instruction {
+        program_id,
+        accounts,
+        data: END_FLASHLOAN_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Builds a `lending_account_borrow` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn borrow(
+    program_id: Pubkey,
+    marginfi_group: Pubkey,
+    marginfi_account: Pubkey,
+    signer: Pubkey,
+    bank: Pubkey,
+    destination_token_account: Pubkey,
+    bank_liquidity_vault: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(&BORROW_DISCRIMINATOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(marginfi_group, false),
+            AccountMeta::new(marginfi_account, false),
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new(bank, false),
+            AccountMeta::new(destination_token_account, false),
+            AccountMeta::new(liquidity_vault_authority(&program_id, &bank), false),
+            AccountMeta::new(bank_liquidity_vault, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+        data,
+    }
+}
+
+/// Builds a `lending_account_repay` instruction that repays the whole liability
+#[allow(clippy::too_many_arguments)]
+pub fn repay(
+    program_id: Pubkey,
+    marginfi_group: Pubkey,
+    marginfi_account: Pubkey,
+    signer: Pubkey,
+    bank: Pubkey,
+    signer_token_account: Pubkey,
+    bank_liquidity_vault: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(18);
+    data.extend_from_slice(&REPAY_DISCRIMINATOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+    // repay_all: Some(true), so rounding on the liability cannot leave dust behind
+    data.extend_from_slice(&[1, 1]);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(marginfi_group, false),
+            AccountMeta::new(marginfi_account, false),
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new(bank, false),
+            AccountMeta::new(signer_token_account, false),
+            AccountMeta::new(bank_liquidity_vault, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+        data,
+    }
+}
+
+/// Assembles the full MarginFi flash loan transaction around `arbitrage_ix`
+///
+/// `first_index` is the position the start instruction will take in the transaction,
+/// e.g. after any compute budget instructions. `repay_source` is `loan_token_account`,
+/// or in vault mode the authority's repayment token account.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_loan_transaction(
+    program_id: Pubkey,
+    marginfi_group: Pubkey,
+    marginfi_account: Pubkey,
+    signer: Pubkey,
+    bank: Pubkey,
+    bank_liquidity_vault: Pubkey,
+    loan_token_account: Pubkey,
+    repay_source: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+    health_accounts: &[AccountMeta],
+    arbitrage_ix: Instruction,
+    first_index: u64,
+) -> Vec<Instruction> {
+    vec![
+        start_flashloan(program_id, marginfi_account, signer, first_index + 4),
+        borrow(
+            program_id,
+            marginfi_group,
+            marginfi_account,
+            signer,
+            bank,
+            loan_token_account,
+            bank_liquidity_vault,
+            token_program,
+            amount,
+        ),
+        arbitrage_ix,
+        repay(
+            program_id,
+            marginfi_group,
+            marginfi_account,
+            signer,
+            bank,
+            repay_source,
+            bank_liquidity_vault,
+            token_program,
+            amount,
+        ),
+        end_flashloan(program_id, marginfi_account, signer, health_accounts),
+    ]
+}
+
+impl MarginFi {
+    /// Returns the indexes of the start and end flashloan instructions around the
+    /// current instruction
+    fn flashloan_bounds(&self, accounts: &LendingAccounts) -> Result<(usize, usize)> {
+        let lending_program = accounts.lending_program.key();
+        let marginfi_account = accounts.remaining_accounts[MARGINFI_ACCOUNT].key();
+        let current_index = load_current_index_checked(accounts.instructions_sysvar)? as usize;
+
+        for index in (0..current_index).rev() {
+            let ix = load_instruction_at_checked(index, accounts.instructions_sysvar)?;
+            if !is_flashloan_ix(&ix, &lending_program, &marginfi_account, &START_FLASHLOAN_DISCRIMINATOR)
+                || ix.data.len() < 16
+            {
+                continue;
+            }
+
+            let end_index = read_u64(&ix.data[8..16]) as usize;
+            require!(
+                end_index > current_index,
+                FlashLoanArbitrageError::OutsideMarginFiFlashloan
+            );
+
+            let end_ix = load_instruction_at_checked(end_index, accounts.instructions_sysvar)
+                .map_err(|_| FlashLoanArbitrageError::OutsideMarginFiFlashloan)?;
+            require!(
+                is_flashloan_ix(&end_ix, &lending_program, &marginfi_account, &END_FLASHLOAN_DISCRIMINATOR),
+                FlashLoanArbitrageError::OutsideMarginFiFlashloan
+            );
+
+            return Ok((index, end_index));
+        }
+
+        err!(FlashLoanArbitrageError::OutsideMarginFiFlashloan)
+    }
+
+    /// Checks that `ix` is a borrow or repay against our bank and MarginFi account
+    fn is_bank_ix(&self, ix: &Instruction, accounts: &LendingAccounts, discriminator: &[u8; 8]) -> bool {
+        ix.program_id == accounts.lending_program.key()
+            && ix.data.len() >= 16
+            && ix.data[..8] == discriminator[..]
+            && account_at(ix, GROUP_INDEX) == Some(accounts.remaining_accounts[MARGINFI_GROUP].key)
+            && account_at(ix, MARGINFI_ACCOUNT_INDEX)
+                == Some(accounts.remaining_accounts[MARGINFI_ACCOUNT].key)
+            && account_at(ix, BANK_INDEX) == Some(accounts.reserve.key)
+    }
+}
+
+impl LendingAdapter for MarginFi {
+    fn remaining_accounts_len(&self) -> usize {
+        REMAINING_ACCOUNTS_LEN
+    }
+
+    fn verify_borrow(&self, accounts: &LendingAccounts, amount: u64) -> Result<u8> {
+        require!(
+            *accounts.reserve.owner == accounts.lending_program.key(),
+            FlashLoanArbitrageError::LendingPoolOwnerMismatch
+        );
+
+        let (start_index, _) = self.flashloan_bounds(accounts)?;
+        let current_index = load_current_index_checked(accounts.instructions_sysvar)? as usize;
+
+        // The borrow has to sit inside the flashloan, before this instruction
+        for index in (start_index + 1..current_index).rev() {
+            let ix = load_instruction_at_checked(index, accounts.instructions_sysvar)?;
+            if self.is_bank_ix(&ix, accounts, &BORROW_DISCRIMINATOR)
+                && read_u64(&ix.data[8..16]) == amount
+                && account_at(&ix, BORROW_DESTINATION_INDEX) == Some(accounts.loan_token_account.key)
+                && account_at(&ix, BORROW_LIQUIDITY_VAULT_INDEX) == Some(accounts.fee_receiver.key)
+            {
+                return u8::try_from(index)
+                    .map_err(|_| FlashLoanArbitrageError::MissingFlashBorrowInstruction.into());
+            }
+        }
+
+        err!(FlashLoanArbitrageError::MissingFlashBorrowInstruction)
+    }
+
+    fn verify_repay(
+        &self,
+        accounts: &LendingAccounts,
+        amount: u64,
+        _borrow_instruction_index: u8,
+    ) -> Result<usize> {
+        let (_, end_index) = self.flashloan_bounds(accounts)?;
+        let current_index = load_current_index_checked(accounts.instructions_sysvar)? as usize;
+
+        // The repay has to sit inside the flashloan, after this instruction
+        for index in current_index + 1..end_index {
+            let ix = load_instruction_at_checked(index, accounts.instructions_sysvar)?;
+            if self.is_bank_ix(&ix, accounts, &REPAY_DISCRIMINATOR)
+                && (read_u64(&ix.data[8..16]) >= amount || repays_all(&ix))
+                && account_at(&ix, REPAY_SOURCE_INDEX) == Some(accounts.repay_source.key)
+                && account_at(&ix, REPAY_LIQUIDITY_VAULT_INDEX) == Some(accounts.fee_receiver.key)
+            {
+                return Ok(index);
+            }
+        }
+
+        err!(FlashLoanArbitrageError::MissingFlashRepayInstruction)
+    }
+
+    fn flash_loan_fee(&self, _accounts: &LendingAccounts, _principal: u64) -> Result<u64> {
+        // MarginFi does not charge for flash loans
+        Ok(0)
+    }
+}
+
+fn is_flashloan_ix(
+    ix: &Instruction,
+    lending_program: &Pubkey,
+    marginfi_account: &Pubkey,
+    discriminator: &[u8; 8],
+) -> bool {
+    ix.program_id == *lending_program
+        && ix.data.len() >= 8
+        && ix.data[..8] == discriminator[..]
+        && account_at(ix, FLASHLOAN_MARGINFI_ACCOUNT_INDEX) == Some(marginfi_account)
+}
+
+/// Reads the `repay_all: Option<bool>` argument of a repay instruction
+fn repays_all(ix: &Instruction) -> bool {
+    ix.data.get(16..18) == Some(&[1, 1][..])
+}
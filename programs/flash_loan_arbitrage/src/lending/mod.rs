@@ -0,0 +1,127 @@
+//! Flash loan lenders refuse to be called through CPI, so the borrow and repay
+//! instructions sit at the top level of the transaction around ours. The helpers
+//! here scan the instructions sysvar for them.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+pub mod marginfi;
+pub mod solend;
+
+pub use marginfi::MarginFi;
+pub use solend::Solend;
+
+/// Flash loan lenders supported by the program, selected per instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LendingProvider {
+    Solend,
+    MarginFi,
+}
+
+impl LendingProvider {
+    /// Returns the adapter that knows this lender's accounts, encoding and fees
+    pub fn adapter(&self) -> &'static dyn LendingAdapter {
+        match self {
+            LendingProvider::Solend => &Solend,
+            LendingProvider::MarginFi => &MarginFi,
+        }
+    }
+}
+
+/// The accounts a lending adapter works with
+///
+/// The fixed accounts come from `FlashLoanAndArbitrage`; anything specific to one
+/// lender is passed at the front of `remaining_accounts`.
+pub struct LendingAccounts<'a, 'info> {
+    pub lending_program: &'a AccountInfo<'info>,
+    pub reserve: &'a AccountInfo<'info>,
+    pub fee_receiver: &'a AccountInfo<'info>,
+    pub loan_token_account: &'a AccountInfo<'info>,
+    /// The account the repay draws from: the loan token account, or in vault mode the
+    /// authority's repayment token account
+    pub repay_source: &'a AccountInfo<'info>,
+    pub instructions_sysvar: &'a AccountInfo<'info>,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+/// A flash loan lender
+///
+/// Implementations check the lender's top-level borrow and repay instructions and
+/// work out what the loan costs, so new lenders can be added without touching the
+/// arbitrage flow.
+pub trait LendingAdapter {
+    /// Number of lender-specific accounts taken from `remaining_accounts`
+    fn remaining_accounts_len(&self) -> usize;
+
+    /// Checks the borrow that funds the current instruction and returns its index
+    fn verify_borrow(&self, accounts: &LendingAccounts, amount: u64) -> Result<u8>;
+
+    /// Checks that the repay for `borrow_instruction_index` follows the current
+    /// instruction and returns its index
+    fn verify_repay(
+        &self,
+        accounts: &LendingAccounts,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Result<usize>;
+
+    /// Returns the fee charged on a flash loan of `principal`
+    fn flash_loan_fee(&self, accounts: &LendingAccounts, principal: u64) -> Result<u64>;
+}
+
+/// Returns the closest instruction before the current one matching `predicate`
+pub fn find_instruction_before<F>(
+    instructions_sysvar: &AccountInfo,
+    predicate: F,
+) -> Result<Option<(usize, Instruction)>>
+where
+    F: Fn(&Instruction) -> bool,
+{
+    let current_index = load_current_index_checked(instructions_sysvar)? as usize;
+
+    for index in (0..current_index).rev() {
+        let ix = load_instruction_at_checked(index, instructions_sysvar)?;
+        if predicate(&ix) {
+            return Ok(Some((index, ix)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the closest instruction after the current one matching `predicate`
+pub fn find_instruction_after<F>(
+    instructions_sysvar: &AccountInfo,
+    predicate: F,
+) -> Result<Option<(usize, Instruction)>>
+where
+    F: Fn(&Instruction) -> bool,
+{
+    let current_index = load_current_index_checked(instructions_sysvar)? as usize;
+
+    let mut index = current_index + 1;
+    // Loading past the last instruction fails, which ends the scan
+    while let Ok(ix) = load_instruction_at_checked(index, instructions_sysvar) {
+        if predicate(&ix) {
+            return Ok(Some((index, ix)));
+        }
+        index += 1;
+    }
+
+    Ok(None)
+}
+
+/// Returns the key of the account at `index` in an instruction
+pub(crate) fn account_at(ix: &Instruction, index: usize) -> Option<&Pubkey> {
+    ix.accounts.get(index).map(|meta| &meta.pubkey)
+}
+
+/// Reads a little-endian u64 from an eight byte slice
+pub(crate) fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
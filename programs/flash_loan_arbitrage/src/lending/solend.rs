@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    sysvar,
+};
+
+use crate::errors::FlashLoanArbitrageError;
+use crate::lending::{
+    account_at, find_instruction_after, find_instruction_before, read_u64, LendingAccounts,
+    LendingAdapter,
+};
+
+// Solend (Save) token-lending instruction tags
+pub const FLASH_BORROW_RESERVE_LIQUIDITY: u8 = 19;
+pub const FLASH_REPAY_RESERVE_LIQUIDITY: u8 = 20;
+
+// Offsets inside a packed Solend `Reserve` account
+const RESERVE_LEN: usize = 619;
+const RESERVE_LENDING_MARKET_OFFSET: usize = 10;
+const RESERVE_LIQUIDITY_SUPPLY_OFFSET: usize = 75;
+const RESERVE_FLASH_LOAN_FEE_WAD_OFFSET: usize = 314;
+const RESERVE_HOST_FEE_PERCENTAGE_OFFSET: usize = 322;
+const RESERVE_FEE_RECEIVER_OFFSET: usize = 339;
+
+// Solend's fixed-point scale; fees are rounded up like `Decimal::try_ceil_u64`
+const WAD: u128 = 1_000_000_000_000_000_000;
+// A flash loan fee of u64::MAX means flash loans are disabled on the reserve
+const FLASH_LOANS_DISABLED: u64 = u64::MAX;
+
+// Account positions inside the Solend flash loan instructions
+const BORROW_SOURCE_INDEX: usize = 0;
+const BORROW_DESTINATION_INDEX: usize = 1;
+const BORROW_RESERVE_INDEX: usize = 2;
+const BORROW_LENDING_MARKET_INDEX: usize = 3;
+const REPAY_SOURCE_INDEX: usize = 0;
+const REPAY_DESTINATION_INDEX: usize = 1;
+const REPAY_FEE_RECEIVER_INDEX: usize = 2;
+const REPAY_RESERVE_INDEX: usize = 4;
+const REPAY_LENDING_MARKET_INDEX: usize = 5;
+
+// Solend accounts expected at the front of `remaining_accounts`
+const RESERVE_LIQUIDITY_SUPPLY: usize = 0;
+const LENDING_MARKET: usize = 1;
+const REMAINING_ACCOUNTS_LEN: usize = 2;
+
+/// Solend (Save) flash loans
+///
+/// Remaining accounts: `[reserve_liquidity_supply, lending_market]`
+pub struct Solend;
+
+/// The parts of a Solend reserve the flash loan needs
+pub struct ReserveInfo {
+    pub lending_market: Pubkey,
+    pub liquidity_supply: Pubkey,
+    pub flash_loan_fee_wad: u64,
+    pub host_fee_percentage: u8,
+    pub fee_receiver: Pubkey,
+}
+
+impl ReserveInfo {
+    /// Reads the fields from the packed reserve account data
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= RESERVE_LEN,
+            FlashLoanArbitrageError::InvalidLendingAccounts
+        );
+
+        Ok(Self {
+            lending_market: read_pubkey(data, RESERVE_LENDING_MARKET_OFFSET),
+            liquidity_supply: read_pubkey(data, RESERVE_LIQUIDITY_SUPPLY_OFFSET),
+            flash_loan_fee_wad: read_u64(
+                &data[RESERVE_FLASH_LOAN_FEE_WAD_OFFSET..RESERVE_FLASH_LOAN_FEE_WAD_OFFSET + 8],
+            ),
+            host_fee_percentage: data[RESERVE_HOST_FEE_PERCENTAGE_OFFSET],
+            fee_receiver: read_pubkey(data, RESERVE_FEE_RECEIVER_OFFSET),
+        })
+    }
+
+    /// Loads the reserve from its account, checking it is owned by the lending program
+    pub fn load(reserve: &AccountInfo, lending_program: &Pubkey) -> Result<Self> {
+        require!(
+            reserve.owner == lending_program,
+            FlashLoanArbitrageError::LendingPoolOwnerMismatch
+        );
+        Self::unpack(&reserve.try_borrow_data()?)
+    }
+}
+
+/// Calculates the flash loan fees Solend charges on `amount`
+///
+/// Mirrors `ReserveFees::calculate_flash_loan_fees`: the fee is at least one token
+/// (two when a host fee applies), computed in wads and rounded up to whole tokens in
+/// the reserve's favour. Returns `(origination_fee, host_fee)`.
+pub fn calculate_flash_loan_fees(
+    amount: u64,
+    flash_loan_fee_wad: u64,
+    host_fee_percentage: u8,
+) -> Result<(u64, u64)> {
+    require!(
+        flash_loan_fee_wad != FLASH_LOANS_DISABLED,
+        FlashLoanArbitrageError::FlashLoanInitFailed
+    );
+    if flash_loan_fee_wad == 0 || amount == 0 {
+        return Ok((0, 0));
+    }
+
+    let need_to_assess_host_fee = host_fee_percentage > 0;
+    let minimum_fee: u128 = if need_to_assess_host_fee { 2 } else { 1 };
+
+    let amount_wads = (amount as u128)
+        .checked_mul(WAD)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    let fee_wads = (amount as u128)
+        .checked_mul(flash_loan_fee_wad as u128)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?
+        .max(minimum_fee * WAD);
+    require!(
+        fee_wads < amount_wads,
+        FlashLoanArbitrageError::LoanAmountTooSmall
+    );
+
+    let borrow_fee = ceil_wads(fee_wads)?;
+    let host_fee = if need_to_assess_host_fee {
+        let host_fee_wads = fee_wads
+            .checked_mul(host_fee_percentage as u128)
+            .ok_or(FlashLoanArbitrageError::MathOverflow)?
+            / 100;
+        ceil_wads(host_fee_wads)?.max(1)
+    } else {
+        0
+    };
+
+    let origination_fee = borrow_fee
+        .checked_sub(host_fee)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    Ok((origination_fee, host_fee))
+}
+
+fn ceil_wads(value: u128) -> Result<u64> {
+    let rounded = value.div_ceil(WAD);
+    u64::try_from(rounded).map_err(|_| FlashLoanArbitrageError::MathOverflow.into())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(buf)
+}
+
+/// Derives the lending market authority PDA that owns the reserve liquidity supply
+pub fn lending_market_authority(program_id: &Pubkey, lending_market: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[lending_market.as_ref()], program_id).0
+}
+
+/// Builds a `FlashBorrowReserveLiquidity` instruction
+///
+/// Solend rejects flash borrows made through CPI, so this instruction has to be placed
+/// at the top level of the transaction, before `flash_loan_and_arbitrage`.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_borrow_reserve_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity: Pubkey,
+    destination_liquidity: Pubkey,
+    reserve: Pubkey,
+    lending_market: Pubkey,
+    lending_market_authority: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.push(FLASH_BORROW_RESERVE_LIQUIDITY);
+    data.extend_from_slice(&liquidity_amount.to_le_bytes());
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity, false),
+            AccountMeta::new(destination_liquidity, false),
+            AccountMeta::new(reserve, false),
+            AccountMeta::new_readonly(lending_market, false),
+            AccountMeta::new_readonly(lending_market_authority, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+        data,
+    }
+}
+
+/// Builds a `FlashRepayReserveLiquidity` instruction
+///
+/// `liquidity_amount` is the borrowed principal; Solend adds the flash loan and host fees
+/// itself. `borrow_instruction_index` is the position of the matching flash borrow.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_repay_reserve_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    borrow_instruction_index: u8,
+    source_liquidity: Pubkey,
+    destination_liquidity: Pubkey,
+    reserve_liquidity_fee_receiver: Pubkey,
+    host_fee_receiver: Pubkey,
+    reserve: Pubkey,
+    lending_market: Pubkey,
+    user_transfer_authority: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let mut data = Vec::with_capacity(10);
+    data.push(FLASH_REPAY_RESERVE_LIQUIDITY);
+    data.extend_from_slice(&liquidity_amount.to_le_bytes());
+    data.push(borrow_instruction_index);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity, false),
+            AccountMeta::new(destination_liquidity, false),
+            AccountMeta::new(reserve_liquidity_fee_receiver, false),
+            AccountMeta::new(host_fee_receiver, false),
+            AccountMeta::new(reserve, false),
+            AccountMeta::new_readonly(lending_market, false),
+            AccountMeta::new_readonly(user_transfer_authority, true),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+        data,
+    }
+}
+
+impl LendingAdapter for Solend {
+    fn remaining_accounts_len(&self) -> usize {
+        REMAINING_ACCOUNTS_LEN
+    }
+
+    fn verify_borrow(&self, accounts: &LendingAccounts, amount: u64) -> Result<u8> {
+        let lending_program = accounts.lending_program.key();
+        let reserve = accounts.reserve.key();
+        let destination_liquidity = accounts.loan_token_account.key();
+        let reserve_liquidity_supply = accounts.remaining_accounts[RESERVE_LIQUIDITY_SUPPLY].key();
+        let lending_market = accounts.remaining_accounts[LENDING_MARKET].key();
+
+        // The supply, market and fee receiver must be the ones the reserve records
+        let reserve_info = ReserveInfo::load(accounts.reserve, &lending_program)?;
+        require!(
+            reserve_info.liquidity_supply == reserve_liquidity_supply
+                && reserve_info.lending_market == lending_market
+                && reserve_info.fee_receiver == accounts.fee_receiver.key(),
+            FlashLoanArbitrageError::InvalidLendingAccounts
+        );
+
+        let (index, _) = find_instruction_before(accounts.instructions_sysvar, |ix| {
+            ix.program_id == lending_program
+                && ix.data.len() >= 9
+                && ix.data[0] == FLASH_BORROW_RESERVE_LIQUIDITY
+                && read_u64(&ix.data[1..9]) == amount
+                && account_at(ix, BORROW_SOURCE_INDEX) == Some(&reserve_liquidity_supply)
+                && account_at(ix, BORROW_DESTINATION_INDEX) == Some(&destination_liquidity)
+                && account_at(ix, BORROW_RESERVE_INDEX) == Some(&reserve)
+                && account_at(ix, BORROW_LENDING_MARKET_INDEX) == Some(&lending_market)
+        })?
+        .ok_or(FlashLoanArbitrageError::MissingFlashBorrowInstruction)?;
+
+        u8::try_from(index)
+            .map_err(|_| FlashLoanArbitrageError::MissingFlashBorrowInstruction.into())
+    }
+
+    fn verify_repay(
+        &self,
+        accounts: &LendingAccounts,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Result<usize> {
+        let lending_program = accounts.lending_program.key();
+        let reserve = accounts.reserve.key();
+        let fee_receiver = accounts.fee_receiver.key();
+        let source_liquidity = accounts.repay_source.key();
+        let reserve_liquidity_supply = accounts.remaining_accounts[RESERVE_LIQUIDITY_SUPPLY].key();
+        let lending_market = accounts.remaining_accounts[LENDING_MARKET].key();
+
+        let (index, _) = find_instruction_after(accounts.instructions_sysvar, |ix| {
+            ix.program_id == lending_program
+                && ix.data.len() >= 10
+                && ix.data[0] == FLASH_REPAY_RESERVE_LIQUIDITY
+                && read_u64(&ix.data[1..9]) == amount
+                && ix.data[9] == borrow_instruction_index
+                && account_at(ix, REPAY_SOURCE_INDEX) == Some(&source_liquidity)
+                && account_at(ix, REPAY_DESTINATION_INDEX) == Some(&reserve_liquidity_supply)
+                && account_at(ix, REPAY_FEE_RECEIVER_INDEX) == Some(&fee_receiver)
+                && account_at(ix, REPAY_RESERVE_INDEX) == Some(&reserve)
+                && account_at(ix, REPAY_LENDING_MARKET_INDEX) == Some(&lending_market)
+        })?
+        .ok_or(FlashLoanArbitrageError::MissingFlashRepayInstruction)?;
+
+        Ok(index)
+    }
+
+    fn flash_loan_fee(&self, accounts: &LendingAccounts, principal: u64) -> Result<u64> {
+        let reserve_info = ReserveInfo::load(accounts.reserve, &accounts.lending_program.key())?;
+        let (origination_fee, host_fee) = calculate_flash_loan_fees(
+            principal,
+            reserve_info.flash_loan_fee_wad,
+            reserve_info.host_fee_percentage,
+        )?;
+
+        // The repay takes both fees along with the principal
+        origination_fee
+            .checked_add(host_fee)
+            .ok_or(FlashLoanArbitrageError::MathOverflow.into())
+    }
+}
@@ -1,10 +1,25 @@
+// Anchor's `Error` is large; every instruction handler returns it
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
 
+// Replace this with your actual deployed program ID when you go to production
 declare_id!("9chwqr3q9XBJnCs8euyFpyqzHamXpZk4mCAEzsfXjWCC");
 
+pub mod dex;
+pub mod errors;
+pub mod events;
+pub mod guard;
 pub mod instructions;
+pub mod lending;
+pub mod oracle;
 pub mod state;
 
+use dex::{DexKind, RouteLeg, SwapLegParams};
+use instructions::{OptimalLoan, SimulationReport};
+use lending::LendingProvider;
+use oracle::OracleKind;
+use state::{ConfigParams, ProgramKind, Role, TradeDeadline};
 use state::accounts::*;
 
 #[program]
@@ -13,18 +28,365 @@ pub mod flash_loan_arbitrage {
 
     /// The main instruction that performs flash loan arbitrage across two DEXes
     ///
+    /// With the loan and intermediate mints' oracle accounts passed, each leg's
+    /// execution price is checked against the oracle prices.
+    /// 
     /// # Arguments
     /// * `ctx` - The context containing all accounts needed for the operation
     /// * `loan_amount` - The amount of SOL tokens to borrow for the flash loan
     /// * `min_profit_amount` - The minimum profit required for the transaction to succeed
+    /// * `provider` - The flash loan lender; its own accounts lead `remaining_accounts`
+    /// * `leg_a` - The DEX A swap; its accounts follow the lender's in `remaining_accounts`
+    /// * `leg_b` - The DEX B swap; its accounts follow leg A's in `remaining_accounts`
+    /// * `min_out_leg_a` - The least intermediate tokens leg A may return
+    /// * `min_out_leg_b` - The least loan tokens leg B may return
+    /// * `slippage_bps` - Replaces the config's default slippage, up to its maximum
+    /// * `deadline` - The last slot the trade may execute in and the slot its quote was taken at
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    #[allow(clippy::too_many_arguments)]
+    pub fn flash_loan_and_arbitrage<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+        loan_amount: u64,
+        min_profit_amount: u64,
+        provider: LendingProvider,
+        leg_a: SwapLegParams,
+        leg_b: SwapLegParams,
+        min_out_leg_a: u64,
+        min_out_leg_b: u64,
+        slippage_bps: Option<u16>,
+        deadline: TradeDeadline,
+    ) -> Result<()> {
+        instructions::flash_loan::flash_loan_and_arbitrage(
+            ctx,
+            loan_amount,
+            min_profit_amount,
+            provider,
+            leg_a,
+            leg_b,
+            min_out_leg_a,
+            min_out_leg_b,
+            slippage_bps,
+            deadline,
+        )
+    }
+    
+    /// Performs a flash loan arbitrage along a route of 2 to 5 swaps
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the lending accounts; every leg's accounts are in `remaining_accounts`
+    /// * `loan_amount` - The amount of tokens to borrow for the flash loan
+    /// * `min_profit_amount` - The minimum profit required for the transaction to succeed
+    /// * `provider` - The flash loan lender; its own accounts lead `remaining_accounts`
+    /// * `route` - The swaps in order, which must start and end in the loan token account
+    /// * `slippage_bps` - Replaces the config's default slippage for every pool, up to its maximum
+    /// * `deadline` - The last slot the trade may execute in and the slot its quote was taken at
     ///
     /// # Returns
     /// * `Result<()>` - Result indicating success or an error code
-    pub fn flash_loan_and_arbitrage(
-        ctx: Context<FlashLoanAndArbitrage>,
+    pub fn flash_loan_route<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanRoute<'info>>,
         loan_amount: u64,
         min_profit_amount: u64,
+        provider: LendingProvider,
+        route: Vec<RouteLeg>,
+        slippage_bps: Option<u16>,
+        deadline: TradeDeadline,
     ) -> Result<()> {
-        instructions::flash_loan::process_flash_loan_and_arbitrage(ctx, loan_amount, min_profit_amount)
+        instructions::flash_loan_route::flash_loan_route(
+            ctx,
+            loan_amount,
+            min_profit_amount,
+            provider,
+            route,
+            slippage_bps,
+            deadline,
+        )
+    }
+
+    /// Simulates a flash loan arbitrage to check if it would be profitable
+    /// without actually executing any transactions
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing all accounts needed for the simulation
+    /// * `loan_amount` - The amount of tokens to borrow for the flash loan
+    /// * `min_profit_amount` - The net profit at which the report counts as profitable
+    /// * `provider` - The flash loan lender whose fee applies
+    /// * `leg_a` - The DEX and accounts layout of the first swap, quoted first
+    /// * `leg_b` - The DEX and accounts layout of the second swap
+    ///
+    /// # Returns
+    /// * `Result<SimulationReport>` - The quoted legs, fee and profit, set as return data
+    pub fn simulate_arbitrage<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+        loan_amount: u64,
+        min_profit_amount: u64,
+        provider: LendingProvider,
+        leg_a: SwapLegParams,
+        leg_b: SwapLegParams,
+    ) -> Result<SimulationReport> {
+        instructions::flash_loan::simulate_arbitrage(
+            &ctx,
+            loan_amount,
+            min_profit_amount,
+            provider,
+            leg_a,
+            leg_b,
+        )
+    }
+
+    /// Searches for the loan size that maximizes the simulated arbitrage profit
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing all accounts needed for the simulation
+    /// * `min_profit_amount` - The minimum profit the best loan size must reach
+    /// * `provider` - The flash loan lender whose fee applies
+    /// * `leg_a` - The DEX and accounts layout of the first swap, quoted first
+    /// * `leg_b` - The DEX and accounts layout of the second swap
+    /// * `max_evaluations` - How many loan sizes to quote, 2 to 64; bounds the compute used
+    ///
+    /// # Returns
+    /// * `Result<OptimalLoan>` - The best loan size with its expected profit and leg outputs
+    pub fn find_optimal_loan<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoanAndArbitrage<'info>>,
+        min_profit_amount: u64,
+        provider: LendingProvider,
+        leg_a: SwapLegParams,
+        leg_b: SwapLegParams,
+        max_evaluations: u8,
+    ) -> Result<OptimalLoan> {
+        instructions::flash_loan::find_optimal_loan(
+            &ctx,
+            min_profit_amount,
+            provider,
+            leg_a,
+            leg_b,
+            max_evaluations,
+        )
+    }
+
+    /// Creates the global config that holds the arbitrage limits
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the admin, who must be the program's upgrade authority and is given the first admin role
+    /// * `params` - The loan, profit, slippage and time limits
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        instructions::config::initialize_config(ctx, params)
+    }
+
+    /// Changes the arbitrage limits and the pause switch
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the config and an admin with their role
+    /// * `params` - The new loan, profit, slippage and time limits
+    /// * `paused` - Whether the arbitrage instructions are stopped
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams, paused: bool) -> Result<()> {
+        instructions::config::update_config(ctx, params, paused)
+    }
+
+    /// Creates the empty registry of lender and DEX programs the arbitrage may call
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the registry and an admin with their role
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn initialize_program_registry(ctx: Context<InitializeProgramRegistry>) -> Result<()> {
+        instructions::program_registry::initialize_program_registry(ctx)
+    }
+
+    /// Approves a program for one lender or DEX adapter
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the registry and an admin with their role
+    /// * `program_id` - The program to approve
+    /// * `kind` - The lender or DEX adapter that may call it
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn approve_program(
+        ctx: Context<UpdateProgramRegistry>,
+        program_id: Pubkey,
+        kind: ProgramKind,
+    ) -> Result<()> {
+        instructions::program_registry::approve_program(ctx, program_id, kind)
+    }
+
+    /// Removes a program's approval for one lender or DEX adapter
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the registry and an admin with their role
+    /// * `program_id` - The program to revoke
+    /// * `kind` - The lender or DEX adapter it was approved for
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn revoke_program(
+        ctx: Context<UpdateProgramRegistry>,
+        program_id: Pubkey,
+        kind: ProgramKind,
+    ) -> Result<()> {
+        instructions::program_registry::revoke_program(ctx, program_id, kind)
+    }
+
+    /// Registers a pool, binding it to its vaults, mints and DEX adapter
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the pool, its vaults, its DEX program and an admin
+    /// * `dex` - The adapter that swaps through the pool
+    /// * `fee_rate` - The pool's fee tier in hundredths of a basis point
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn register_pool(ctx: Context<RegisterPool>, dex: DexKind, fee_rate: u32) -> Result<()> {
+        instructions::pool_registry::register_pool(ctx, dex, fee_rate)
+    }
+
+    /// Removes a pool's registry entry, returning its rent to the admin
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the pool entry and an admin
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn deregister_pool(ctx: Context<DeregisterPool>) -> Result<()> {
+        instructions::pool_registry::deregister_pool(ctx)
+    }
+
+    /// Registers the price feed a mint's legs are checked against
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the mint, its price feed and an admin
+    /// * `kind` - Whether the feed is a Pyth pull or a Switchboard On-Demand feed
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn register_oracle(ctx: Context<RegisterOracle>, kind: OracleKind) -> Result<()> {
+        instructions::oracle_registry::register_oracle(ctx, kind)
+    }
+
+    /// Removes a mint's oracle entry, returning its rent to the admin
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the oracle entry and an admin
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn deregister_oracle(ctx: Context<DeregisterOracle>) -> Result<()> {
+        instructions::oracle_registry::deregister_oracle(ctx)
+    }
+
+    /// Creates the vault that owns vault-mode token accounts
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the vault and an admin
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+        instructions::vault::initialize_vault(ctx)
+    }
+
+    /// Creates the ledger of the vault's profit in a mint, which vault-mode trades credit
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the mint, the ledger and an admin
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn initialize_profit_ledger(ctx: Context<InitializeProfitLedger>) -> Result<()> {
+        instructions::vault::initialize_profit_ledger(ctx)
+    }
+
+    /// Moves profit out of a vault token account, up to what its mint's ledger has accrued
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the vault token account, its profit ledger, the destination and a withdrawer
+    /// * `amount` - The amount to withdraw in base units
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn withdraw_profit(ctx: Context<WithdrawProfit>, amount: u64) -> Result<()> {
+        instructions::vault::withdraw_profit(ctx, amount)
+    }
+
+    /// Creates a profit-sharing strategy for a mint, with its assets account and share mint
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the mint, the treasury and an admin
+    /// * `performance_fee_bps` - The share of each trade's profit kept for the treasury
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn initialize_strategy(ctx: Context<InitializeStrategy>, performance_fee_bps: u16) -> Result<()> {
+        instructions::strategy::initialize_strategy(ctx, performance_fee_bps)
+    }
+
+    /// Deposits assets into a strategy in exchange for shares at the current share price
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the strategy and the depositor's token accounts
+    /// * `amount` - The amount to deposit in base units
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        instructions::strategy::deposit(ctx, amount)
+    }
+
+    /// Redeems strategy shares for their assets at the current share price
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the strategy and the depositor's token accounts
+    /// * `shares` - The number of shares to burn
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+        instructions::strategy::withdraw(ctx, shares)
+    }
+
+    /// Sends a strategy's accrued performance fees to its treasury
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the strategy, its treasury and a withdrawer
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        instructions::strategy::collect_fees(ctx)
+    }
+
+    /// Grants a role to a key
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the new role account and an admin
+    /// * `operator` - The key to grant the role to
+    /// * `role` - The role to grant
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn add_operator(ctx: Context<AddOperator>, operator: Pubkey, role: Role) -> Result<()> {
+        instructions::operator::add_operator(ctx, operator, role)
+    }
+
+    /// Revokes a role from a key, returning the role account's rent to the admin
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the role account and an admin
+    /// * `operator` - The key to revoke the role from
+    /// * `role` - The role to revoke
+    ///
+    /// # Returns
+    /// * `Result<()>` - Result indicating success or an error code
+    pub fn remove_operator(ctx: Context<RemoveOperator>, operator: Pubkey, role: Role) -> Result<()> {
+        instructions::operator::remove_operator(ctx, operator, role)
     }
-}
\ No newline at end of file
+} 
\ No newline at end of file
@@ -0,0 +1,199 @@
+//! Oracle prices for the sanity check on the arbitrage's leg prices
+//!
+//! A manipulated pool can quote a trade that looks profitable only because its price
+//! has been pushed away from the market. When the trade passes the oracles registered
+//! for its loan and intermediate mints, each leg's execution price must stay within the
+//! config's band around the oracle prices, and the oracles must be fresh and confident.
+//!
+//! Both supported feeds are read straight from their account data, as the DEX adapters
+//! read pool state:
+//! - Pyth pull `PriceUpdateV2` accounts, owned by the Pyth receiver program. Only fully
+//!   verified updates are accepted.
+//! - Switchboard On-Demand `PullFeedAccountData` accounts, whose current result is used.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey;
+
+use crate::dex::math;
+use crate::errors::FlashLoanArbitrageError;
+use crate::state::Config;
+
+/// The Pyth receiver program, which owns `PriceUpdateV2` accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// The Switchboard On-Demand program, which owns `PullFeedAccountData` accounts
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+// Oracle prices are scaled to 18 decimals, Switchboard's own precision
+const PRICE_DECIMALS: i32 = 18;
+const BPS_DIVISOR: u128 = 10_000;
+
+// `PriceUpdateV2`: discriminator, write authority, verification level, then the price message
+const PYTH_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+const PYTH_VERIFICATION_LEVEL_OFFSET: usize = 40;
+// Borsh tag of `VerificationLevel::Full`, which carries no fields
+const PYTH_VERIFICATION_FULL: u8 = 1;
+const PYTH_PRICE_OFFSET: usize = 73;
+const PYTH_CONF_OFFSET: usize = 81;
+const PYTH_EXPONENT_OFFSET: usize = 89;
+const PYTH_PUBLISH_TIME_OFFSET: usize = 93;
+
+// `PullFeedAccountData` is zero-copy; offsets include its discriminator
+const SWITCHBOARD_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+const SWITCHBOARD_LAST_UPDATE_TIMESTAMP_OFFSET: usize = 2216;
+const SWITCHBOARD_RESULT_VALUE_OFFSET: usize = 2264;
+const SWITCHBOARD_RESULT_STD_DEV_OFFSET: usize = 2280;
+
+/// The feed an oracle account belongs to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleKind {
+    PythPull,
+    SwitchboardOnDemand,
+}
+
+impl OracleKind {
+    /// The program that must own this kind of oracle account
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            OracleKind::PythPull => PYTH_RECEIVER_PROGRAM_ID,
+            OracleKind::SwitchboardOnDemand => SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+        }
+    }
+
+    /// Reads the price an oracle account holds
+    pub fn load(&self, oracle: &AccountInfo) -> Result<OraclePrice> {
+        require!(
+            *oracle.owner == self.program_id(),
+            FlashLoanArbitrageError::InvalidOracleAccount
+        );
+        let data = oracle.try_borrow_data()?;
+        match self {
+            OracleKind::PythPull => load_pyth(&data),
+            OracleKind::SwitchboardOnDemand => load_switchboard(&data),
+        }
+    }
+}
+
+/// An oracle's price in the quote currency per whole token, with 18 decimals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: u128,
+    /// Pyth's confidence interval or Switchboard's standard deviation, in the price's units
+    pub confidence: u128,
+    /// Unix timestamp the price was published at
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Rejects a price older than the config allows or with too wide a confidence
+    pub fn check(&self, config: &Config, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(self.publish_time) <= config.oracle_max_age_secs as i64,
+            FlashLoanArbitrageError::StaleOraclePrice
+        );
+        let confidence_bps = math::mul_div_floor(self.confidence, BPS_DIVISOR, self.price)
+            .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        require!(
+            confidence_bps <= config.oracle_max_confidence_bps as u128,
+            FlashLoanArbitrageError::OracleConfidenceTooWide
+        );
+        Ok(())
+    }
+
+    /// The value of `amount` base units of a mint with `decimals` decimals
+    fn value(&self, amount: u64, decimals: u8) -> Result<u128> {
+        let unit = 10u128
+            .checked_pow(decimals as u32)
+            .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+        math::mul_div_floor(amount as u128, self.price, unit)
+            .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow))
+    }
+}
+
+/// A mint's oracle price and decimals, for valuing the amounts a leg swaps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPrice {
+    pub price: OraclePrice,
+    pub decimals: u8,
+}
+
+/// Rejects a leg whose execution price deviates from the oracle prices by more than
+/// the config's band, in either direction
+pub fn check_leg(
+    config: &Config,
+    input: &TokenPrice,
+    amount_in: u64,
+    output: &TokenPrice,
+    amount_out: u64,
+) -> Result<()> {
+    let value_in = input.price.value(amount_in, input.decimals)?;
+    let value_out = output.price.value(amount_out, output.decimals)?;
+    require!(value_in > 0, FlashLoanArbitrageError::OraclePriceDeviation);
+
+    let deviation_bps = math::mul_div_floor(value_in.abs_diff(value_out), BPS_DIVISOR, value_in)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    require!(
+        deviation_bps <= config.oracle_max_deviation_bps as u128,
+        FlashLoanArbitrageError::OraclePriceDeviation
+    );
+    Ok(())
+}
+
+fn load_pyth(data: &[u8]) -> Result<OraclePrice> {
+    require!(
+        data.get(..8) == Some(&PYTH_DISCRIMINATOR[..])
+            && data.get(PYTH_VERIFICATION_LEVEL_OFFSET) == Some(&PYTH_VERIFICATION_FULL),
+        FlashLoanArbitrageError::InvalidOracleAccount
+    );
+    let price = i64::from_le_bytes(oracle_field(data, PYTH_PRICE_OFFSET)?);
+    let conf = u64::from_le_bytes(oracle_field(data, PYTH_CONF_OFFSET)?);
+    let exponent = i32::from_le_bytes(oracle_field(data, PYTH_EXPONENT_OFFSET)?);
+    let price = scale_price(price.max(0) as u128, exponent)?;
+    require!(price > 0, FlashLoanArbitrageError::InvalidOracleAccount);
+
+    Ok(OraclePrice {
+        price,
+        confidence: scale_price(conf as u128, exponent)?,
+        publish_time: i64::from_le_bytes(oracle_field(data, PYTH_PUBLISH_TIME_OFFSET)?),
+    })
+}
+
+fn load_switchboard(data: &[u8]) -> Result<OraclePrice> {
+    require!(
+        data.get(..8) == Some(&SWITCHBOARD_DISCRIMINATOR[..]),
+        FlashLoanArbitrageError::InvalidOracleAccount
+    );
+    let value = i128::from_le_bytes(oracle_field(data, SWITCHBOARD_RESULT_VALUE_OFFSET)?);
+    let std_dev = i128::from_le_bytes(oracle_field(data, SWITCHBOARD_RESULT_STD_DEV_OFFSET)?);
+    require!(value > 0, FlashLoanArbitrageError::InvalidOracleAccount);
+
+    Ok(OraclePrice {
+        price: value as u128,
+        confidence: std_dev.unsigned_abs(),
+        publish_time: i64::from_le_bytes(oracle_field(data, SWITCHBOARD_LAST_UPDATE_TIMESTAMP_OFFSET)?),
+    })
+}
+
+/// Rescales a price with a decimal exponent to 18 decimals
+fn scale_price(value: u128, exponent: i32) -> Result<u128> {
+    let shift = PRICE_DECIMALS
+        .checked_add(exponent)
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    let factor = 10u128
+        .checked_pow(shift.unsigned_abs())
+        .ok_or(FlashLoanArbitrageError::MathOverflow)?;
+    if shift >= 0 {
+        value.checked_mul(factor)
+    } else {
+        Some(value / factor)
+    }
+    .ok_or_else(|| error!(FlashLoanArbitrageError::MathOverflow))
+}
+
+fn oracle_field<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(
+        data.get(offset..offset + N)
+            .ok_or(FlashLoanArbitrageError::InvalidOracleAccount)?,
+    );
+    Ok(bytes)
+}
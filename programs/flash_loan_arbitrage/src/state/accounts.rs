@@ -1,5 +1,29 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use super::{Config, Operator, OracleEntry, PoolEntry, ProfitLedger, ProgramRegistry, Role, Strategy, Vault};
+
+/// The owner the user token accounts must have: the vault in vault mode, otherwise the
+/// authority
+pub fn token_owner(authority: &Signer, vault: &Option<Account<Vault>>) -> Pubkey {
+    match vault {
+        Some(vault) => vault.key(),
+        None => authority.key(),
+    }
+}
+
+/// The owner the arbitrage's user token accounts must have: the strategy when one is
+/// traded, otherwise as for `token_owner`
+pub fn arbitrage_token_owner(
+    authority: &Signer,
+    vault: &Option<Account<Vault>>,
+    strategy: &Option<Box<Account<Strategy>>>,
+) -> Pubkey {
+    match strategy {
+        Some(strategy) => strategy.key(),
+        None => token_owner(authority, vault),
+    }
+}
 
 // Common accounts that will be reused across different instructions
 #[derive(Accounts)]
@@ -8,8 +32,8 @@ pub struct ArbitrageState<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// SPL Token program
-    pub token_program: Program<'info, Token>,
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
     
     /// System program
     pub system_program: Program<'info, System>,
@@ -17,99 +41,837 @@ pub struct ArbitrageState<'info> {
 
 // Accounts needed for the flash loan and arbitrage instruction
 #[derive(Accounts)]
+#[instruction(loan_amount: u64, min_profit_amount: u64)]
 pub struct FlashLoanAndArbitrage<'info> {
     /// The base arbitrage accounts
     pub base: ArbitrageState<'info>,
+
+    /// The program's global config
+    #[account(seeds = [Config::SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The lender and DEX programs this instruction may call
+    #[account(seeds = [ProgramRegistry::SEED], bump = program_registry.bump)]
+    pub program_registry: Account<'info, ProgramRegistry>,
+
+    /// The vault, in vault mode. It then owns the user token accounts and signs the
+    /// swaps, the lender is repaid through the repayment token account and the
+    /// profit goes to the strategy or, without one, the profit ledger.
+    #[account(
+        seeds = [Vault::SEED],
+        bump = vault.bump,
+        constraint = repayment_token_account.is_some() @ crate::errors::FlashLoanArbitrageError::MissingRepaymentAccount,
+        constraint = strategy.is_some() || profit_ledger.is_some() @ crate::errors::FlashLoanArbitrageError::MissingProfitLedger
+    )]
+    pub vault: Option<Account<'info, Vault>>,
+
+    /// The authority's executor role
+    #[account(
+        seeds = [Operator::SEED, Role::Executor.seed(), base.authority.key().as_ref()],
+        bump = executor_role.bump
+    )]
+    pub executor_role: Account<'info, Operator>,
+
+    /// The loan mint's profit ledger, in vault mode, credited with the trade's profit
+    #[account(
+        mut,
+        seeds = [ProfitLedger::SEED, loan_token_account.mint.as_ref()],
+        bump = profit_ledger.bump
+    )]
+    pub profit_ledger: Option<Account<'info, ProfitLedger>>,
+
+    /// The strategy to trade for, in vault mode. It then owns the user token accounts
+    /// instead of the vault, the loan token account must be its assets account, and the
+    /// profit is added to its assets.
+    #[account(
+        mut,
+        seeds = [Strategy::SEED, strategy.mint.as_ref()],
+        bump = strategy.bump,
+        constraint = vault.is_some() @ crate::errors::FlashLoanArbitrageError::Unauthorized,
+        constraint = strategy.assets_token_account == loan_token_account.key() @ crate::errors::FlashLoanArbitrageError::StrategyAccountMismatch
+    )]
+    pub strategy: Option<Box<Account<'info, Strategy>>>,
     
     // === Lending Protocol Accounts ===
     
     /// The lending protocol program ID
-    pub lending_program: Program<'info, Token>,
+    /// CHECK: The handler checks it is approved for the selected lender in the program registry
+    #[account(executable)]
+    pub lending_program: AccountInfo<'info>,
     
     /// The loan token account of the arbitrageur
     #[account(
         mut,
-        token::authority = base.authority
+        constraint = loan_token_account.owner == arbitrage_token_owner(&base.authority, &vault, &strategy) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
+    )]
+    pub loan_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The loan mint, for the checked transfer of the repayment. It may not have a
+    /// transfer fee, which the repayment and profit are not counted net of.
+    #[account(
+        address = loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = !crate::dex::mint_has_transfer_fee(&loan_mint.to_account_info())? @ crate::errors::FlashLoanArbitrageError::LoanMintTransferFeeNotSupported
+    )]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The authority's token account the lender's repay draws from, in vault mode
+    ///
+    /// The PDA owning the loan token account cannot sign the lender's top-level repay,
+    /// so the handler moves exactly the repayment here once the repay is verified.
+    #[account(
+        mut,
+        constraint = repayment_token_account.mint == loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = repayment_token_account.owner == base.authority.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
     )]
-    pub loan_token_account: Account<'info, TokenAccount>,
+    pub repayment_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     
     /// The lending protocol's reserve account
+    /// CHECK: This account is validated in the instruction logic to be owned by the lending program
     #[account(mut)]
-    pub loan_reserve_account: Account<'info, TokenAccount>,
+    pub loan_reserve_account: AccountInfo<'info>,
     
-    /// The fee account for the lending protocol
+    /// The fee receiver token account of the lending reserve
+    /// CHECK: This account is matched against the flash repay instruction in the handler
     #[account(mut)]
-    pub lending_fee_account: Account<'info, TokenAccount>,
+    pub lending_fee_account: AccountInfo<'info>,
+
+    /// The instructions sysvar, used to find the lender's flash borrow and repay instructions
+    /// CHECK: The address is checked against the sysvar ID
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
     
     // === DEX A Accounts ===
     
     /// The DEX A program ID
-    pub dex_a_program: Program<'info, Token>,
+    /// CHECK: The handler checks it is approved for leg A's DEX in the program registry
+    #[account(executable)]
+    pub dex_a_program: AccountInfo<'info>,
     
     /// The DEX A pool account
-    /// CHECK: This account is verified by the DEX program
+    /// CHECK: This account is validated in the instruction logic to be owned by DEX A program
     #[account(mut)]
     pub dex_a_pool: AccountInfo<'info>,
+
+    /// The registry entry binding the DEX A pool to its vaults and mints
+    #[account(seeds = [PoolEntry::SEED, dex_a_pool.key().as_ref()], bump = dex_a_pool_entry.bump)]
+    pub dex_a_pool_entry: Box<Account<'info, PoolEntry>>,
     
-    /// The DEX A authority account
-    /// CHECK: DEX authority verified by the DEX program
+    /// The DEX A authority account, for DEXes whose swap takes a pool authority
+    /// CHECK: This account is validated by the DEX program
     pub dex_a_authority: AccountInfo<'info>,
     
-    /// The input token account for DEX A swap (loan token)
+    /// The input token account for DEX A swap (loan token), the loan token account itself
     #[account(
         mut,
-        token::authority = base.authority
+        address = loan_token_account.key() @ crate::errors::FlashLoanArbitrageError::RouteNotClosed,
+        constraint = dex_a_input_token_account.mint == loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = dex_a_input_token_account.owner == arbitrage_token_owner(&base.authority, &vault, &strategy) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
     )]
-    pub dex_a_input_token_account: Account<'info, TokenAccount>,
+    pub dex_a_input_token_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The output token account for DEX A swap (intermediate token)
     #[account(
         mut,
-        token::authority = base.authority
+        constraint = dex_a_output_token_account.owner == arbitrage_token_owner(&base.authority, &vault, &strategy) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
     )]
-    pub dex_a_output_token_account: Account<'info, TokenAccount>,
+    pub dex_a_output_token_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The DEX A pool's token A account
     #[account(mut)]
-    pub dex_a_token_a_account: Account<'info, TokenAccount>,
+    pub dex_a_token_a_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The DEX A pool's token B account
     #[account(mut)]
-    pub dex_a_token_b_account: Account<'info, TokenAccount>,
+    pub dex_a_token_b_account: InterfaceAccount<'info, TokenAccount>,
     
     // === DEX B Accounts ===
     
     /// The DEX B program ID
-    pub dex_b_program: Program<'info, Token>,
+    /// CHECK: The handler checks it is approved for leg B's DEX in the program registry
+    #[account(executable)]
+    pub dex_b_program: AccountInfo<'info>,
     
     /// The DEX B pool account
-    /// CHECK: This account is verified by the DEX program
+    /// CHECK: This account is validated in the instruction logic to be owned by DEX B program
     #[account(mut)]
     pub dex_b_pool: AccountInfo<'info>,
+
+    /// The registry entry binding the DEX B pool to its vaults and mints
+    #[account(seeds = [PoolEntry::SEED, dex_b_pool.key().as_ref()], bump = dex_b_pool_entry.bump)]
+    pub dex_b_pool_entry: Box<Account<'info, PoolEntry>>,
     
-    /// The DEX B authority account
-    /// CHECK: DEX authority verified by the DEX program
+    /// The DEX B authority account, for DEXes whose swap takes a pool authority
+    /// CHECK: This account is validated by the DEX program
     pub dex_b_authority: AccountInfo<'info>,
     
-    /// The input token account for DEX B swap (intermediate token)
+    /// The input token account for DEX B swap (intermediate token), the account DEX A
+    /// paid into
     #[account(
         mut,
-        token::authority = base.authority
+        address = dex_a_output_token_account.key() @ crate::errors::FlashLoanArbitrageError::RouteNotClosed,
+        constraint = dex_b_input_token_account.mint == dex_a_output_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = dex_b_input_token_account.owner == arbitrage_token_owner(&base.authority, &vault, &strategy) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
     )]
-    pub dex_b_input_token_account: Account<'info, TokenAccount>,
+    pub dex_b_input_token_account: InterfaceAccount<'info, TokenAccount>,
     
-    /// The output token account for DEX B swap (loan token)
+    /// The output token account for DEX B swap (loan token), the loan token account itself
     #[account(
         mut,
-        token::authority = base.authority
+        address = loan_token_account.key() @ crate::errors::FlashLoanArbitrageError::RouteNotClosed,
+        constraint = dex_b_output_token_account.mint == loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = dex_b_output_token_account.owner == arbitrage_token_owner(&base.authority, &vault, &strategy) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
     )]
-    pub dex_b_output_token_account: Account<'info, TokenAccount>,
+    pub dex_b_output_token_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The DEX B pool's token A account
     #[account(mut)]
-    pub dex_b_token_a_account: Account<'info, TokenAccount>,
+    pub dex_b_token_a_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The DEX B pool's token B account
     #[account(mut)]
-    pub dex_b_token_b_account: Account<'info, TokenAccount>,
-} 
\ No newline at end of file
+    pub dex_b_token_b_account: InterfaceAccount<'info, TokenAccount>,
+
+    // === Oracle Accounts ===
+
+    /// The oracle entry of the loan mint, to check the legs against oracle prices
+    #[account(
+        seeds = [OracleEntry::SEED, loan_token_account.mint.as_ref()],
+        bump = loan_oracle_entry.bump
+    )]
+    pub loan_oracle_entry: Option<Box<Account<'info, OracleEntry>>>,
+
+    /// The loan mint's price feed
+    /// CHECK: The handler matches it against the loan oracle entry and reads its price
+    pub loan_oracle: Option<AccountInfo<'info>>,
+
+    /// The oracle entry of the intermediate mint
+    #[account(
+        seeds = [OracleEntry::SEED, dex_a_output_token_account.mint.as_ref()],
+        bump = intermediate_oracle_entry.bump
+    )]
+    pub intermediate_oracle_entry: Option<Box<Account<'info, OracleEntry>>>,
+
+    /// The intermediate mint's price feed
+    /// CHECK: The handler matches it against the intermediate oracle entry and reads its price
+    pub intermediate_oracle: Option<AccountInfo<'info>>,
+} 
+
+// Accounts needed for the flash loan route instruction. The legs' accounts are
+// passed in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct FlashLoanRoute<'info> {
+    /// The base arbitrage accounts
+    pub base: ArbitrageState<'info>,
+
+    /// The program's global config
+    #[account(seeds = [Config::SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The lender and DEX programs this instruction may call
+    #[account(seeds = [ProgramRegistry::SEED], bump = program_registry.bump)]
+    pub program_registry: Account<'info, ProgramRegistry>,
+
+    /// The vault, in vault mode. It then owns the user token accounts and signs the
+    /// swaps, the lender is repaid through the repayment token account and the
+    /// profit is credited to the profit ledger.
+    #[account(
+        seeds = [Vault::SEED],
+        bump = vault.bump,
+        constraint = repayment_token_account.is_some() @ crate::errors::FlashLoanArbitrageError::MissingRepaymentAccount,
+        constraint = profit_ledger.is_some() @ crate::errors::FlashLoanArbitrageError::MissingProfitLedger
+    )]
+    pub vault: Option<Account<'info, Vault>>,
+
+    /// The authority's executor role
+    #[account(
+        seeds = [Operator::SEED, Role::Executor.seed(), base.authority.key().as_ref()],
+        bump = executor_role.bump
+    )]
+    pub executor_role: Account<'info, Operator>,
+
+    /// The loan mint's profit ledger, in vault mode, credited with the trade's profit
+    #[account(
+        mut,
+        seeds = [ProfitLedger::SEED, loan_token_account.mint.as_ref()],
+        bump = profit_ledger.bump
+    )]
+    pub profit_ledger: Option<Account<'info, ProfitLedger>>,
+
+    // === Lending Protocol Accounts ===
+
+    /// The lending protocol program ID
+    /// CHECK: The handler checks it is approved for the selected lender in the program registry
+    #[account(executable)]
+    pub lending_program: AccountInfo<'info>,
+
+    /// The loan token account of the arbitrageur, where the route starts and ends
+    #[account(
+        mut,
+        constraint = loan_token_account.owner == token_owner(&base.authority, &vault) @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
+    )]
+    pub loan_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The loan mint, for the checked transfer of the repayment. It may not have a
+    /// transfer fee, which the repayment and profit are not counted net of.
+    #[account(
+        address = loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = !crate::dex::mint_has_transfer_fee(&loan_mint.to_account_info())? @ crate::errors::FlashLoanArbitrageError::LoanMintTransferFeeNotSupported
+    )]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The authority's token account the lender's repay draws from, in vault mode
+    ///
+    /// The PDA owning the loan token account cannot sign the lender's top-level repay,
+    /// so the handler moves exactly the repayment here once the repay is verified.
+    #[account(
+        mut,
+        constraint = repayment_token_account.mint == loan_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch,
+        constraint = repayment_token_account.owner == base.authority.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
+    )]
+    pub repayment_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The lending protocol's reserve account
+    /// CHECK: This account is validated in the instruction logic to be owned by the lending program
+    #[account(mut)]
+    pub loan_reserve_account: AccountInfo<'info>,
+
+    /// The fee receiver token account of the lending reserve
+    /// CHECK: This account is matched against the flash repay instruction in the handler
+    #[account(mut)]
+    pub lending_fee_account: AccountInfo<'info>,
+
+    /// The instructions sysvar, used to find the lender's flash borrow and repay instructions
+    /// CHECK: The address is checked against the sysvar ID
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+// Accounts needed to create the global config
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// The first admin, who must be the program's upgrade authority
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The global config, created here
+    #[account(
+        init,
+        payer = admin,
+        space = Config::LEN,
+        seeds = [Config::SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// This program, to find its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::FlashLoanArbitrage>,
+
+    /// The program data account holding the upgrade authority
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ crate::errors::FlashLoanArbitrageError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// The first admin's role, created here
+    #[account(
+        init,
+        payer = admin,
+        space = Operator::LEN,
+        seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()],
+        bump
+    )]
+    pub admin_role: Account<'info, Operator>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to change the global config
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// An admin
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The global config
+    #[account(mut, seeds = [Config::SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+// Accounts needed to create the program registry
+#[derive(Accounts)]
+pub struct InitializeProgramRegistry<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The program registry, created empty here
+    #[account(
+        init,
+        payer = admin,
+        space = ProgramRegistry::LEN,
+        seeds = [ProgramRegistry::SEED],
+        bump
+    )]
+    pub program_registry: Account<'info, ProgramRegistry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to approve or revoke a program
+#[derive(Accounts)]
+pub struct UpdateProgramRegistry<'info> {
+    /// An admin
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The program registry
+    #[account(mut, seeds = [ProgramRegistry::SEED], bump = program_registry.bump)]
+    pub program_registry: Account<'info, ProgramRegistry>,
+}
+
+// Accounts needed to register a pool
+#[derive(Accounts)]
+pub struct RegisterPool<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The program registry, which must approve the pool's DEX program
+    #[account(seeds = [ProgramRegistry::SEED], bump = program_registry.bump)]
+    pub program_registry: Account<'info, ProgramRegistry>,
+
+    /// The pool's DEX program
+    /// CHECK: The handler checks it is approved in the program registry
+    #[account(executable)]
+    pub dex_program: AccountInfo<'info>,
+
+    /// The pool to register
+    /// CHECK: The handler checks it is owned by the DEX program and reads its vaults
+    pub pool: AccountInfo<'info>,
+
+    /// The pool's token A vault
+    /// CHECK: The handler matches it against the pool's state and reads its mint
+    pub pool_token_a_account: AccountInfo<'info>,
+
+    /// The pool's token B vault
+    /// CHECK: The handler matches it against the pool's state and reads its mint
+    pub pool_token_b_account: AccountInfo<'info>,
+
+    /// The pool's registry entry, created here
+    #[account(
+        init,
+        payer = admin,
+        space = PoolEntry::LEN,
+        seeds = [PoolEntry::SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_entry: Account<'info, PoolEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to remove a pool from the registry
+#[derive(Accounts)]
+pub struct DeregisterPool<'info> {
+    /// An admin, who receives the entry's rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The registry entry to close
+    #[account(mut, close = admin)]
+    pub pool_entry: Account<'info, PoolEntry>,
+}
+
+// Accounts needed to register a mint's oracle
+#[derive(Accounts)]
+pub struct RegisterOracle<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The mint the oracle prices
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The mint's price feed
+    /// CHECK: The handler checks its owner and reads its price as the given kind
+    pub oracle: AccountInfo<'info>,
+
+    /// The mint's oracle entry, created here
+    #[account(
+        init,
+        payer = admin,
+        space = OracleEntry::LEN,
+        seeds = [OracleEntry::SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub oracle_entry: Account<'info, OracleEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to remove a mint's oracle
+#[derive(Accounts)]
+pub struct DeregisterOracle<'info> {
+    /// An admin, who receives the entry's rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The oracle entry to close
+    #[account(mut, close = admin)]
+    pub oracle_entry: Account<'info, OracleEntry>,
+}
+
+// Accounts needed to create the vault
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The vault, created here
+    #[account(
+        init,
+        payer = admin,
+        space = Vault::LEN,
+        seeds = [Vault::SEED],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to create a mint's profit ledger
+#[derive(Accounts)]
+pub struct InitializeProfitLedger<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The mint whose profit the ledger tracks
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The profit ledger, created here
+    #[account(
+        init,
+        payer = admin,
+        space = ProfitLedger::LEN,
+        seeds = [ProfitLedger::SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub profit_ledger: Account<'info, ProfitLedger>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to move proceeds out of a vault token account
+#[derive(Accounts)]
+pub struct WithdrawProfit<'info> {
+    /// A withdrawer
+    pub withdrawer: Signer<'info>,
+
+    /// The withdrawer's role
+    #[account(
+        seeds = [Operator::SEED, Role::Withdrawer.seed(), withdrawer.key().as_ref()],
+        bump = withdrawer_role.bump
+    )]
+    pub withdrawer_role: Account<'info, Operator>,
+
+    /// The vault
+    #[account(seeds = [Vault::SEED], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// The vault token account to withdraw from
+    #[account(
+        mut,
+        constraint = vault_token_account.owner == vault.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountOwnerMismatch
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The profit ledger of the withdrawn mint, which caps the withdrawal
+    #[account(
+        mut,
+        seeds = [ProfitLedger::SEED, vault_token_account.mint.as_ref()],
+        bump = profit_ledger.bump
+    )]
+    pub profit_ledger: Account<'info, ProfitLedger>,
+
+    /// Where the proceeds go
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == vault_token_account.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The mint of the withdrawn token
+    #[account(address = vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Accounts needed to create a strategy
+#[derive(Accounts)]
+pub struct InitializeStrategy<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The mint the strategy holds and trades
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The strategy, created here
+    #[account(
+        init,
+        payer = admin,
+        space = Strategy::LEN,
+        seeds = [Strategy::SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub strategy: Box<Account<'info, Strategy>>,
+
+    /// The strategy's assets account, created here
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = strategy,
+        seeds = [Strategy::ASSETS_SEED, strategy.key().as_ref()],
+        bump
+    )]
+    pub assets_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The strategy's share mint, created here
+    #[account(
+        init,
+        payer = admin,
+        mint::decimals = mint.decimals,
+        mint::authority = strategy,
+        seeds = [Strategy::SHARES_SEED, strategy.key().as_ref()],
+        bump
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    /// Where the performance fees go
+    #[account(
+        constraint = treasury_token_account.mint == mint.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to deposit into a strategy
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    /// The depositor
+    pub depositor: Signer<'info>,
+
+    /// The global config
+    #[account(seeds = [Config::SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The strategy
+    #[account(mut, seeds = [Strategy::SEED, strategy.mint.as_ref()], bump = strategy.bump)]
+    pub strategy: Box<Account<'info, Strategy>>,
+
+    /// The strategy's mint
+    #[account(address = strategy.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The strategy's assets account
+    #[account(mut, address = strategy.assets_token_account)]
+    pub assets_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The strategy's share mint
+    #[account(mut, address = strategy.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    /// The token account the deposit comes from
+    #[account(
+        mut,
+        constraint = depositor_token_account.mint == strategy.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The token account the shares go to
+    #[account(
+        mut,
+        constraint = depositor_share_account.mint == share_mint.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub depositor_share_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Accounts needed to redeem strategy shares
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    /// The depositor
+    pub depositor: Signer<'info>,
+
+    /// The strategy
+    #[account(mut, seeds = [Strategy::SEED, strategy.mint.as_ref()], bump = strategy.bump)]
+    pub strategy: Box<Account<'info, Strategy>>,
+
+    /// The strategy's mint
+    #[account(address = strategy.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The strategy's assets account
+    #[account(mut, address = strategy.assets_token_account)]
+    pub assets_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The strategy's share mint
+    #[account(mut, address = strategy.share_mint)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    /// The token account the shares are burned from
+    #[account(
+        mut,
+        constraint = depositor_share_account.mint == share_mint.key() @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub depositor_share_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The token account the assets go to
+    #[account(
+        mut,
+        constraint = depositor_token_account.mint == strategy.mint @ crate::errors::FlashLoanArbitrageError::TokenAccountMintMismatch
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Accounts needed to send a strategy's accrued fees to its treasury
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    /// A withdrawer
+    pub withdrawer: Signer<'info>,
+
+    /// The withdrawer's role
+    #[account(
+        seeds = [Operator::SEED, Role::Withdrawer.seed(), withdrawer.key().as_ref()],
+        bump = withdrawer_role.bump
+    )]
+    pub withdrawer_role: Account<'info, Operator>,
+
+    /// The strategy
+    #[account(mut, seeds = [Strategy::SEED, strategy.mint.as_ref()], bump = strategy.bump)]
+    pub strategy: Box<Account<'info, Strategy>>,
+
+    /// The strategy's mint
+    #[account(address = strategy.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The strategy's assets account
+    #[account(mut, address = strategy.assets_token_account)]
+    pub assets_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The strategy's treasury
+    #[account(mut, address = strategy.treasury_token_account)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token or Token-2022 program
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Accounts needed to grant a role
+#[derive(Accounts)]
+#[instruction(operator: Pubkey, role: Role)]
+pub struct AddOperator<'info> {
+    /// An admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The granted role, created here
+    #[account(
+        init,
+        payer = admin,
+        space = Operator::LEN,
+        seeds = [Operator::SEED, role.seed(), operator.as_ref()],
+        bump
+    )]
+    pub operator_role: Account<'info, Operator>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts needed to revoke a role
+#[derive(Accounts)]
+#[instruction(operator: Pubkey, role: Role)]
+pub struct RemoveOperator<'info> {
+    /// An admin, who receives the role's rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The admin's role
+    #[account(seeds = [Operator::SEED, Role::Admin.seed(), admin.key().as_ref()], bump = admin_role.bump)]
+    pub admin_role: Account<'info, Operator>,
+
+    /// The role to revoke
+    #[account(
+        mut,
+        close = admin,
+        seeds = [Operator::SEED, role.seed(), operator.as_ref()],
+        bump = operator_role.bump
+    )]
+    pub operator_role: Account<'info, Operator>,
+}
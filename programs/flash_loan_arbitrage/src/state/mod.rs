@@ -1,5 +1,452 @@
+//! Accounts the program owns
+
+use anchor_lang::prelude::*;
+
+use crate::dex::{self, DexKind, Leg};
+use crate::errors::FlashLoanArbitrageError;
+use crate::lending::LendingProvider;
+use crate::oracle::OracleKind;
+
 pub mod accounts;
-pub mod errors;
 
-pub use accounts::*;
-pub use errors::*; 
\ No newline at end of file
+/// Limits the arbitrage instructions enforce, set by the admin
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfigParams {
+    /// Minimum loan amount in base units
+    pub min_loan_amount: u64,
+    /// Maximum loan amount in base units
+    pub max_loan_amount: u64,
+    /// Lowest `min_profit_amount` a caller may ask for, in base units
+    pub min_profit_threshold: u64,
+    /// Slippage tolerance applied to each leg's quote, in basis points
+    pub default_slippage_bps: u16,
+    /// Highest slippage a caller may ask for instead of the default, in basis points
+    pub max_slippage_bps: u16,
+    /// Most slots a trade's quote may be older than the slot it executes in
+    pub max_quote_age_slots: u64,
+    /// Whether the arbitrage instructions reject transactions with instructions the
+    /// transaction guard does not allow
+    pub transaction_guard: bool,
+    /// Whether other programs may invoke the arbitrage instructions through CPI
+    pub allow_cpi: bool,
+    /// Most a leg's execution price may deviate from the oracle prices, in basis points
+    pub oracle_max_deviation_bps: u16,
+    /// Widest oracle confidence interval accepted, in basis points of the price
+    pub oracle_max_confidence_bps: u16,
+    /// Most seconds an oracle price may be older than the trade
+    pub oracle_max_age_secs: u64,
+}
+
+impl ConfigParams {
+    /// Checks the limits are consistent with each other
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.min_loan_amount > 0
+                && self.min_loan_amount <= self.max_loan_amount
+                && self.default_slippage_bps <= self.max_slippage_bps
+                && self.max_slippage_bps <= 10_000
+                && self.max_quote_age_slots > 0
+                && self.oracle_max_deviation_bps <= 10_000
+                && self.oracle_max_confidence_bps <= 10_000,
+            FlashLoanArbitrageError::InvalidConfig
+        );
+        Ok(())
+    }
+}
+
+/// When a trade must land, as set by its caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TradeDeadline {
+    /// The last slot the trade may execute in
+    pub valid_until_slot: u64,
+    /// The slot the caller's quote was taken at
+    pub quote_slot: u64,
+}
+
+/// The program's global config, a PDA at `[Config::SEED]`
+#[account]
+pub struct Config {
+    /// The upgrade authority that created the config and holds the first admin role
+    pub admin: Pubkey,
+    /// Emergency switch that stops every arbitrage instruction
+    pub paused: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub min_profit_threshold: u64,
+    pub default_slippage_bps: u16,
+    pub max_slippage_bps: u16,
+    pub max_quote_age_slots: u64,
+    pub transaction_guard: bool,
+    pub allow_cpi: bool,
+    pub oracle_max_deviation_bps: u16,
+    pub oracle_max_confidence_bps: u16,
+    pub oracle_max_age_secs: u64,
+    pub bump: u8,
+}
+
+impl Config {
+    pub const SEED: &'static [u8] = b"config";
+
+    // Discriminator + admin + paused + three limits + two slippages + quote age +
+    // guard flags + oracle bands + oracle age + bump
+    pub const LEN: usize = 8 + 32 + 1 + 8 * 3 + 2 * 2 + 8 + 2 + 2 * 2 + 8 + 1;
+
+    /// Overwrites the limits with `params`
+    pub fn set_params(&mut self, params: &ConfigParams) {
+        self.min_loan_amount = params.min_loan_amount;
+        self.max_loan_amount = params.max_loan_amount;
+        self.min_profit_threshold = params.min_profit_threshold;
+        self.default_slippage_bps = params.default_slippage_bps;
+        self.max_slippage_bps = params.max_slippage_bps;
+        self.max_quote_age_slots = params.max_quote_age_slots;
+        self.transaction_guard = params.transaction_guard;
+        self.allow_cpi = params.allow_cpi;
+        self.oracle_max_deviation_bps = params.oracle_max_deviation_bps;
+        self.oracle_max_confidence_bps = params.oracle_max_confidence_bps;
+        self.oracle_max_age_secs = params.oracle_max_age_secs;
+    }
+
+    /// Rejects the call while the program is paused
+    pub fn check_not_paused(&self) -> Result<()> {
+        require!(!self.paused, FlashLoanArbitrageError::ProgramPaused);
+        Ok(())
+    }
+
+    /// Checks a loan and the caller's minimum profit against the limits
+    pub fn check_trade(&self, loan_amount: u64, min_profit_amount: u64) -> Result<()> {
+        require!(
+            loan_amount >= self.min_loan_amount,
+            FlashLoanArbitrageError::LoanAmountTooSmall
+        );
+        require!(
+            loan_amount <= self.max_loan_amount,
+            FlashLoanArbitrageError::LoanAmountTooLarge
+        );
+        require!(
+            min_profit_amount >= self.min_profit_threshold,
+            FlashLoanArbitrageError::InsufficientProfit
+        );
+        Ok(())
+    }
+
+    /// The slippage a trade applies to its quotes: the caller's override, which may not
+    /// exceed the maximum, or else the default
+    pub fn slippage_bps(&self, slippage_override: Option<u16>) -> Result<u16> {
+        match slippage_override {
+            Some(slippage_bps) => {
+                require!(
+                    slippage_bps <= self.max_slippage_bps,
+                    FlashLoanArbitrageError::SlippageAboveMaximum
+                );
+                Ok(slippage_bps)
+            }
+            None => Ok(self.default_slippage_bps),
+        }
+    }
+
+    /// Rejects a trade past its deadline or priced from a quote that is too old
+    ///
+    /// The quote may not be from a slot later than `current_slot`.
+    pub fn check_deadline(&self, current_slot: u64, deadline: &TradeDeadline) -> Result<()> {
+        require!(
+            current_slot <= deadline.valid_until_slot,
+            FlashLoanArbitrageError::DeadlineExceeded
+        );
+        let quote_age = current_slot
+            .checked_sub(deadline.quote_slot)
+            .ok_or(FlashLoanArbitrageError::StaleQuote)?;
+        require!(
+            quote_age <= self.max_quote_age_slots,
+            FlashLoanArbitrageError::StaleQuote
+        );
+        Ok(())
+    }
+}
+
+/// The adapter an approved program may be driven by
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgramKind {
+    Lender(LendingProvider),
+    Dex(DexKind),
+    /// Not a program but a tip account the transaction guard lets system transfers pay
+    Tip,
+}
+
+/// A program ID the admin has approved for one adapter
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ApprovedProgram {
+    pub program_id: Pubkey,
+    pub kind: ProgramKind,
+}
+
+impl ApprovedProgram {
+    // Program ID + kind and adapter tags
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+/// The lender and DEX programs the arbitrage instructions may call, a PDA at
+/// `[ProgramRegistry::SEED]`
+///
+/// A program used by two adapters, such as Whirlpool's `swap` and `swap_v2`, needs an
+/// entry for each. The registry also lists the tip accounts the transaction guard
+/// allows.
+#[account]
+pub struct ProgramRegistry {
+    pub programs: Vec<ApprovedProgram>,
+    pub bump: u8,
+}
+
+impl ProgramRegistry {
+    pub const SEED: &'static [u8] = b"program_registry";
+    pub const MAX_PROGRAMS: usize = 24;
+
+    // Discriminator + vector length and entries + bump
+    pub const LEN: usize = 8 + 4 + Self::MAX_PROGRAMS * ApprovedProgram::LEN + 1;
+
+    pub fn is_approved(&self, program_id: &Pubkey, kind: ProgramKind) -> bool {
+        self.programs
+            .iter()
+            .any(|entry| entry.program_id == *program_id && entry.kind == kind)
+    }
+
+    /// Rejects a program that is not approved for `kind`
+    pub fn check_approved(&self, program_id: &Pubkey, kind: ProgramKind) -> Result<()> {
+        if !self.is_approved(program_id, kind) {
+            msg!("Program {} is not approved for {:?}", program_id, kind);
+            return err!(FlashLoanArbitrageError::ProgramNotApproved);
+        }
+        Ok(())
+    }
+
+    /// Adds an entry, doing nothing if it is already approved
+    pub fn approve(&mut self, entry: ApprovedProgram) -> Result<()> {
+        if self.is_approved(&entry.program_id, entry.kind) {
+            return Ok(());
+        }
+        require!(
+            self.programs.len() < Self::MAX_PROGRAMS,
+            FlashLoanArbitrageError::ProgramRegistryFull
+        );
+        self.programs.push(entry);
+        Ok(())
+    }
+
+    /// Removes an entry
+    pub fn revoke(&mut self, entry: ApprovedProgram) -> Result<()> {
+        let index = self
+            .programs
+            .iter()
+            .position(|approved| *approved == entry)
+            .ok_or(FlashLoanArbitrageError::ProgramNotApproved)?;
+        self.programs.swap_remove(index);
+        Ok(())
+    }
+}
+
+/// A pool the admin has registered, a PDA at `[PoolEntry::SEED, pool]`
+///
+/// Binds the pool to its vaults and mints, as read from the pool when it was
+/// registered, and to the adapter that swaps through it.
+#[account]
+pub struct PoolEntry {
+    pub pool: Pubkey,
+    pub dex: DexKind,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Fee tier in hundredths of a basis point, for off-chain routing
+    pub fee_rate: u32,
+    pub bump: u8,
+}
+
+impl PoolEntry {
+    pub const SEED: &'static [u8] = b"pool";
+
+    // Discriminator + pool + DEX tag + vaults + mints + fee rate + bump
+    pub const LEN: usize = 8 + 32 + 1 + 32 * 2 + 32 * 2 + 4 + 1;
+
+    /// Checks the leg swaps through this pool, with its adapter, vaults and mints
+    pub fn check_leg(&self, leg: &Leg) -> Result<()> {
+        let (input_mint, output_mint) = if leg.a_to_b {
+            (self.mint_a, self.mint_b)
+        } else {
+            (self.mint_b, self.mint_a)
+        };
+        require!(
+            self.pool == leg.accounts.pool.key()
+                && self.dex == leg.dex
+                && self.vault_a == leg.accounts.pool_token_a_account.key()
+                && self.vault_b == leg.accounts.pool_token_b_account.key()
+                && dex::token_account_mint(leg.accounts.input_token_account)? == input_mint
+                && dex::token_account_mint(leg.accounts.output_token_account)? == output_mint,
+            FlashLoanArbitrageError::InvalidPoolAccount
+        );
+        Ok(())
+    }
+}
+
+/// The oracle the admin has registered for a mint, a PDA at `[OracleEntry::SEED, mint]`
+#[account]
+pub struct OracleEntry {
+    pub mint: Pubkey,
+    pub kind: OracleKind,
+    /// The price feed account
+    pub oracle: Pubkey,
+    /// The mint's decimals, to value amounts at the oracle's price
+    pub decimals: u8,
+    pub bump: u8,
+}
+
+impl OracleEntry {
+    pub const SEED: &'static [u8] = b"oracle";
+
+    // Discriminator + mint + oracle tag + oracle + decimals + bump
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 1 + 1;
+}
+
+/// The PDA that owns vault-mode token accounts and signs their swaps, at `[Vault::SEED]`
+///
+/// Profits stay in the vault's token accounts until a withdrawer withdraws them, up to
+/// what each mint's `ProfitLedger` has credited.
+/// Executors may only trigger trades.
+#[account]
+pub struct Vault {
+    pub bump: u8,
+}
+
+impl Vault {
+    pub const SEED: &'static [u8] = b"vault";
+
+    // Discriminator + bump
+    pub const LEN: usize = 8 + 1;
+
+    /// The seeds the vault signs with
+    pub fn signer_seeds(&self) -> [&[u8]; 2] {
+        [Self::SEED, core::slice::from_ref(&self.bump)]
+    }
+}
+
+/// The profit the vault has made in one mint, a PDA at `[ProfitLedger::SEED, mint]`
+///
+/// The vault's token accounts also hold the capital the arbitrage trades with, so
+/// withdrawers may only take out what the arbitrage has added to them.
+#[account]
+pub struct ProfitLedger {
+    pub mint: Pubkey,
+    /// Profit credited by vault-mode trades and not yet withdrawn
+    pub accrued_profit: u64,
+    pub bump: u8,
+}
+
+impl ProfitLedger {
+    pub const SEED: &'static [u8] = b"profit_ledger";
+
+    // Discriminator + mint + accrued profit + bump
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
+
+/// A profit-sharing strategy for one mint, a PDA at `[Strategy::SEED, mint]`
+///
+/// Depositors fund the strategy's assets account and receive shares. Arbitrage traded
+/// from the assets account adds its profit, less the performance fee, to
+/// `total_assets`, which sets the share price. The strategy PDA owns the assets
+/// account and every token account its trades use, and is the share mint's authority.
+///
+/// `total_assets` only changes through deposits, withdrawals and credited profit, so
+/// tokens sent straight to the assets account do not move the share price.
+#[account]
+pub struct Strategy {
+    pub mint: Pubkey,
+    pub assets_token_account: Pubkey,
+    pub share_mint: Pubkey,
+    /// Where collected performance fees go
+    pub treasury_token_account: Pubkey,
+    /// Share of each trade's profit kept as a performance fee, in basis points
+    pub performance_fee_bps: u16,
+    /// Assets backing the shares
+    pub total_assets: u64,
+    /// Performance fees held in the assets account until they are collected
+    pub accrued_fees: u64,
+    pub bump: u8,
+}
+
+impl Strategy {
+    pub const SEED: &'static [u8] = b"strategy";
+    pub const ASSETS_SEED: &'static [u8] = b"strategy_assets";
+    pub const SHARES_SEED: &'static [u8] = b"strategy_shares";
+
+    // Discriminator + mint + three token accounts and mints + fee + totals + bump
+    pub const LEN: usize = 8 + 32 * 4 + 2 + 8 * 2 + 1;
+
+    // Virtual shares and assets added to both sides of the share price, so that the
+    // first deposit is priced like any other
+    const VIRTUAL_SHARES: u128 = 1;
+    const VIRTUAL_ASSETS: u128 = 1;
+
+    /// The seeds the strategy signs with
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [Self::SEED, self.mint.as_ref(), core::slice::from_ref(&self.bump)]
+    }
+
+    /// The shares a deposit of `amount` mints, rounded down
+    pub fn shares_for_deposit(&self, amount: u64, share_supply: u64) -> Option<u64> {
+        let shares = amount as u128 * (share_supply as u128 + Self::VIRTUAL_SHARES)
+            / (self.total_assets as u128 + Self::VIRTUAL_ASSETS);
+        u64::try_from(shares).ok()
+    }
+
+    /// The assets `shares` redeem for, rounded down
+    pub fn assets_for_shares(&self, shares: u64, share_supply: u64) -> Option<u64> {
+        let assets = shares as u128 * (self.total_assets as u128 + Self::VIRTUAL_ASSETS)
+            / (share_supply as u128 + Self::VIRTUAL_SHARES);
+        u64::try_from(assets).ok()
+    }
+
+    /// Adds a trade's profit, setting the performance fee aside, and returns the fee
+    pub fn credit_profit(&mut self, profit: u64) -> Option<u64> {
+        let fee = u64::try_from(profit as u128 * self.performance_fee_bps as u128 / 10_000).ok()?;
+        self.total_assets = self.total_assets.checked_add(profit - fee)?;
+        self.accrued_fees = self.accrued_fees.checked_add(fee)?;
+        Some(fee)
+    }
+}
+
+/// What an operator key may do
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Manages the config, registries, vault, strategies and roles
+    Admin,
+    /// Runs arbitrage from the vault's and strategies' token accounts
+    Executor,
+    /// Moves profit and fees out of the vault and strategies
+    Withdrawer,
+}
+
+impl Role {
+    /// The seed that sets this role's PDAs apart
+    pub fn seed(self) -> &'static [u8] {
+        match self {
+            Role::Admin => b"admin",
+            Role::Executor => b"executor",
+            Role::Withdrawer => b"withdrawer",
+        }
+    }
+}
+
+/// One role granted to one key, a PDA at `[Operator::SEED, role.seed(), operator]`
+///
+/// A key holding several roles has one account per role.
+#[account]
+pub struct Operator {
+    pub operator: Pubkey,
+    pub role: Role,
+    pub bump: u8,
+}
+
+impl Operator {
+    pub const SEED: &'static [u8] = b"operator";
+
+    // Discriminator + operator + role tag + bump
+    pub const LEN: usize = 8 + 32 + 1 + 1;
+}
@@ -0,0 +1,399 @@
+//! The tick walk in `dex::clmm` against a step-by-step reference in arbitrary-precision
+//! integers, for Whirlpool and Raydium CLMM pools, and checks that a quote depends only
+//! on the ticks the swap crosses
+
+use flash_loan_arbitrage_program::dex::clmm::{
+    quote_exact_input, raydium_sqrt_price_from_tick_index, sqrt_price_from_tick_index, ClmmPool, ClmmProgram,
+    QuoteError, SwapQuote, TickArrayView, FEE_RATE_DENOMINATOR, MAX_SQRT_PRICE_X64, MAX_TICK_INDEX,
+    MIN_SQRT_PRICE_X64, MIN_TICK_INDEX, RAYDIUM_MAX_SQRT_PRICE_X64,
+};
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+const TICKS_PER_ARRAY: i32 = 8;
+
+/// A tick array held as `liquidity_net` per offset, `None` where the tick is not initialized
+#[derive(Clone, Debug)]
+struct TickArray {
+    start_tick_index: i32,
+    ticks: Vec<Option<i128>>,
+}
+
+impl TickArrayView for TickArray {
+    fn start_tick_index(&self) -> i32 {
+        self.start_tick_index
+    }
+
+    fn liquidity_net(&self, offset: usize) -> Option<i128> {
+        self.ticks.get(offset).copied().flatten()
+    }
+}
+
+/// A pool with the tick arrays a swap in one direction passes
+#[derive(Clone, Debug)]
+struct Swap {
+    pool: ClmmPool,
+    tick_arrays: Vec<TickArray>,
+    amount: u64,
+    a_to_b: bool,
+    sqrt_price_limit: u128,
+}
+
+impl Swap {
+    fn sqrt_price_at(&self, tick: i32) -> Result<u128, QuoteError> {
+        self.pool.program.sqrt_price_from_tick_index(tick)
+    }
+
+    fn quote(&self) -> Result<SwapQuote, QuoteError> {
+        quote_exact_input(&self.pool, &self.tick_arrays, self.amount, self.a_to_b, self.sqrt_price_limit)
+    }
+
+    /// Initialized ticks in the order the swap reaches them, ending with the edge of the
+    /// last tick array
+    fn stops(&self) -> Vec<(i32, Option<i128>)> {
+        let spacing = self.pool.tick_spacing as i32;
+        let current = self.pool.tick_current_index;
+        let mut stops: Vec<_> = self
+            .tick_arrays
+            .iter()
+            .flat_map(|array| {
+                array.ticks.iter().enumerate().filter_map(move |(offset, net)| {
+                    net.map(|net| (array.start_tick_index + offset as i32 * spacing, Some(net)))
+                })
+            })
+            .filter(|(tick, _)| if self.a_to_b { *tick <= current } else { *tick > current })
+            .collect();
+        stops.sort_by_key(|(tick, _)| if self.a_to_b { -tick } else { *tick });
+
+        let last = self.tick_arrays.last().unwrap().start_tick_index;
+        let edge = if self.a_to_b { last } else { last + spacing * (TICKS_PER_ARRAY - 1) };
+        if stops.last().map(|(tick, _)| *tick) != Some(edge) {
+            stops.push((edge, None));
+        }
+        stops
+    }
+}
+
+fn big(value: u128) -> BigUint {
+    BigUint::from(value)
+}
+
+fn q64() -> BigUint {
+    BigUint::from(1u8) << 64u32
+}
+
+fn div_ceil(numerator: BigUint, denominator: &BigUint) -> BigUint {
+    (numerator + denominator - 1u32) / denominator
+}
+
+fn fits_u64(value: &BigUint) -> Option<u64> {
+    u64::try_from(value).ok()
+}
+
+/// Input between two prices, rounded up
+fn reference_input(from: u128, to: u128, liquidity: u128, a_to_b: bool) -> BigUint {
+    let (lower, upper) = (from.min(to), from.max(to));
+    let numerator = big(liquidity) * big(upper - lower);
+    if a_to_b {
+        div_ceil(numerator * q64(), &(big(lower) * big(upper)))
+    } else {
+        div_ceil(numerator, &q64())
+    }
+}
+
+/// Output between two prices, rounded down
+fn reference_output(from: u128, to: u128, liquidity: u128, a_to_b: bool) -> BigUint {
+    let (lower, upper) = (from.min(to), from.max(to));
+    let numerator = big(liquidity) * big(upper - lower);
+    if a_to_b {
+        numerator / q64()
+    } else {
+        numerator * q64() / (big(lower) * big(upper))
+    }
+}
+
+/// The quote worked out one range at a time, each step in exact integers
+fn reference_quote(swap: &Swap) -> Result<SwapQuote, QuoteError> {
+    let pool = &swap.pool;
+    let limit = match swap.sqrt_price_limit {
+        0 => pool.program.default_sqrt_price_limit(swap.a_to_b),
+        limit => limit,
+    };
+    let max_sqrt_price = pool.program.max_sqrt_price();
+    let fee_rate = big(pool.fee_rate as u128);
+    let fee_complement = big(FEE_RATE_DENOMINATOR - pool.fee_rate as u128);
+
+    let mut remaining = swap.amount;
+    let mut amount_out = 0u64;
+    let mut fee_amount = 0u64;
+    let mut sqrt_price = pool.sqrt_price;
+    let mut liquidity = pool.liquidity;
+    let mut stops = swap.stops().into_iter();
+
+    while remaining > 0 && sqrt_price != limit {
+        let Some((tick, liquidity_net)) = stops.next() else {
+            return Err(QuoteError::TickArraySequence);
+        };
+        let tick_price = swap.sqrt_price_at(tick)?;
+        let target = if swap.a_to_b { tick_price.max(limit) } else { tick_price.min(limit) };
+
+        let after_fee = big(remaining as u128) * &fee_complement / big(FEE_RATE_DENOMINATOR);
+        let to_target = reference_input(sqrt_price, target, liquidity, swap.a_to_b);
+        let reaches_target = fits_u64(&to_target).is_some() && to_target <= after_fee;
+        let next = if reaches_target {
+            target
+        } else if swap.a_to_b {
+            let numerator = big(liquidity) * big(sqrt_price) * q64();
+            let denominator = big(liquidity) * q64() + &after_fee * big(sqrt_price);
+            u128::try_from(div_ceil(numerator, &denominator)).map_err(|_| QuoteError::MathOverflow)?
+        } else {
+            if liquidity == 0 {
+                return Err(QuoteError::MathOverflow);
+            }
+            let delta = u128::try_from(&after_fee * q64() / big(liquidity)).unwrap();
+            sqrt_price.checked_add(delta).ok_or(QuoteError::MathOverflow)?
+        };
+        if !(MIN_SQRT_PRICE_X64..=max_sqrt_price).contains(&next) {
+            return Err(QuoteError::MathOverflow);
+        }
+
+        let step_out = fits_u64(&reference_output(sqrt_price, next, liquidity, swap.a_to_b))
+            .ok_or(QuoteError::MathOverflow)?;
+        let step_in = if reaches_target {
+            to_target
+        } else {
+            reference_input(sqrt_price, next, liquidity, swap.a_to_b)
+        };
+        let step_in = fits_u64(&step_in).ok_or(QuoteError::MathOverflow)?;
+        let step_fee = if reaches_target {
+            fits_u64(&div_ceil(big(step_in as u128) * &fee_rate, &fee_complement)).ok_or(QuoteError::MathOverflow)?
+        } else {
+            remaining - step_in
+        };
+
+        remaining = remaining.checked_sub(step_in + step_fee).ok_or(QuoteError::MathOverflow)?;
+        amount_out = amount_out.checked_add(step_out).ok_or(QuoteError::MathOverflow)?;
+        fee_amount += step_fee;
+        if next == tick_price {
+            if let Some(net) = liquidity_net {
+                let net = if swap.a_to_b { -net } else { net };
+                liquidity = liquidity.checked_add_signed(net).ok_or(QuoteError::MathOverflow)?;
+            }
+        }
+        sqrt_price = next;
+    }
+
+    Ok(SwapQuote {
+        amount_in: swap.amount - remaining,
+        amount_out,
+        fee_amount,
+        end_sqrt_price: sqrt_price,
+    })
+}
+
+/// Pools of one to three tick arrays with sparse initialized ticks and the current tick
+/// in the first array's slot, the rest in the swap direction. Raydium pools may leave
+/// out up to two arrays before each one, the first included.
+fn swap() -> impl Strategy<Value = Swap> {
+    let ticks = prop::collection::vec(
+        prop_oneof![2 => Just(None), 1 => (-10i128.pow(17)..10i128.pow(17)).prop_map(Some)],
+        TICKS_PER_ARRAY as usize,
+    );
+    (
+        prop::sample::select(vec![1u16, 8, 64]),
+        -100i32..100,
+        prop::collection::vec(ticks, 1..=3),
+        0..TICKS_PER_ARRAY - 1,
+        0.0..1.0f64,
+        any::<bool>(),
+        10u128.pow(15)..10u128.pow(20),
+        prop_oneof![0u32..=30_000, Just(100_000)],
+        prop_oneof![any::<u64>(), 0u64..1_000_000_000_000],
+        prop::option::of(0i32..2_000),
+        prop_oneof![Just(ClmmProgram::Whirlpool), Just(ClmmProgram::RaydiumClmm)],
+        prop::collection::vec(prop_oneof![2 => Just(0i32), 1 => 1i32..=2], 3),
+    )
+        .prop_map(
+            |(
+                spacing,
+                base,
+                arrays,
+                current_offset,
+                within_tick,
+                a_to_b,
+                liquidity,
+                fee_rate,
+                amount,
+                limit_ticks,
+                program,
+                gaps,
+            )| {
+                let spacing_ticks = spacing as i32;
+                let ticks_in_array = TICKS_PER_ARRAY * spacing_ticks;
+                let start = base * ticks_in_array;
+                let step = if a_to_b { -ticks_in_array } else { ticks_in_array };
+                let mut slot = 0;
+                let tick_arrays: Vec<_> = arrays
+                    .into_iter()
+                    .zip(gaps)
+                    .map(|(ticks, gap)| {
+                        if program == ClmmProgram::RaydiumClmm {
+                            slot += gap;
+                        }
+                        let tick_array = TickArray { start_tick_index: start + step * slot, ticks };
+                        slot += 1;
+                        tick_array
+                    })
+                    .collect();
+
+                // Somewhere inside a tick of the first array's slot
+                let tick = start + current_offset * spacing_ticks + (within_tick * spacing as f64) as i32;
+                let lower = program.sqrt_price_from_tick_index(tick).unwrap();
+                let upper = program.sqrt_price_from_tick_index(tick + 1).unwrap();
+                let sqrt_price = lower + ((upper - lower) as f64 * within_tick) as u128;
+
+                let sqrt_price_limit = match limit_ticks {
+                    None => 0,
+                    Some(ticks) if a_to_b => program.sqrt_price_from_tick_index(tick - ticks).unwrap(),
+                    Some(ticks) => program.sqrt_price_from_tick_index(tick + 1 + ticks).unwrap(),
+                };
+                Swap {
+                    pool: ClmmPool {
+                        program,
+                        sqrt_price,
+                        liquidity,
+                        tick_current_index: tick,
+                        tick_spacing: spacing,
+                        fee_rate,
+                        ticks_per_array: TICKS_PER_ARRAY,
+                    },
+                    tick_arrays,
+                    amount,
+                    a_to_b,
+                    sqrt_price_limit,
+                }
+            },
+        )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2048))]
+
+    #[test]
+    fn quote_matches_reference(swap in swap()) {
+        prop_assert_eq!(swap.quote(), reference_quote(&swap));
+    }
+
+    /// Initialized ticks past where the swap ends do not change its quote
+    #[test]
+    fn quote_ignores_ticks_it_does_not_reach(swap in swap(), net in 1i128..10i128.pow(17)) {
+        let Ok(quote) = swap.quote() else { return Ok(()) };
+        let spacing = swap.pool.tick_spacing as i32;
+        let mut changed = swap.clone();
+        for array in &mut changed.tick_arrays {
+            for (offset, tick) in array.ticks.iter_mut().enumerate() {
+                let price = swap.sqrt_price_at(array.start_tick_index + offset as i32 * spacing).unwrap();
+                let beyond = if swap.a_to_b { price < quote.end_sqrt_price } else { price > quote.end_sqrt_price };
+                if beyond {
+                    *tick = tick.map_or(Some(net), |_| None);
+                }
+            }
+        }
+        prop_assert_eq!(changed.quote(), Ok(quote));
+    }
+
+    /// Another tick array after the last does not change a swap that ends before its edge
+    #[test]
+    fn quote_ignores_tick_arrays_it_does_not_reach(swap in swap()) {
+        let Ok(quote) = swap.quote() else { return Ok(()) };
+        let edge = swap.stops().last().unwrap().0;
+        prop_assume!(quote.end_sqrt_price != swap.sqrt_price_at(edge).unwrap());
+
+        let mut extended = swap.clone();
+        let last = swap.tick_arrays.last().unwrap().start_tick_index;
+        let ticks_in_array = TICKS_PER_ARRAY * swap.pool.tick_spacing as i32;
+        extended.tick_arrays.push(TickArray {
+            start_tick_index: if swap.a_to_b { last - ticks_in_array } else { last + ticks_in_array },
+            ticks: vec![Some(1); TICKS_PER_ARRAY as usize],
+        });
+        prop_assert_eq!(extended.quote(), Ok(quote));
+    }
+
+    #[test]
+    fn sqrt_price_rises_with_tick(tick in MIN_TICK_INDEX..MAX_TICK_INDEX) {
+        prop_assert!(sqrt_price_from_tick_index(tick).unwrap() < sqrt_price_from_tick_index(tick + 1).unwrap());
+        prop_assert!(raydium_sqrt_price_from_tick_index(tick).unwrap() < raydium_sqrt_price_from_tick_index(tick + 1).unwrap());
+    }
+
+    /// Within a part in 10^10 of `sqrt(1.0001^tick) * 2^64`
+    #[test]
+    fn sqrt_price_matches_float(tick in -200_000i32..200_000) {
+        let expected = 1.0001f64.powf(tick as f64 / 2.0) * 2f64.powi(64);
+        for sqrt_price in [sqrt_price_from_tick_index(tick), raydium_sqrt_price_from_tick_index(tick)] {
+            let sqrt_price = sqrt_price.unwrap() as f64;
+            prop_assert!((sqrt_price - expected).abs() / expected < 1e-10, "{} vs {}", sqrt_price, expected);
+        }
+    }
+}
+
+#[test]
+fn sqrt_price_at_known_ticks() {
+    assert_eq!(sqrt_price_from_tick_index(0), Ok(1 << 64));
+    assert_eq!(sqrt_price_from_tick_index(MIN_TICK_INDEX), Ok(MIN_SQRT_PRICE_X64));
+    assert_eq!(sqrt_price_from_tick_index(MAX_TICK_INDEX), Ok(MAX_SQRT_PRICE_X64));
+    assert_eq!(sqrt_price_from_tick_index(MIN_TICK_INDEX - 1), Err(QuoteError::MathOverflow));
+    assert_eq!(sqrt_price_from_tick_index(MAX_TICK_INDEX + 1), Err(QuoteError::MathOverflow));
+
+    // Raydium rounds the same ticks differently, which moves its upper bound
+    assert_eq!(raydium_sqrt_price_from_tick_index(0), Ok(1 << 64));
+    assert_eq!(raydium_sqrt_price_from_tick_index(-1), Ok(18_445_821_805_675_395_072));
+    assert_eq!(sqrt_price_from_tick_index(-1), Ok(18_445_821_805_675_392_311));
+    assert_eq!(raydium_sqrt_price_from_tick_index(MIN_TICK_INDEX), Ok(MIN_SQRT_PRICE_X64));
+    assert_eq!(raydium_sqrt_price_from_tick_index(MAX_TICK_INDEX), Ok(RAYDIUM_MAX_SQRT_PRICE_X64));
+    assert_eq!(raydium_sqrt_price_from_tick_index(MIN_TICK_INDEX - 1), Err(QuoteError::MathOverflow));
+    assert_eq!(raydium_sqrt_price_from_tick_index(MAX_TICK_INDEX + 1), Err(QuoteError::MathOverflow));
+}
+
+/// Crossing the edge of the first tick array into the next, in both directions
+#[test]
+fn crosses_into_the_next_tick_array() {
+    for a_to_b in [true, false] {
+        let mut ticks = vec![None; TICKS_PER_ARRAY as usize];
+        let (start, current, crossed) = if a_to_b {
+            ticks[0] = Some(-1_000_000_000_000);
+            (0, 3, 0)
+        } else {
+            ticks[TICKS_PER_ARRAY as usize - 1] = Some(1_000_000_000_000);
+            (0, 3, TICKS_PER_ARRAY - 1)
+        };
+        let step = if a_to_b { -TICKS_PER_ARRAY } else { TICKS_PER_ARRAY };
+        let swap = Swap {
+            pool: ClmmPool {
+                program: ClmmProgram::Whirlpool,
+                sqrt_price: sqrt_price_from_tick_index(current).unwrap(),
+                liquidity: 10_000_000_000_000,
+                tick_current_index: current,
+                tick_spacing: 1,
+                fee_rate: 3_000,
+                ticks_per_array: TICKS_PER_ARRAY,
+            },
+            tick_arrays: vec![
+                TickArray { start_tick_index: start, ticks },
+                TickArray { start_tick_index: start + step, ticks: vec![None; TICKS_PER_ARRAY as usize] },
+            ],
+            amount: 4_000_000_000,
+            a_to_b,
+            sqrt_price_limit: 0,
+        };
+
+        let quote = swap.quote().unwrap();
+        assert_eq!(quote, reference_quote(&swap).unwrap());
+        assert_eq!(quote.amount_in, swap.amount);
+        let crossed_price = sqrt_price_from_tick_index(crossed).unwrap();
+        if a_to_b {
+            assert!(quote.end_sqrt_price < crossed_price);
+        } else {
+            assert!(quote.end_sqrt_price > crossed_price);
+        }
+    }
+}
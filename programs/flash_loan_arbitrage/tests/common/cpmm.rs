@@ -0,0 +1,212 @@
+//! A stand-in for Raydium CPMM's `swap_base_input` at the CPMM program address
+//!
+//! Pools use CPMM's `PoolState` and `AmmConfig` layouts and swap at the constant
+//! product after the trade fee, with the vaults owned by CPMM's vault authority PDA.
+//! A pool can be set to pay short of what it checked against `minimum_amount_out`,
+//! like a DEX whose output differs from its quote.
+
+use anchor_spl::token_2022::spl_token_2022::instruction::transfer_checked;
+use flash_loan_arbitrage_program::dex::raydium::{
+    CPMM_AMM_CONFIG_DISCRIMINATOR, CPMM_FEE_RATE_DENOMINATOR, CPMM_POOL_STATE_DISCRIMINATOR,
+    CPMM_SWAP_BASE_INPUT_DISCRIMINATOR,
+};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+use super::{read_u64, write_pubkey, TokenProgram};
+
+pub const ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+
+const VAULT_AUTHORITY_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+// `PoolState` and `AmmConfig` layouts
+const POOL_LEN: usize = 637;
+const POOL_AMM_CONFIG_OFFSET: usize = 8;
+const POOL_TOKEN_0_VAULT_OFFSET: usize = 72;
+const POOL_TOKEN_1_VAULT_OFFSET: usize = 104;
+// Unused padding of `PoolState` that holds how far the pool pays short, in bps
+const POOL_SHORTFALL_BPS_OFFSET: usize = 600;
+const AMM_CONFIG_LEN: usize = 236;
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 12;
+
+// Mint decimals sit after the optional mint authority and the supply
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// Raised when the swap pays less than `minimum_amount_out`, like CPMM's
+/// `ExceededSlippage`
+pub const EXCEEDED_SLIPPAGE: u32 = 6005;
+
+/// A pool of two mints with its config, observation account and vaults
+pub struct Pool {
+    pub pool: Pubkey,
+    pub amm_config: Pubkey,
+    pub observation_state: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub token_0_program: TokenProgram,
+    pub token_1_program: TokenProgram,
+}
+
+impl Pool {
+    /// Adds a pool holding `reserves` of token 0 and token 1 that charges
+    /// `trade_fee_rate` in hundredths of a basis point
+    pub fn add(
+        program_test: &mut ProgramTest,
+        mints: [(Pubkey, TokenProgram); 2],
+        reserves: [u64; 2],
+        trade_fee_rate: u64,
+    ) -> Self {
+        let [(token_0_mint, token_0_program), (token_1_mint, token_1_program)] = mints;
+        let pool = Self {
+            pool: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            token_0_vault: Pubkey::new_unique(),
+            token_1_vault: Pubkey::new_unique(),
+            token_0_mint,
+            token_1_mint,
+            token_0_program,
+            token_1_program,
+        };
+
+        let mut config = vec![0u8; AMM_CONFIG_LEN];
+        config[..8].copy_from_slice(&CPMM_AMM_CONFIG_DISCRIMINATOR);
+        config[AMM_CONFIG_TRADE_FEE_RATE_OFFSET..AMM_CONFIG_TRADE_FEE_RATE_OFFSET + 8]
+            .copy_from_slice(&trade_fee_rate.to_le_bytes());
+        add_program_account(program_test, pool.amm_config, config);
+
+        let mut state = vec![0u8; POOL_LEN];
+        state[..8].copy_from_slice(&CPMM_POOL_STATE_DISCRIMINATOR);
+        write_pubkey(&mut state, POOL_AMM_CONFIG_OFFSET, &pool.amm_config);
+        write_pubkey(&mut state, POOL_TOKEN_0_VAULT_OFFSET, &pool.token_0_vault);
+        write_pubkey(&mut state, POOL_TOKEN_1_VAULT_OFFSET, &pool.token_1_vault);
+        add_program_account(program_test, pool.pool, state);
+        add_program_account(program_test, pool.observation_state, vec![0u8; 8]);
+
+        let authority = vault_authority();
+        token_0_program.add_token_account(program_test, pool.token_0_vault, &token_0_mint, &authority, reserves[0]);
+        token_1_program.add_token_account(program_test, pool.token_1_vault, &token_1_mint, &authority, reserves[1]);
+        pool
+    }
+
+    /// The accounts a leg on this pool passes after its fixed accounts
+    pub fn leg_accounts(&self) -> [Pubkey; 6] {
+        [
+            self.amm_config,
+            self.observation_state,
+            self.token_0_mint,
+            self.token_1_mint,
+            self.token_0_program.id(),
+            self.token_1_program.id(),
+        ]
+    }
+}
+
+/// Makes `pool` pay `shortfall_bps` less than the output it checks against the
+/// swap's minimum
+pub async fn set_shortfall(context: &mut ProgramTestContext, pool: &Pubkey, shortfall_bps: u16) {
+    let account = context.banks_client.get_account(*pool).await.unwrap().unwrap();
+    let mut account = AccountSharedData::from(account);
+    account.data_as_mut_slice()[POOL_SHORTFALL_BPS_OFFSET..POOL_SHORTFALL_BPS_OFFSET + 2]
+        .copy_from_slice(&shortfall_bps.to_le_bytes());
+    assert_eq!(account.owner(), &ID);
+    context.set_account(pool, &account);
+}
+
+/// The PDA that owns every pool's vaults
+pub fn vault_authority() -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_AUTHORITY_SEED], &ID).0
+}
+
+fn add_program_account(program_test: &mut ProgramTest, address: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: ID,
+            ..Account::default()
+        },
+    );
+}
+
+/// The constant product output of `amount_in`, after a trade fee taken from the input
+/// and rounded up
+pub fn swap_output(amount_in: u64, reserve_in: u64, reserve_out: u64, trade_fee_rate: u64) -> u64 {
+    let fee = (amount_in as u128 * trade_fee_rate as u128).div_ceil(CPMM_FEE_RATE_DENOMINATOR as u128);
+    let amount_in_after_fee = amount_in as u128 - fee;
+    (reserve_out as u128 * amount_in_after_fee / (reserve_in as u128 + amount_in_after_fee)) as u64
+}
+
+/// `swap_base_input`: `[payer, authority, amm_config, pool_state, input_token_account,
+/// output_token_account, input_vault, output_vault, input_token_program,
+/// output_token_program, input_token_mint, output_token_mint, observation_state]`
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 24 || data[..8] != CPMM_SWAP_BASE_INPUT_DISCRIMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount_in = read_u64(data, 8);
+    let minimum_amount_out = read_u64(data, 16);
+    let [payer, authority, amm_config, pool_state, input_token_account, output_token_account, input_vault, output_vault, input_token_program, output_token_program, input_mint, output_mint, ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let trade_fee_rate = read_u64(&amm_config.try_borrow_data()?, AMM_CONFIG_TRADE_FEE_RATE_OFFSET);
+    let reserve_in = read_u64(&input_vault.try_borrow_data()?, 64);
+    let reserve_out = read_u64(&output_vault.try_borrow_data()?, 64);
+    let amount_out = swap_output(amount_in, reserve_in, reserve_out, trade_fee_rate);
+    if amount_out < minimum_amount_out {
+        return Err(ProgramError::Custom(EXCEEDED_SLIPPAGE));
+    }
+    let shortfall_bps = {
+        let data = pool_state.try_borrow_data()?;
+        u16::from_le_bytes([data[POOL_SHORTFALL_BPS_OFFSET], data[POOL_SHORTFALL_BPS_OFFSET + 1]]) as u64
+    };
+    let amount_out = amount_out - amount_out * shortfall_bps / 10_000;
+
+    let decimals = |mint: &AccountInfo| -> Result<u8, ProgramError> { Ok(mint.try_borrow_data()?[MINT_DECIMALS_OFFSET]) };
+    let ix = transfer_checked(
+        input_token_program.key,
+        input_token_account.key,
+        input_mint.key,
+        input_vault.key,
+        payer.key,
+        &[],
+        amount_in,
+        decimals(input_mint)?,
+    )?;
+    invoke(
+        &ix,
+        &[input_token_account.clone(), input_mint.clone(), input_vault.clone(), payer.clone(), input_token_program.clone()],
+    )?;
+
+    let (_, bump) = Pubkey::find_program_address(&[VAULT_AUTHORITY_SEED], &ID);
+    let ix = transfer_checked(
+        output_token_program.key,
+        output_vault.key,
+        output_mint.key,
+        output_token_account.key,
+        authority.key,
+        &[],
+        amount_out,
+        decimals(output_mint)?,
+    )?;
+    invoke_signed(
+        &ix,
+        &[output_vault.clone(), output_mint.clone(), output_token_account.clone(), authority.clone(), output_token_program.clone()],
+        &[&[VAULT_AUTHORITY_SEED, &[bump]]],
+    )
+}
@@ -0,0 +1,99 @@
+//! A stand-in for MarginFi v2's flash loan instructions at MarginFi's program address
+//!
+//! Borrow and repay move tokens out of and back into the bank's liquidity vault.
+//! Unlike MarginFi, start and end flashloan do nothing, so a transaction that leaves
+//! one out reaches the arbitrage program's own check.
+
+#[allow(deprecated)]
+use anchor_spl::token_2022::spl_token_2022::instruction::transfer;
+use flash_loan_arbitrage_program::lending::marginfi::{
+    BORROW_DISCRIMINATOR, END_FLASHLOAN_DISCRIMINATOR, REPAY_DISCRIMINATOR, START_FLASHLOAN_DISCRIMINATOR,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use super::{add_account, read_u64, TokenProgram};
+
+pub const ID: Pubkey = pubkey!("MFv2hWf31Z9kbCa1snEPYctwafyhdvnV7FZnsebVacA");
+
+const LIQUIDITY_VAULT_AUTHORITY_SEED: &[u8] = b"liquidity_vault_auth";
+
+/// A bank of the loan mint with its liquidity vault, and the MarginFi account that
+/// borrows from it
+pub struct Bank {
+    pub group: Pubkey,
+    pub marginfi_account: Pubkey,
+    pub bank: Pubkey,
+    pub liquidity_vault: Pubkey,
+}
+
+impl Bank {
+    /// Adds a bank whose liquidity vault holds `liquidity` tokens
+    pub fn add(program_test: &mut ProgramTest, mint: &Pubkey, token_program: TokenProgram, liquidity: u64) -> Self {
+        let bank = Self {
+            group: Pubkey::new_unique(),
+            marginfi_account: Pubkey::new_unique(),
+            bank: Pubkey::new_unique(),
+            liquidity_vault: Pubkey::new_unique(),
+        };
+        add_account(program_test, bank.bank, ID, vec![0u8; 8]);
+        add_account(program_test, bank.marginfi_account, ID, vec![0u8; 8]);
+        let authority = vault_authority(&bank.bank).0;
+        token_program.add_token_account(program_test, bank.liquidity_vault, mint, &authority, liquidity);
+        bank
+    }
+}
+
+/// The PDA that signs transfers out of a bank's liquidity vault
+pub fn vault_authority(bank: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LIQUIDITY_VAULT_AUTHORITY_SEED, bank.as_ref()], &ID)
+}
+
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let discriminator: [u8; 8] = data
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match discriminator {
+        START_FLASHLOAN_DISCRIMINATOR | END_FLASHLOAN_DISCRIMINATOR => Ok(()),
+        BORROW_DISCRIMINATOR => borrow(accounts, read_u64(data, 8)),
+        REPAY_DISCRIMINATOR => repay(accounts, read_u64(data, 8)),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// `[group, marginfi_account, signer, bank, destination_token_account,
+/// bank_liquidity_vault_authority, bank_liquidity_vault, token_program]`
+fn borrow(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let [_group, _marginfi_account, _signer, bank, destination, authority, vault, token_program, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let bump = vault_authority(bank.key).1;
+    #[allow(deprecated)]
+    let ix = transfer(token_program.key, vault.key, destination.key, authority.key, &[], amount)?;
+    invoke_signed(
+        &ix,
+        &[vault.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        &[&[LIQUIDITY_VAULT_AUTHORITY_SEED, bank.key.as_ref(), &[bump]]],
+    )
+}
+
+/// `[group, marginfi_account, signer, bank, signer_token_account,
+/// bank_liquidity_vault, token_program]`
+fn repay(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let [_group, _marginfi_account, signer, _bank, source, vault, token_program, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    #[allow(deprecated)]
+    let ix = transfer(token_program.key, source.key, vault.key, signer.key, &[], amount)?;
+    invoke(&ix, &[source.clone(), vault.clone(), signer.clone(), token_program.clone()])
+}
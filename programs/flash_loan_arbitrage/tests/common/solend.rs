@@ -0,0 +1,193 @@
+//! A stand-in for Solend's flash borrow and repay at Solend's program address
+//!
+//! It reads the reserve at the offsets the arbitrage program reads and charges the
+//! same fees, but unlike Solend the borrow does not look ahead for its repay, so a
+//! transaction without one reaches the arbitrage program's own check.
+//!
+//! The reserve and lending market are written to Solend's layouts with everything its
+//! flash borrow and repay read, so the real program dumped to `tests/fixtures/solend.so`
+//! runs on the same accounts.
+
+#[allow(deprecated)]
+use anchor_spl::token_2022::spl_token_2022::instruction::transfer;
+use flash_loan_arbitrage_program::lending::solend::{
+    calculate_flash_loan_fees, ReserveInfo, FLASH_BORROW_RESERVE_LIQUIDITY,
+    FLASH_REPAY_RESERVE_LIQUIDITY,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+use super::{write_pubkey, write_u64, TokenProgram, LOAN_DECIMALS};
+
+pub const ID: Pubkey = pubkey!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
+
+// Solend's account version and fixed-point scale
+const PROGRAM_VERSION: u8 = 1;
+const WAD: u128 = 1_000_000_000_000_000_000;
+
+// Offsets inside a packed Solend `Reserve` account
+const RESERVE_LEN: usize = 619;
+const RESERVE_LENDING_MARKET_OFFSET: usize = 10;
+const RESERVE_LIQUIDITY_MINT_OFFSET: usize = 42;
+const RESERVE_LIQUIDITY_MINT_DECIMALS_OFFSET: usize = 74;
+const RESERVE_LIQUIDITY_SUPPLY_OFFSET: usize = 75;
+const RESERVE_LIQUIDITY_AVAILABLE_AMOUNT_OFFSET: usize = 171;
+const RESERVE_LIQUIDITY_MARKET_PRICE_OFFSET: usize = 211;
+const RESERVE_FLASH_LOAN_FEE_WAD_OFFSET: usize = 314;
+const RESERVE_HOST_FEE_PERCENTAGE_OFFSET: usize = 322;
+const RESERVE_FEE_RECEIVER_OFFSET: usize = 339;
+const RESERVE_RATE_LIMITER_OFFSET: usize = 389;
+
+// Offsets inside a packed Solend `LendingMarket` account
+const LENDING_MARKET_LEN: usize = 290;
+const LENDING_MARKET_BUMP_SEED_OFFSET: usize = 1;
+const LENDING_MARKET_OWNER_OFFSET: usize = 2;
+const LENDING_MARKET_TOKEN_PROGRAM_OFFSET: usize = 66;
+const LENDING_MARKET_RATE_LIMITER_OFFSET: usize = 162;
+
+// A `RateLimiter` starts with its config: the window in slots, then the most that may
+// flow out in one window
+const RATE_LIMITER_MAX_OUTFLOW_OFFSET: usize = 8;
+
+/// A reserve of the loan mint with its supply and fee receiver
+pub struct Reserve {
+    pub reserve: Pubkey,
+    pub lending_market: Pubkey,
+    pub market_authority: Pubkey,
+    pub liquidity_supply: Pubkey,
+    pub fee_receiver: Pubkey,
+}
+
+impl Reserve {
+    /// Adds a reserve holding `supply` tokens that charges `flash_loan_fee_wad`
+    pub fn add(
+        program_test: &mut ProgramTest,
+        mint: &Pubkey,
+        token_program: TokenProgram,
+        supply: u64,
+        flash_loan_fee_wad: u64,
+    ) -> Self {
+        let lending_market = Pubkey::new_unique();
+        let (market_authority, bump_seed) = market_authority(&lending_market);
+        let reserve = Self {
+            reserve: Pubkey::new_unique(),
+            lending_market,
+            market_authority,
+            liquidity_supply: Pubkey::new_unique(),
+            fee_receiver: Pubkey::new_unique(),
+        };
+
+        let mut data = vec![0u8; RESERVE_LEN];
+        data[0] = PROGRAM_VERSION;
+        write_pubkey(&mut data, RESERVE_LENDING_MARKET_OFFSET, &reserve.lending_market);
+        write_pubkey(&mut data, RESERVE_LIQUIDITY_MINT_OFFSET, mint);
+        data[RESERVE_LIQUIDITY_MINT_DECIMALS_OFFSET] = LOAN_DECIMALS;
+        write_pubkey(&mut data, RESERVE_LIQUIDITY_SUPPLY_OFFSET, &reserve.liquidity_supply);
+        write_u64(&mut data, RESERVE_LIQUIDITY_AVAILABLE_AMOUNT_OFFSET, supply);
+        data[RESERVE_LIQUIDITY_MARKET_PRICE_OFFSET..RESERVE_LIQUIDITY_MARKET_PRICE_OFFSET + 16]
+            .copy_from_slice(&WAD.to_le_bytes());
+        write_u64(&mut data, RESERVE_FLASH_LOAN_FEE_WAD_OFFSET, flash_loan_fee_wad);
+        data[RESERVE_HOST_FEE_PERCENTAGE_OFFSET] = 0;
+        write_pubkey(&mut data, RESERVE_FEE_RECEIVER_OFFSET, &reserve.fee_receiver);
+        write_unlimited_rate_limiter(&mut data, RESERVE_RATE_LIMITER_OFFSET);
+        add_solend_account(program_test, reserve.reserve, data);
+
+        let mut data = vec![0u8; LENDING_MARKET_LEN];
+        data[0] = PROGRAM_VERSION;
+        data[LENDING_MARKET_BUMP_SEED_OFFSET] = bump_seed;
+        write_pubkey(&mut data, LENDING_MARKET_OWNER_OFFSET, &Pubkey::new_unique());
+        write_pubkey(&mut data, LENDING_MARKET_TOKEN_PROGRAM_OFFSET, &token_program.id());
+        write_unlimited_rate_limiter(&mut data, LENDING_MARKET_RATE_LIMITER_OFFSET);
+        add_solend_account(program_test, reserve.lending_market, data);
+
+        token_program.add_token_account(program_test, reserve.liquidity_supply, mint, &market_authority, supply);
+        token_program.add_token_account(program_test, reserve.fee_receiver, mint, &Pubkey::new_unique(), 0);
+        reserve
+    }
+}
+
+fn add_solend_account(program_test: &mut ProgramTest, address: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: ID,
+            ..Account::default()
+        },
+    );
+}
+
+/// Writes a rate limiter that lets any amount out in its one-slot window
+fn write_unlimited_rate_limiter(data: &mut [u8], offset: usize) {
+    write_u64(data, offset, 1);
+    write_u64(data, offset + RATE_LIMITER_MAX_OUTFLOW_OFFSET, u64::MAX);
+}
+
+/// The lending market authority PDA that owns the market's supplies
+pub fn market_authority(lending_market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[lending_market.as_ref()], &ID)
+}
+
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let amount = data
+        .get(1..9)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match data[0] {
+        FLASH_BORROW_RESERVE_LIQUIDITY => flash_borrow(accounts, amount),
+        FLASH_REPAY_RESERVE_LIQUIDITY => flash_repay(accounts, amount),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// `[source_liquidity, destination_liquidity, reserve, lending_market,
+/// lending_market_authority, instructions_sysvar, token_program]`
+fn flash_borrow(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let [source, destination, _reserve, lending_market, authority, _instructions_sysvar, token_program, ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let bump = market_authority(lending_market.key).1;
+    #[allow(deprecated)]
+    let ix = transfer(token_program.key, source.key, destination.key, authority.key, &[], amount)?;
+    invoke_signed(
+        &ix,
+        &[source.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        &[&[lending_market.key.as_ref(), &[bump]]],
+    )
+}
+
+/// `[source_liquidity, destination_liquidity, reserve_liquidity_fee_receiver,
+/// host_fee_receiver, reserve, lending_market, user_transfer_authority,
+/// instructions_sysvar, token_program]`
+fn flash_repay(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let [source, destination, fee_receiver, host_fee_receiver, reserve, _lending_market, authority, _instructions_sysvar, token_program, ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let reserve = ReserveInfo::unpack(&reserve.try_borrow_data()?)?;
+    let (origination_fee, host_fee) =
+        calculate_flash_loan_fees(amount, reserve.flash_loan_fee_wad, reserve.host_fee_percentage)?;
+    for (recipient, amount) in [(destination, amount), (fee_receiver, origination_fee), (host_fee_receiver, host_fee)] {
+        if amount == 0 {
+            continue;
+        }
+        #[allow(deprecated)]
+        let ix = transfer(token_program.key, source.key, recipient.key, authority.key, &[], amount)?;
+        invoke(&ix, &[source.clone(), recipient.clone(), authority.clone(), token_program.clone()])?;
+    }
+    Ok(())
+}
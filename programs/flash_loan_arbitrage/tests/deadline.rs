@@ -0,0 +1,74 @@
+//! The trade deadline and quote age are checked against the slot the arbitrage runs in
+
+mod common;
+
+use common::{assert_error, program_test, Arbitrage, MAX_QUOTE_AGE_SLOTS};
+use flash_loan_arbitrage_program::{errors::FlashLoanArbitrageError, state::TradeDeadline};
+use solana_sdk::instruction::Instruction;
+
+const LOAN_AMOUNT: u64 = 1_000_000_000;
+const QUOTE_SLOT: u64 = 1_000;
+
+/// A borrow, arbitrage and repay with `deadline`
+fn instructions(arbitrage: &Arbitrage, deadline: TradeDeadline) -> [Instruction; 3] {
+    let mut args = arbitrage.args(LOAN_AMOUNT, deadline.quote_slot);
+    args.deadline = deadline;
+    [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), args),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ]
+}
+
+#[tokio::test]
+async fn trade_past_its_deadline_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let deadline = TradeDeadline {
+        valid_until_slot: QUOTE_SLOT + 10,
+        quote_slot: QUOTE_SLOT,
+    };
+
+    // The deadline's own slot is still in time
+    context.warp_to_slot(QUOTE_SLOT + 10).unwrap();
+    arbitrage.send(&mut context, &instructions(&arbitrage, deadline)).await.unwrap();
+
+    context.warp_to_slot(QUOTE_SLOT + 11).unwrap();
+    let result = arbitrage.send(&mut context, &instructions(&arbitrage, deadline)).await;
+    assert_error(result, 1, FlashLoanArbitrageError::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn quote_older_than_the_configured_age_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let deadline = TradeDeadline {
+        valid_until_slot: QUOTE_SLOT + MAX_QUOTE_AGE_SLOTS * 10,
+        quote_slot: QUOTE_SLOT,
+    };
+
+    context.warp_to_slot(QUOTE_SLOT + MAX_QUOTE_AGE_SLOTS).unwrap();
+    arbitrage.send(&mut context, &instructions(&arbitrage, deadline)).await.unwrap();
+
+    // Well inside the deadline, but one slot too old
+    context.warp_to_slot(QUOTE_SLOT + MAX_QUOTE_AGE_SLOTS + 1).unwrap();
+    let result = arbitrage.send(&mut context, &instructions(&arbitrage, deadline)).await;
+    assert_error(result, 1, FlashLoanArbitrageError::StaleQuote);
+}
+
+#[tokio::test]
+async fn quote_from_a_later_slot_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    context.warp_to_slot(QUOTE_SLOT).unwrap();
+
+    let deadline = TradeDeadline {
+        valid_until_slot: QUOTE_SLOT + MAX_QUOTE_AGE_SLOTS,
+        quote_slot: QUOTE_SLOT + 1,
+    };
+    let result = arbitrage.send(&mut context, &instructions(&arbitrage, deadline)).await;
+    assert_error(result, 1, FlashLoanArbitrageError::StaleQuote);
+}
@@ -0,0 +1,108 @@
+//! `dex::execute_leg` reports what the output token account actually received,
+//! read from its data after the swap, rather than what the pool was quoted to pay.
+//! The profit is likewise worked out from the loan token account's data.
+
+mod common;
+
+use common::{
+    assert_error, cpmm, program_test, set_token_balance, token_balance, Arbitrage, FLASH_LOAN_FEE_WAD,
+    POOL_A_RESERVES, POOL_B_RESERVES, TRADE_FEE_RATE,
+};
+use flash_loan_arbitrage_program::{errors::FlashLoanArbitrageError, lending::LendingProvider};
+
+const LOAN_AMOUNT: u64 = 1_000_000_000;
+const SHORTFALL_BPS: u16 = 100;
+
+#[tokio::test]
+async fn second_leg_swaps_what_the_first_delivered() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    cpmm::set_shortfall(&mut context, &arbitrage.pool_a.pool, SHORTFALL_BPS).await;
+
+    let mut args = arbitrage.args(LOAN_AMOUNT, 0);
+    args.slippage_bps = Some(500);
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), args),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    arbitrage.send(&mut context, &instructions).await.unwrap();
+
+    // Leg B spent the 1% less leg A paid, not leg A's quote, which the account
+    // would not have covered
+    let quoted = cpmm::swap_output(LOAN_AMOUNT, POOL_A_RESERVES[0], POOL_A_RESERVES[1], TRADE_FEE_RATE);
+    let delivered = quoted - quoted * SHORTFALL_BPS as u64 / 10_000;
+    let returned = cpmm::swap_output(delivered, POOL_B_RESERVES[1], POOL_B_RESERVES[0], TRADE_FEE_RATE);
+    let lender_fee = (LOAN_AMOUNT as u128 * FLASH_LOAN_FEE_WAD as u128 / 10u128.pow(18)) as u64;
+    assert_eq!(token_balance(&mut context, arbitrage.intermediate_token_account).await, 0);
+    assert_eq!(
+        token_balance(&mut context, arbitrage.pool_b.token_1_vault).await,
+        POOL_B_RESERVES[1] + delivered
+    );
+    assert_eq!(
+        token_balance(&mut context, arbitrage.loan_token_account).await,
+        returned - LOAN_AMOUNT - lender_fee
+    );
+}
+
+#[tokio::test]
+async fn delivery_short_of_the_minimum_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    // The pool checks the minimum against the full output, then pays 1% less
+    cpmm::set_shortfall(&mut context, &arbitrage.pool_a.pool, SHORTFALL_BPS).await;
+
+    let mut args = arbitrage.args(LOAN_AMOUNT, 0);
+    args.slippage_bps = Some(0);
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), args),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::FirstSwapInsufficientOutput);
+}
+
+#[tokio::test]
+async fn second_leg_short_of_its_minimum_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    cpmm::set_shortfall(&mut context, &arbitrage.pool_b.pool, SHORTFALL_BPS).await;
+
+    // Leg B must deliver its full quote, which pool B checks before paying 1% less
+    let intermediate = cpmm::swap_output(LOAN_AMOUNT, POOL_A_RESERVES[0], POOL_A_RESERVES[1], TRADE_FEE_RATE);
+    let mut args = arbitrage.args(LOAN_AMOUNT, 0);
+    args.min_out_leg_b = cpmm::swap_output(intermediate, POOL_B_RESERVES[1], POOL_B_RESERVES[0], TRADE_FEE_RATE);
+    args.slippage_bps = Some(500);
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), args),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::SecondSwapInsufficientOutput);
+}
+
+#[tokio::test]
+async fn losing_round_trip_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // Pool B prices the pair like pool A, so each leg gets its quote and the round trip only pays both
+    // trade fees. The authority's own funds cover the loss, so the repayment is still there.
+    set_token_balance(&mut context, arbitrage.pool_b.token_0_vault, POOL_A_RESERVES[0]).await;
+    set_token_balance(&mut context, arbitrage.pool_b.token_1_vault, POOL_A_RESERVES[1]).await;
+    set_token_balance(&mut context, arbitrage.loan_token_account, LOAN_AMOUNT).await;
+
+    // MarginFi charges no fee, so Anchor's cached balance, which predates the swaps, would show neither
+    // profit nor loss. Only the balance read from the account after the swaps shows the loss.
+    let mut args = arbitrage.args(LOAN_AMOUNT, 0);
+    args.provider = LendingProvider::MarginFi;
+    let ix = arbitrage.instruction(arbitrage.accounts_for(LendingProvider::MarginFi), args);
+    let result = arbitrage.send(&mut context, &arbitrage.marginfi_flash_loan(LOAN_AMOUNT, ix)).await;
+    assert_error(result, 2, FlashLoanArbitrageError::InsufficientProfit);
+}
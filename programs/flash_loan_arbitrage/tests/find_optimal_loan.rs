@@ -0,0 +1,162 @@
+//! `find_optimal_loan` against the profit worked out for every loan size, and held to
+//! the config's loan limits
+
+mod common;
+
+use anchor_lang::AnchorDeserialize;
+use common::{
+    cpmm, program_test, set_loan_limits, Arbitrage, FLASH_LOAN_FEE_WAD, POOL_A_RESERVES, POOL_B_RESERVES,
+    TRADE_FEE_RATE,
+};
+use flash_loan_arbitrage_program::{
+    errors::FlashLoanArbitrageError, instructions::OptimalLoan, lending::solend::calculate_flash_loan_fees,
+    lending::LendingProvider,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+/// Both legs' outputs and the profit after the Solend fee of borrowing `loan_amount`
+fn reference(loan_amount: u64) -> ([u64; 2], i128) {
+    let intermediate_amount = cpmm::swap_output(loan_amount, POOL_A_RESERVES[0], POOL_A_RESERVES[1], TRADE_FEE_RATE);
+    let final_amount = cpmm::swap_output(intermediate_amount, POOL_B_RESERVES[1], POOL_B_RESERVES[0], TRADE_FEE_RATE);
+    let (origination_fee, host_fee) = calculate_flash_loan_fees(loan_amount, FLASH_LOAN_FEE_WAD, 0).unwrap();
+    let profit = final_amount as i128 - (loan_amount + origination_fee + host_fee) as i128;
+    ([intermediate_amount, final_amount], profit)
+}
+
+/// The most profitable loan size, found by ternary search over every size and then a
+/// scan around it for the plateaus the rounding leaves
+fn reference_optimum() -> (u64, i128) {
+    let (mut low, mut high) = (1u64, 1_000_000_000_000);
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        if reference(low + third).1 < reference(high - third).1 {
+            low += third;
+        } else {
+            high -= third;
+        }
+    }
+    (low.saturating_sub(1_000)..=high + 1_000)
+        .map(|loan_amount| (loan_amount, reference(loan_amount).1))
+        .max_by_key(|(_, profit)| *profit)
+        .unwrap()
+}
+
+async fn find_optimal_loan(
+    arbitrage: &Arbitrage,
+    context: &mut ProgramTestContext,
+    max_evaluations: u8,
+) -> Result<OptimalLoan, TransactionError> {
+    let instruction = arbitrage.find_optimal_loan(LendingProvider::Solend, max_evaluations);
+    let mut data = arbitrage.simulate(context, &[instruction]).await?;
+    // Return data comes back without its trailing zero bytes
+    data.resize(std::mem::size_of::<OptimalLoan>(), 0);
+    Ok(OptimalLoan::deserialize(&mut &data[..]).unwrap())
+}
+
+/// The search's answer is what the pools and the lender would give at its loan size
+fn assert_matches_reference(optimal_loan: &OptimalLoan) {
+    let (leg_outputs, profit) = reference(optimal_loan.loan_amount);
+    assert_eq!(optimal_loan.leg_outputs, leg_outputs);
+    assert_eq!(optimal_loan.expected_profit as i128, profit.max(0));
+}
+
+#[tokio::test]
+async fn search_converges_on_the_most_profitable_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let (optimal_amount, optimal_profit) = reference_optimum();
+
+    let mut last_profit = 0;
+    for max_evaluations in [8, 16, 32, 64] {
+        let optimal_loan = find_optimal_loan(&arbitrage, &mut context, max_evaluations).await.unwrap();
+        assert_matches_reference(&optimal_loan);
+        // More evaluations only narrow the interval further
+        assert!(optimal_loan.expected_profit >= last_profit, "{max_evaluations} evaluations");
+        last_profit = optimal_loan.expected_profit;
+    }
+
+    // Within a token of the best profit, at a loan within 0.1% of the best size
+    let optimal_loan = find_optimal_loan(&arbitrage, &mut context, 64).await.unwrap();
+    assert!(optimal_profit - optimal_loan.expected_profit as i128 <= 1, "{optimal_loan:?} vs {optimal_profit}");
+    assert!(optimal_loan.loan_amount.abs_diff(optimal_amount) < optimal_amount / 1_000);
+}
+
+#[tokio::test]
+async fn search_stays_below_the_maximum_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let max_loan_amount = reference_optimum().0 / 4;
+    set_loan_limits(&mut context, 1, max_loan_amount).await;
+
+    // Profit still rises at the limit, so the search ends within a hundred-millionth of
+    // the range under it
+    let optimal_loan = find_optimal_loan(&arbitrage, &mut context, 64).await.unwrap();
+    assert_matches_reference(&optimal_loan);
+    assert!(optimal_loan.loan_amount <= max_loan_amount);
+    assert!((max_loan_amount - optimal_loan.loan_amount) * 100_000_000 <= max_loan_amount, "{optimal_loan:?}");
+}
+
+#[tokio::test]
+async fn search_stays_above_the_minimum_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let min_loan_amount = reference_optimum().0 * 3 / 2;
+    set_loan_limits(&mut context, min_loan_amount, min_loan_amount * 4).await;
+
+    // Profit falls from the limit, so the search ends within a hundred-millionth of the
+    // range over it
+    let optimal_loan = find_optimal_loan(&arbitrage, &mut context, 64).await.unwrap();
+    assert_matches_reference(&optimal_loan);
+    assert!(optimal_loan.loan_amount >= min_loan_amount);
+    assert!((optimal_loan.loan_amount - min_loan_amount) * 100_000_000 <= min_loan_amount * 3, "{optimal_loan:?}");
+}
+
+#[tokio::test]
+async fn sizes_that_cannot_be_quoted_are_skipped() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    // Solend's minimum fee of a whole token leaves nothing of a one-token loan, so its
+    // fee cannot be quoted
+    assert!(calculate_flash_loan_fees(1, FLASH_LOAN_FEE_WAD, 0).is_err());
+    assert!(calculate_flash_loan_fees(2, FLASH_LOAN_FEE_WAD, 0).is_ok());
+
+    // Both sizes are candidates and only the one with a fee is kept
+    set_loan_limits(&mut context, 1, 2).await;
+    let optimal_loan = find_optimal_loan(&arbitrage, &mut context, 8).await.unwrap();
+    assert_eq!(optimal_loan.loan_amount, 2);
+    assert_matches_reference(&optimal_loan);
+
+    // With no size left to quote there is no loan to take
+    set_loan_limits(&mut context, 1, 1).await;
+    let result = find_optimal_loan(&arbitrage, &mut context, 8).await;
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(FlashLoanArbitrageError::InsufficientProfit.into())
+        )
+    );
+}
+
+#[tokio::test]
+async fn search_budget_is_bounded() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    for max_evaluations in [1, 65] {
+        let result = find_optimal_loan(&arbitrage, &mut context, max_evaluations).await;
+        assert_eq!(
+            result.unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(FlashLoanArbitrageError::InvalidSearchBudget.into())
+            )
+        );
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{assert_error, program_test, token_balance, Arbitrage, TokenProgram, RESERVE_SUPPLY};
+use flash_loan_arbitrage_program::errors::FlashLoanArbitrageError;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const LOAN_AMOUNT: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn arbitrage_between_two_pools_keeps_the_profit() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    arbitrage.send(&mut context, &instructions).await.unwrap();
+
+    // The reserve gets its loan back with the 0.3% fee, and the rest is profit
+    assert_eq!(token_balance(&mut context, arbitrage.reserve.liquidity_supply).await, RESERVE_SUPPLY);
+    assert_eq!(token_balance(&mut context, arbitrage.reserve.fee_receiver).await, 3_000_000);
+    assert!(token_balance(&mut context, arbitrage.loan_token_account).await > 0);
+    assert_eq!(token_balance(&mut context, arbitrage.intermediate_token_account).await, 0);
+}
+
+#[tokio::test]
+async fn mis_chained_token_accounts_are_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    // Token accounts of the authority that the route does not run through
+    let stray_loan_account = Pubkey::new_unique();
+    let stray_intermediate_account = Pubkey::new_unique();
+    let owner = arbitrage.authority.pubkey();
+    TokenProgram::Token.add_token_account(&mut program_test, stray_loan_account, &arbitrage.loan_mint, &owner, 0);
+    TokenProgram::Token.add_token_account(
+        &mut program_test,
+        stray_intermediate_account,
+        &arbitrage.intermediate_mint,
+        &owner,
+        0,
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let mis_chains: [fn(&mut _, Pubkey, Pubkey); 3] = [
+        |accounts: &mut flash_loan_arbitrage_program::accounts::FlashLoanAndArbitrage, loan, _| {
+            accounts.dex_a_input_token_account = loan
+        },
+        |accounts, _, intermediate| accounts.dex_b_input_token_account = intermediate,
+        |accounts, loan, _| accounts.dex_b_output_token_account = loan,
+    ];
+    for mis_chain in mis_chains {
+        let mut accounts = arbitrage.accounts();
+        mis_chain(&mut accounts, stray_loan_account, stray_intermediate_account);
+        let instructions = [
+            arbitrage.borrow(LOAN_AMOUNT),
+            arbitrage.instruction(accounts, arbitrage.args(LOAN_AMOUNT, 0)),
+            arbitrage.repay(LOAN_AMOUNT, 0),
+        ];
+        let result = arbitrage.send(&mut context, &instructions).await;
+        assert_error(result, 1, FlashLoanArbitrageError::RouteNotClosed);
+    }
+}
+
+#[tokio::test]
+async fn loan_mint_with_transfer_fee_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::with_loan_mint(&mut program_test, TokenProgram::Token2022, Some(50));
+    let mut context = program_test.start_with_context().await;
+
+    // The repay would reach the lender net of the fee, so the trade never starts
+    let instructions = [arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0))];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 0, FlashLoanArbitrageError::LoanMintTransferFeeNotSupported);
+}
+
+#[tokio::test]
+async fn token_2022_loan_mint_without_transfer_fee_trades() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::with_loan_mint(&mut program_test, TokenProgram::Token2022, None);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    arbitrage.send(&mut context, &instructions).await.unwrap();
+    assert!(token_balance(&mut context, arbitrage.loan_token_account).await > 0);
+}
@@ -0,0 +1,221 @@
+//! A MarginFi loan is a borrow and repay wrapped in start and end flashloan
+//! instructions, which the arbitrage finds through the instructions sysvar
+//!
+//! The ignored tests run the loan through MarginFi built from source by
+//! `scripts/build_marginfi.sh`, against a mainnet bank `scripts/dump_test_programs.sh`
+//! dumps, in place of the stand-in.
+
+mod common;
+
+use common::{
+    assert_error, dumped_accounts, program_test, program_test_with_marginfi_program, token_balance, Arbitrage,
+    TokenProgram, RESERVE_SUPPLY,
+};
+use flash_loan_arbitrage_program::{
+    errors::FlashLoanArbitrageError,
+    lending::{marginfi as marginfi_lending, LendingProvider},
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const LOAN_AMOUNT: u64 = 1_000_000_000;
+
+// MarginFi `Bank` layout
+const BANK_MINT_OFFSET: usize = 8;
+const BANK_GROUP_OFFSET: usize = 41;
+const BANK_LIQUIDITY_VAULT_OFFSET: usize = 112;
+
+/// The start flashloan, borrow, arbitrage, repay and end flashloan of a MarginFi loan
+fn flash_loan(arbitrage: &Arbitrage) -> Vec<Instruction> {
+    let mut args = arbitrage.args(LOAN_AMOUNT, 0);
+    args.provider = LendingProvider::MarginFi;
+    let ix = arbitrage.instruction(arbitrage.accounts_for(LendingProvider::MarginFi), args);
+    arbitrage.marginfi_flash_loan(LOAN_AMOUNT, ix)
+}
+
+fn start_flashloan(arbitrage: &Arbitrage, end_index: u64) -> Instruction {
+    marginfi_lending::start_flashloan(
+        common::marginfi::ID,
+        arbitrage.bank.marginfi_account,
+        arbitrage.authority.pubkey(),
+        end_index,
+    )
+}
+
+#[tokio::test]
+async fn arbitrage_inside_flashloan_keeps_the_profit() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    arbitrage.send(&mut context, &flash_loan(&arbitrage)).await.unwrap();
+
+    // MarginFi charges no fee, so the bank gets back exactly what it lent
+    assert_eq!(token_balance(&mut context, arbitrage.bank.liquidity_vault).await, RESERVE_SUPPLY);
+    assert!(token_balance(&mut context, arbitrage.loan_token_account).await > 0);
+}
+
+#[tokio::test]
+async fn missing_end_flashloan_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let mut instructions = flash_loan(&arbitrage);
+    instructions.pop();
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 2, FlashLoanArbitrageError::OutsideMarginFiFlashloan);
+}
+
+#[tokio::test]
+async fn end_index_must_point_at_end_flashloan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // The start points at the repay, before the end flashloan
+    let mut instructions = flash_loan(&arbitrage);
+    instructions[0] = start_flashloan(&arbitrage, 3);
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 2, FlashLoanArbitrageError::OutsideMarginFiFlashloan);
+}
+
+#[tokio::test]
+async fn missing_start_flashloan_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let mut instructions = flash_loan(&arbitrage);
+    instructions.remove(0);
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::OutsideMarginFiFlashloan);
+}
+
+#[tokio::test]
+async fn borrow_before_start_flashloan_is_not_the_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // borrow, start flashloan, arbitrage, repay, end flashloan
+    let mut instructions = flash_loan(&arbitrage);
+    instructions.swap(0, 1);
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 2, FlashLoanArbitrageError::MissingFlashBorrowInstruction);
+}
+
+#[tokio::test]
+async fn repay_after_end_flashloan_is_not_the_repay() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // start flashloan, borrow, arbitrage, end flashloan, repay
+    let mut instructions = flash_loan(&arbitrage);
+    instructions.swap(3, 4);
+    instructions[0] = start_flashloan(&arbitrage, 3);
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 2, FlashLoanArbitrageError::MissingFlashRepayInstruction);
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap()
+}
+
+/// Sets the arbitrage up on the mint of the bank dumped to `tests/fixtures/marginfi`,
+/// and opens a MarginFi account for its authority through the built program
+async fn marginfi_program_arbitrage() -> (Arbitrage, ProgramTestContext) {
+    let accounts = dumped_accounts("marginfi");
+    let bank_discriminator = &hash(b"account:Bank").to_bytes()[..8];
+    let (bank, bank_data) = accounts
+        .iter()
+        .find(|(_, account)| account.owner == common::marginfi::ID && account.data.starts_with(bank_discriminator))
+        .map(|(address, account)| (*address, account.data.clone()))
+        .expect("no MarginFi bank among the fixtures");
+
+    let mut program_test = program_test_with_marginfi_program();
+    for (address, account) in accounts {
+        program_test.add_account(address, account);
+    }
+    let loan_mint = read_pubkey(&bank_data, BANK_MINT_OFFSET);
+    let mut arbitrage = Arbitrage::on_loan_mint(&mut program_test, TokenProgram::Token, loan_mint);
+    let marginfi_account = Keypair::new();
+    arbitrage.bank = common::marginfi::Bank {
+        group: read_pubkey(&bank_data, BANK_GROUP_OFFSET),
+        marginfi_account: marginfi_account.pubkey(),
+        bank,
+        liquidity_vault: read_pubkey(&bank_data, BANK_LIQUIDITY_VAULT_OFFSET),
+    };
+    let mut context = program_test.start_with_context().await;
+
+    let authority = arbitrage.authority.pubkey();
+    let initialize = Instruction {
+        program_id: common::marginfi::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(arbitrage.bank.group, false),
+            AccountMeta::new(marginfi_account.pubkey(), true),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: hash(b"global:marginfi_account_initialize").to_bytes()[..8].to_vec(),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[initialize],
+        Some(&authority),
+        &[&arbitrage.authority, &marginfi_account],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    (arbitrage, context)
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/marginfi.so from scripts/build_marginfi.sh and the bank from scripts/dump_test_programs.sh"]
+async fn marginfi_program_lends_around_the_arbitrage() {
+    let (arbitrage, mut context) = marginfi_program_arbitrage().await;
+    let liquidity = token_balance(&mut context, arbitrage.bank.liquidity_vault).await;
+
+    arbitrage.send(&mut context, &flash_loan(&arbitrage)).await.unwrap();
+
+    // MarginFi charges no fee; repaying the whole liability can round it up a token
+    let repaid = token_balance(&mut context, arbitrage.bank.liquidity_vault).await - liquidity;
+    assert!(repaid <= 1, "the bank gained {repaid}");
+    assert!(token_balance(&mut context, arbitrage.loan_token_account).await > 0);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/marginfi.so from scripts/build_marginfi.sh and the bank from scripts/dump_test_programs.sh"]
+async fn marginfi_program_fails_a_flashloan_left_in_debt() {
+    let (arbitrage, mut context) = marginfi_program_arbitrage().await;
+    let authority = arbitrage.authority.pubkey();
+
+    // Without the repay, the end flashloan's health check finds the liability
+    let instructions = [
+        start_flashloan(&arbitrage, 2),
+        marginfi_lending::borrow(
+            common::marginfi::ID,
+            arbitrage.bank.group,
+            arbitrage.bank.marginfi_account,
+            authority,
+            arbitrage.bank.bank,
+            arbitrage.loan_token_account,
+            arbitrage.bank.liquidity_vault,
+            anchor_spl::token::ID,
+            LOAN_AMOUNT,
+        ),
+        marginfi_lending::end_flashloan(common::marginfi::ID, arbitrage.bank.marginfi_account, authority, &[]),
+    ];
+    match arbitrage.send(&mut context, &instructions).await.unwrap_err().unwrap() {
+        TransactionError::InstructionError(2, InstructionError::Custom(_)) => {}
+        other => panic!("expected MarginFi to fail the end flashloan, got {other:?}"),
+    }
+}
@@ -0,0 +1,457 @@
+//! The 256-bit helpers and constant-product pricing in `dex::math`, and the Raydium
+//! CPMM quote built on them, against arbitrary-precision integers
+
+use anchor_spl::token::spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use flash_loan_arbitrage_program::{
+    dex::{
+        math::{constant_product_output, constant_product_spot_output, mul_div_ceil, mul_div_floor, U256},
+        raydium::{CPMM_AMM_CONFIG_DISCRIMINATOR, CPMM_FEE_RATE_DENOMINATOR, CPMM_POOL_STATE_DISCRIMINATOR},
+        DexKind, Leg, SwapAccounts, SwapLegParams,
+    },
+    errors::FlashLoanArbitrageError,
+};
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use solana_sdk::{account_info::AccountInfo, bpf_loader, program_pack::Pack, pubkey, pubkey::Pubkey};
+
+const MAX: u128 = u128::MAX;
+
+fn big(value: u128) -> BigUint {
+    BigUint::from(value)
+}
+
+fn to_u128(value: &BigUint) -> Option<u128> {
+    u128::try_from(value).ok()
+}
+
+fn big_u256(value: U256) -> BigUint {
+    (big(value.hi) << 128u32) + big(value.lo)
+}
+
+/// `a * b / denominator` rounded down, `None` on a zero denominator or a result past `u128`
+fn reference_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    to_u128(&(big(a) * big(b) / big(denominator)))
+}
+
+fn reference_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    to_u128(&big_ceil(big(a), big(b), big(denominator)))
+}
+
+/// `ceil(a * b / denominator)` over arbitrary-precision integers
+fn big_ceil(a: BigUint, b: BigUint, denominator: BigUint) -> BigUint {
+    (a * b + &denominator - 1u32) / denominator
+}
+
+/// The input left once a fee of `fee_numerator / fee_denominator`, rounded up, is taken
+fn reference_after_fee(amount_in: u64, fee_numerator: u64, fee_denominator: u64) -> Option<BigUint> {
+    if fee_denominator == 0 {
+        return None;
+    }
+    let amount_in = big(amount_in as u128);
+    let fee = big_ceil(amount_in.clone(), big(fee_numerator as u128), big(fee_denominator as u128));
+    (fee <= amount_in).then(|| amount_in - fee)
+}
+
+/// The most a swap can pay out while the reserves' product `x * y` does not shrink
+fn reference_output(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<u64> {
+    let amount_in_after_fee = reference_after_fee(amount_in, fee_numerator, fee_denominator)?;
+    if amount_in_after_fee == BigUint::ZERO || reserve_out == 0 {
+        return Some(0);
+    }
+    let (x, y) = (big(reserve_in), big(reserve_out));
+    let x_after = &x + amount_in_after_fee;
+    to_u128(&x_after)?;
+    // The least `y` may fall to is `x * y / (x + amount_in_after_fee)`, rounded up
+    let y_after = big_ceil(x, y.clone(), x_after);
+    u64::try_from(y - y_after).ok()
+}
+
+/// The output at the reserves' price before the swap, rounded down
+fn reference_spot_output(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Option<u64> {
+    let amount_in_after_fee = reference_after_fee(amount_in, fee_numerator, fee_denominator)?;
+    if reserve_in == 0 {
+        return None;
+    }
+    let amount_out = big(reserve_out) * amount_in_after_fee / big(reserve_in);
+    u64::try_from(amount_out).ok()
+}
+
+/// Values of every magnitude, weighted towards the bounds where overflow happens
+fn operand() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        any::<u128>(),
+        any::<u64>().prop_map(u128::from),
+        (0u32..128).prop_map(|bits| 1u128 << bits),
+        (0u128..1024).prop_map(|offset| MAX - offset),
+        (0u32..128, -2i8..=2).prop_map(|(bits, offset)| (1u128 << bits).wrapping_add_signed(offset as i128)),
+    ]
+}
+
+const CPMM_PROGRAM_ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+
+// CPMM `PoolState` and `AmmConfig` layouts
+const CPMM_POOL_LEN: usize = 637;
+const CPMM_POOL_AMM_CONFIG_OFFSET: usize = 8;
+const CPMM_POOL_TOKEN_0_VAULT_OFFSET: usize = 72;
+const CPMM_POOL_TOKEN_1_VAULT_OFFSET: usize = 104;
+const CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET: usize = 341;
+const CPMM_POOL_PROTOCOL_FEES_TOKEN_1_OFFSET: usize = 349;
+const CPMM_POOL_FUND_FEES_TOKEN_0_OFFSET: usize = 357;
+const CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET: usize = 365;
+const CPMM_AMM_CONFIG_LEN: usize = 236;
+const CPMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 12;
+
+/// The balance of a CPMM vault and the protocol and fund fees it holds
+#[derive(Clone, Copy, Debug)]
+struct Vault {
+    balance: u64,
+    protocol_fees: u64,
+    fund_fees: u64,
+}
+
+impl Vault {
+    /// The liquidity the pool prices with, which excludes the fees
+    fn reserve(&self) -> u128 {
+        let fees = big(self.protocol_fees as u128) + big(self.fund_fees as u128);
+        let balance = big(self.balance as u128);
+        if balance > fees {
+            to_u128(&(balance - fees)).unwrap()
+        } else {
+            0
+        }
+    }
+}
+
+/// Which of the pool's mints the leg's input token account holds
+#[derive(Clone, Copy, Debug)]
+enum Input {
+    Token0,
+    Token1,
+    Neither,
+}
+
+/// An account for an `AccountInfo` to borrow
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self { key, owner, lamports: 0, data }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let mut data = vec![0u8; TokenAccount::LEN];
+        let account = TokenAccount {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        account.pack_into_slice(&mut data);
+        Self::new(Pubkey::new_unique(), anchor_spl::token::ID, data)
+    }
+
+    fn mint(key: Pubkey) -> Self {
+        let mut data = vec![0u8; Mint::LEN];
+        let mint = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_into_slice(&mut data);
+        Self::new(key, anchor_spl::token::ID, data)
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+/// The accounts of a leg on a CPMM pool of two SPL Token mints, in `SwapAccounts` order
+/// with the six CPMM accounts last
+fn cpmm_leg_accounts(vaults: [Vault; 2], trade_fee_rate: u64, input: Input) -> Vec<TestAccount> {
+    let (token_0_mint, token_1_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (input_mint, output_mint) = match input {
+        Input::Token0 => (token_0_mint, token_1_mint),
+        Input::Token1 => (token_1_mint, token_0_mint),
+        Input::Neither => (Pubkey::new_unique(), token_1_mint),
+    };
+    let (amm_config, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let token_0_vault = TestAccount::token_account(token_0_mint, Pubkey::new_unique(), vaults[0].balance);
+    let token_1_vault = TestAccount::token_account(token_1_mint, Pubkey::new_unique(), vaults[1].balance);
+
+    let mut pool = vec![0u8; CPMM_POOL_LEN];
+    pool[..8].copy_from_slice(&CPMM_POOL_STATE_DISCRIMINATOR);
+    for (offset, key) in [
+        (CPMM_POOL_AMM_CONFIG_OFFSET, amm_config),
+        (CPMM_POOL_TOKEN_0_VAULT_OFFSET, token_0_vault.key),
+        (CPMM_POOL_TOKEN_1_VAULT_OFFSET, token_1_vault.key),
+    ] {
+        pool[offset..offset + 32].copy_from_slice(key.as_ref());
+    }
+    for (offset, fees) in [
+        (CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET, vaults[0].protocol_fees),
+        (CPMM_POOL_PROTOCOL_FEES_TOKEN_1_OFFSET, vaults[1].protocol_fees),
+        (CPMM_POOL_FUND_FEES_TOKEN_0_OFFSET, vaults[0].fund_fees),
+        (CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET, vaults[1].fund_fees),
+    ] {
+        pool[offset..offset + 8].copy_from_slice(&fees.to_le_bytes());
+    }
+    let mut config = vec![0u8; CPMM_AMM_CONFIG_LEN];
+    config[..8].copy_from_slice(&CPMM_AMM_CONFIG_DISCRIMINATOR);
+    config[CPMM_CONFIG_TRADE_FEE_RATE_OFFSET..CPMM_CONFIG_TRADE_FEE_RATE_OFFSET + 8]
+        .copy_from_slice(&trade_fee_rate.to_le_bytes());
+
+    vec![
+        TestAccount::new(CPMM_PROGRAM_ID, bpf_loader::ID, vec![]),
+        TestAccount::new(Pubkey::new_unique(), CPMM_PROGRAM_ID, pool),
+        TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::token_account(input_mint, user, 0),
+        TestAccount::token_account(output_mint, user, 0),
+        token_0_vault,
+        token_1_vault,
+        TestAccount::new(anchor_spl::token::ID, bpf_loader::ID, vec![]),
+        TestAccount::new(amm_config, CPMM_PROGRAM_ID, config),
+        TestAccount::new(Pubkey::new_unique(), CPMM_PROGRAM_ID, vec![0u8; 8]),
+        TestAccount::mint(token_0_mint),
+        TestAccount::mint(token_1_mint),
+        TestAccount::new(anchor_spl::token::ID, bpf_loader::ID, vec![]),
+        TestAccount::new(anchor_spl::token::ID, bpf_loader::ID, vec![]),
+    ]
+}
+
+/// Runs `f` on the leg `Leg::new` describes from `accounts`
+fn with_cpmm_leg<T>(accounts: &mut [TestAccount], f: impl FnOnce(anchor_lang::Result<Leg>) -> T) -> T {
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    let params = SwapLegParams {
+        dex: DexKind::RaydiumCpmm,
+        remaining_accounts_len: 6,
+        sqrt_price_limit: 0,
+    };
+    let leg = Leg::new(
+        &params,
+        SwapAccounts {
+            dex_program: &infos[0],
+            pool: &infos[1],
+            pool_authority: &infos[2],
+            user_authority: &infos[3],
+            input_token_account: &infos[4],
+            output_token_account: &infos[5],
+            pool_token_a_account: &infos[6],
+            pool_token_b_account: &infos[7],
+            token_program: &infos[8],
+            remaining_accounts: &infos[9..],
+        },
+    );
+    f(leg)
+}
+
+/// Trade fee rates in hundredths of a basis point, up to the whole input
+fn trade_fee_rate() -> impl Strategy<Value = u64> {
+    prop_oneof![0u64..=10_000, 0..=CPMM_FEE_RATE_DENOMINATOR]
+}
+
+/// Fee fractions of the input, mostly at most the whole input
+fn fee() -> impl Strategy<Value = (u64, u64)> {
+    prop_oneof![
+        3 => (1u64..=CPMM_FEE_RATE_DENOMINATOR).prop_flat_map(|denominator| (0..=denominator, Just(denominator))),
+        1 => (any::<u64>(), any::<u64>()),
+    ]
+}
+
+fn vault() -> impl Strategy<Value = Vault> {
+    (1u64.., any::<u32>(), any::<u32>()).prop_map(|(balance, protocol_fees, fund_fees)| Vault {
+        balance,
+        protocol_fees: protocol_fees as u64,
+        fund_fees: fund_fees as u64,
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(4096))]
+
+    #[test]
+    fn mul_div_floor_matches_reference(a in operand(), b in operand(), denominator in operand()) {
+        prop_assert_eq!(mul_div_floor(a, b, denominator), reference_floor(a, b, denominator));
+    }
+
+    #[test]
+    fn mul_div_ceil_matches_reference(a in operand(), b in operand(), denominator in operand()) {
+        prop_assert_eq!(mul_div_ceil(a, b, denominator), reference_ceil(a, b, denominator));
+    }
+
+    /// Where the product fits in a `u128`, plain `u128` division agrees
+    #[test]
+    fn mul_div_floor_matches_u128_division(a in any::<u64>(), b in any::<u64>(), denominator in 1u128..) {
+        let product = a as u128 * b as u128;
+        prop_assert_eq!(mul_div_floor(a as u128, b as u128, denominator), Some(product / denominator));
+        prop_assert_eq!(mul_div_ceil(a as u128, b as u128, denominator), Some(product.div_ceil(denominator)));
+    }
+
+    #[test]
+    fn u256_product_and_division_match_reference(a in operand(), b in operand(), divisor_hi in operand(), divisor_lo in operand()) {
+        let product = U256::mul(a, b);
+        prop_assert_eq!(big_u256(product), big(a) * big(b));
+
+        let divisor = U256 { hi: divisor_hi >> 64, lo: divisor_lo };
+        match product.div_rem(divisor) {
+            None => prop_assert!(divisor.is_zero()),
+            Some((quotient, remainder)) => {
+                prop_assert_eq!(big_u256(quotient), big_u256(product) / big_u256(divisor));
+                prop_assert_eq!(big_u256(remainder), big_u256(product) % big_u256(divisor));
+            }
+        }
+    }
+
+    #[test]
+    fn u256_shift_left_checks_for_lost_bits(hi in operand(), lo in operand(), bits in 0u32..256) {
+        let value = U256 { hi, lo };
+        let shifted = big_u256(value) << bits;
+        let expected = (shifted.bits() <= 256).then_some(shifted);
+        prop_assert_eq!(value.checked_shl(bits).map(big_u256), expected);
+    }
+
+    /// The fee rounds up, the output rounds down, and `x * y` never shrinks
+    #[test]
+    fn constant_product_output_matches_reference(
+        amount_in in any::<u64>(),
+        reserve_in in operand(),
+        reserve_out in operand(),
+        (fee_numerator, fee_denominator) in fee(),
+    ) {
+        let amount_out = constant_product_output(amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator);
+        let spot_amount_out = constant_product_spot_output(amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator);
+        prop_assert_eq!(amount_out, reference_output(amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator));
+        prop_assert_eq!(spot_amount_out, reference_spot_output(amount_in, reserve_in, reserve_out, fee_numerator, fee_denominator));
+        // Price impact only ever takes from the output
+        if let (Some(amount_out), Some(spot_amount_out)) = (amount_out, spot_amount_out) {
+            prop_assert!(amount_out <= spot_amount_out);
+        }
+    }
+
+    /// A CPMM leg swaps in the direction of its input mint and prices with the vault
+    /// balances less the protocol and fund fees
+    #[test]
+    fn cpmm_quote_excludes_fee_vaults(
+        vaults in [vault(), vault()],
+        trade_fee_rate in trade_fee_rate(),
+        a_to_b in any::<bool>(),
+        amount_in in any::<u64>(),
+    ) {
+        let input = if a_to_b { Input::Token0 } else { Input::Token1 };
+        let mut accounts = cpmm_leg_accounts(vaults, trade_fee_rate, input);
+        let (leg_a_to_b, quote, spot_quote) = with_cpmm_leg(&mut accounts, |leg| {
+            let leg = leg.unwrap();
+            (leg.a_to_b, leg.quote(amount_in).ok(), leg.spot_quote(amount_in).ok())
+        });
+        prop_assert_eq!(leg_a_to_b, a_to_b);
+
+        let (reserve_in, reserve_out) = if a_to_b {
+            (vaults[0].reserve(), vaults[1].reserve())
+        } else {
+            (vaults[1].reserve(), vaults[0].reserve())
+        };
+        let fee_denominator = CPMM_FEE_RATE_DENOMINATOR;
+        prop_assert_eq!(quote, reference_output(amount_in, reserve_in, reserve_out, trade_fee_rate, fee_denominator));
+        prop_assert_eq!(spot_quote, reference_spot_output(amount_in, reserve_in, reserve_out, trade_fee_rate, fee_denominator));
+    }
+}
+
+#[test]
+fn constant_product_rounding() {
+    // A fee of any fraction of a unit takes the whole unit
+    assert_eq!(constant_product_output(1, 1_000_000, 1_000_000, 1, 1_000_000), Some(0));
+    assert_eq!(constant_product_spot_output(1, 1_000_000, 1_000_000, 1, 1_000_000), Some(0));
+    // A third of 1_000_000 rounds up to 333_334, leaving 666_666 at a 1:1 price
+    assert_eq!(constant_product_spot_output(1_000_000, 1 << 40, 1 << 40, 1, 3), Some(666_666));
+    // 100 * 10 / 110 and 100 * 10 / 100, rounded down
+    assert_eq!(constant_product_output(10, 100, 100, 0, 1), Some(9));
+    assert_eq!(constant_product_spot_output(10, 100, 100, 0, 1), Some(10));
+    // A fee past the whole input, a zero denominator and an empty input side have no output
+    assert_eq!(constant_product_output(10, 100, 100, 2, 1), None);
+    assert_eq!(constant_product_output(10, 100, 100, 0, 0), None);
+    assert_eq!(constant_product_spot_output(10, 0, 100, 0, 1), None);
+}
+
+#[test]
+fn leg_direction_follows_the_input_mint() {
+    let vault = Vault {
+        balance: 1_000_000,
+        protocol_fees: 0,
+        fund_fees: 0,
+    };
+    for (input, a_to_b) in [(Input::Token0, true), (Input::Token1, false)] {
+        let mut accounts = cpmm_leg_accounts([vault; 2], 2_500, input);
+        assert_eq!(with_cpmm_leg(&mut accounts, |leg| leg.unwrap().a_to_b), a_to_b);
+    }
+
+    let mut accounts = cpmm_leg_accounts([vault; 2], 2_500, Input::Neither);
+    let error = with_cpmm_leg(&mut accounts, |leg| leg.err());
+    assert_eq!(error, Some(FlashLoanArbitrageError::TokenAccountMintMismatch.into()));
+}
+
+#[test]
+fn cpmm_fees_held_in_the_vaults_move_the_quote() {
+    let vault = |protocol_fees, fund_fees| Vault {
+        balance: 1_000_000_000,
+        protocol_fees,
+        fund_fees,
+    };
+    let quote = |vaults| {
+        let mut accounts = cpmm_leg_accounts(vaults, 2_500, Input::Token0);
+        with_cpmm_leg(&mut accounts, |leg| leg.unwrap().quote(1_000_000).unwrap())
+    };
+    let without_fees = quote([vault(0, 0); 2]);
+    // Fees held on the output side leave less to pay out, on the input side they make
+    // the input count for more
+    assert!(quote([vault(0, 0), vault(40_000_000, 60_000_000)]) < without_fees);
+    assert!(quote([vault(40_000_000, 60_000_000), vault(0, 0)]) > without_fees);
+    assert_eq!(
+        quote([vault(40_000_000, 60_000_000), vault(0, 0)]),
+        constant_product_output(1_000_000, 900_000_000, 1_000_000_000, 2_500, CPMM_FEE_RATE_DENOMINATOR).unwrap()
+    );
+}
+
+#[test]
+fn mul_div_overflow_edges() {
+    // The largest product over itself
+    assert_eq!(mul_div_floor(MAX, MAX, MAX), Some(MAX));
+    assert_eq!(mul_div_ceil(MAX, MAX, MAX), Some(MAX));
+    // One less in the denominator takes the quotient past u128
+    assert_eq!(mul_div_floor(MAX, MAX, MAX - 1), None);
+    // 2^128 fits the product's high half but not the result
+    assert_eq!(mul_div_floor(1 << 64, 1 << 64, 1), None);
+    assert_eq!(mul_div_floor(1 << 64, 1 << 64, 2), Some(1 << 127));
+    // The quotient rounds down to u128::MAX, so only rounding up overflows
+    let (a, b, denominator) = (MAX - 1, (1 << 127) + 1, 1 << 127);
+    assert_eq!(mul_div_floor(a, b, denominator), Some(MAX));
+    assert_eq!(mul_div_ceil(a, b, denominator), None);
+    assert_eq!(reference_ceil(a, b, denominator), None);
+    // A zero denominator is never a result
+    assert_eq!(mul_div_floor(1, 1, 0), None);
+    assert_eq!(mul_div_ceil(0, 0, 0), None);
+}
@@ -0,0 +1,761 @@
+//! Raydium AMM v4 and CPMM pools written in-test to Raydium's layouts: decoding an
+//! `AmmInfo` at its real offsets, quoting from the vaults less what the pools hold back,
+//! and the accounts and data of the swaps the adapters build.
+//!
+//! The ignored tests send those swaps to the programs dumped by
+//! `scripts/dump_test_programs.sh`: CPMM swaps on a pool written here, and AMM v4 swaps
+//! on the mainnet SOL/USDC pool and market accounts the script dumps next to the programs.
+//! Both check that the user's balances move exactly as quoted.
+
+use std::{fs, path::Path};
+
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flash_loan_arbitrage_program::{
+    dex::{
+        math::constant_product_output,
+        raydium::{
+            AmmV4State, RaydiumAmmV4, RaydiumCpmm, AMM_V4_SWAP_BASE_IN, CPMM_AMM_CONFIG_DISCRIMINATOR,
+            CPMM_POOL_STATE_DISCRIMINATOR, CPMM_SWAP_BASE_INPUT_DISCRIMINATOR,
+        },
+        DexAdapter, DexKind, Leg, SwapAccounts, SwapLegParams,
+    },
+    errors::FlashLoanArbitrageError,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const AMM_V4_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+const OPENBOOK_PROGRAM_ID: Pubkey = pubkey!("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX");
+const CPMM_PROGRAM_ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+/// The SOL/USDC AMM v4 pool whose accounts the dump script saves
+const AMM_V4_SOL_USDC: Pubkey = pubkey!("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2");
+
+// AMM v4 `AmmInfo` layout
+const AMM_INFO_LEN: usize = 752;
+const AMM_NONCE_OFFSET: usize = 8;
+const AMM_SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const AMM_SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const AMM_NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const AMM_NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const AMM_COIN_VAULT_OFFSET: usize = 336;
+const AMM_PC_VAULT_OFFSET: usize = 368;
+const AMM_COIN_MINT_OFFSET: usize = 400;
+const AMM_PC_MINT_OFFSET: usize = 432;
+const AMM_OPEN_ORDERS_OFFSET: usize = 496;
+const AMM_MARKET_OFFSET: usize = 528;
+const AMM_MARKET_PROGRAM_OFFSET: usize = 560;
+const AMM_TARGET_ORDERS_OFFSET: usize = 592;
+
+// OpenBook `MarketState` layout, after its 5-byte "serum" head padding
+const MARKET_VAULT_SIGNER_NONCE_OFFSET: usize = 45;
+const MARKET_COIN_VAULT_OFFSET: usize = 117;
+const MARKET_PC_VAULT_OFFSET: usize = 165;
+const MARKET_EVENT_QUEUE_OFFSET: usize = 253;
+const MARKET_BIDS_OFFSET: usize = 285;
+const MARKET_ASKS_OFFSET: usize = 317;
+
+// CPMM `PoolState`, `AmmConfig` and `ObservationState` layouts
+const CPMM_POOL_LEN: usize = 637;
+const CPMM_POOL_AMM_CONFIG_OFFSET: usize = 8;
+const CPMM_POOL_TOKEN_0_VAULT_OFFSET: usize = 72;
+const CPMM_POOL_TOKEN_1_VAULT_OFFSET: usize = 104;
+const CPMM_POOL_LP_MINT_OFFSET: usize = 136;
+const CPMM_POOL_TOKEN_0_MINT_OFFSET: usize = 168;
+const CPMM_POOL_TOKEN_1_MINT_OFFSET: usize = 200;
+const CPMM_POOL_TOKEN_0_PROGRAM_OFFSET: usize = 232;
+const CPMM_POOL_TOKEN_1_PROGRAM_OFFSET: usize = 264;
+const CPMM_POOL_OBSERVATION_KEY_OFFSET: usize = 296;
+const CPMM_POOL_AUTH_BUMP_OFFSET: usize = 328;
+const CPMM_POOL_DECIMALS_OFFSET: usize = 330;
+const CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET: usize = 341;
+const CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET: usize = 365;
+const CPMM_AMM_CONFIG_LEN: usize = 236;
+const CPMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 12;
+const CPMM_CONFIG_PROTOCOL_FEE_RATE_OFFSET: usize = 20;
+const CPMM_CONFIG_FUND_FEE_RATE_OFFSET: usize = 28;
+const CPMM_OBSERVATION_STATE_LEN: usize = 4075;
+const CPMM_OBSERVATION_POOL_ID_OFFSET: usize = 11;
+
+const SWAP_FEE_NUMERATOR: u64 = 25;
+const SWAP_FEE_DENOMINATOR: u64 = 10_000;
+const CPMM_TRADE_FEE_RATE: u64 = 2_500;
+/// Coin and pc (token 0 and token 1) vault balances
+const RESERVES: [u64; 2] = [1_000_000_000_000, 150_000_000_000_000];
+/// PnL the AMM v4 pool has yet to take from its coin and pc vaults
+const NEED_TAKE_PNL: [u64; 2] = [4_000_000_000, 900_000_000_000];
+/// Protocol fees in the CPMM token 0 vault and fund fees in its token 1 vault
+const CPMM_FEES: [u64; 2] = [3_000_000_000, 700_000_000_000];
+const BALANCE: u64 = 1_000_000_000_000_000;
+const AMOUNT_IN: u64 = 5_000_000_000;
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap()
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()).max(LAMPORTS_PER_SOL),
+        data,
+        owner,
+        ..Account::default()
+    }
+}
+
+fn mint_account(token_program: Pubkey) -> Account {
+    let mut data = vec![0u8; Mint::LEN];
+    Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: u64::MAX / 2,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    account(token_program, data)
+}
+
+/// A token account holding `amount`, with the lamports behind it for wrapped SOL
+fn token_account(token_program: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let rent = Rent::default().minimum_balance(TokenAccount::LEN);
+    let is_native = mint == spl_token::native_mint::ID;
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        is_native: if is_native { COption::Some(rent) } else { COption::None },
+        ..TokenAccount::default()
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: if is_native { rent + amount } else { rent },
+        data,
+        owner: token_program,
+        ..Account::default()
+    }
+}
+
+fn token_amount(account: &Account) -> u64 {
+    TokenAccount::unpack_from_slice(&account.data).unwrap().amount
+}
+
+/// An account for an `AccountInfo` to borrow
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+/// Looks each key up among `accounts`; programs missing from them are owned by the loader
+fn test_accounts(accounts: &[(Pubkey, Account)], keys: Vec<(Pubkey, bool)>) -> Vec<TestAccount> {
+    keys.into_iter()
+        .map(|(key, executable)| {
+            let account = accounts.iter().find(|(address, _)| *address == key);
+            let (owner, data) = match account {
+                Some((_, account)) => (account.owner, account.data.clone()),
+                None if executable => (bpf_loader_upgradeable::ID, vec![]),
+                // PDAs that hold no data, like the pool authorities
+                None => (Pubkey::default(), vec![]),
+            };
+            TestAccount { key, owner, lamports: 0, data }
+        })
+        .collect()
+}
+
+fn with_leg<T>(dex: DexKind, accounts: &mut [TestAccount], f: impl FnOnce(Leg) -> T) -> T {
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    let params = SwapLegParams {
+        dex,
+        remaining_accounts_len: (infos.len() - 9) as u8,
+        sqrt_price_limit: 0,
+    };
+    let leg = Leg::new(
+        &params,
+        SwapAccounts {
+            dex_program: &infos[0],
+            pool: &infos[1],
+            pool_authority: &infos[2],
+            user_authority: &infos[3],
+            input_token_account: &infos[4],
+            output_token_account: &infos[5],
+            pool_token_a_account: &infos[6],
+            pool_token_b_account: &infos[7],
+            token_program: &infos[8],
+            remaining_accounts: &infos[9..],
+        },
+    )
+    .unwrap();
+    f(leg)
+}
+
+/// The (input, output) pair of `pair`, ordered by the swap direction
+fn by_direction<T: Copy>(pair: [T; 2], a_to_b: bool) -> (T, T) {
+    if a_to_b {
+        (pair[0], pair[1])
+    } else {
+        (pair[1], pair[0])
+    }
+}
+
+/// An AMM v4 pool with its OpenBook market, and a user holding both of its tokens
+struct AmmV4Pool {
+    user: Keypair,
+    amm: Pubkey,
+    amm_authority: Pubkey,
+    /// The coin and pc vaults
+    vaults: [Pubkey; 2],
+    user_token_accounts: [Pubkey; 2],
+    /// `[amm_open_orders, amm_target_orders, serum_program, serum_market, serum_bids,
+    /// serum_asks, serum_event_queue, serum_coin_vault, serum_pc_vault, serum_vault_signer]`
+    market_accounts: [Pubkey; 10],
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl AmmV4Pool {
+    /// A pool whose `AmmInfo` is all 0xff apart from the fields the adapter reads, so
+    /// reading any other offset shows up in the quote
+    fn new() -> Self {
+        let user = Keypair::new();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let user_token_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (amm_authority, _) = Pubkey::find_program_address(&[b"amm authority"], &AMM_V4_PROGRAM_ID);
+        let amm = Pubkey::new_unique();
+        let mut market_accounts = [(); 10].map(|_| Pubkey::new_unique());
+        market_accounts[2] = OPENBOOK_PROGRAM_ID;
+
+        let mut data = vec![0xff; AMM_INFO_LEN];
+        write(&mut data, AMM_SWAP_FEE_NUMERATOR_OFFSET, &SWAP_FEE_NUMERATOR.to_le_bytes());
+        write(&mut data, AMM_SWAP_FEE_DENOMINATOR_OFFSET, &SWAP_FEE_DENOMINATOR.to_le_bytes());
+        write(&mut data, AMM_NEED_TAKE_PNL_COIN_OFFSET, &NEED_TAKE_PNL[0].to_le_bytes());
+        write(&mut data, AMM_NEED_TAKE_PNL_PC_OFFSET, &NEED_TAKE_PNL[1].to_le_bytes());
+        write(&mut data, AMM_COIN_VAULT_OFFSET, vaults[0].as_ref());
+        write(&mut data, AMM_PC_VAULT_OFFSET, vaults[1].as_ref());
+
+        let token = anchor_spl::token::ID;
+        let accounts = vec![
+            (amm, account(AMM_V4_PROGRAM_ID, data)),
+            (mints[0], mint_account(token)),
+            (mints[1], mint_account(token)),
+            (vaults[0], token_account(token, mints[0], amm_authority, RESERVES[0])),
+            (vaults[1], token_account(token, mints[1], amm_authority, RESERVES[1])),
+            (user_token_accounts[0], token_account(token, mints[0], user.pubkey(), BALANCE)),
+            (user_token_accounts[1], token_account(token, mints[1], user.pubkey(), BALANCE)),
+        ];
+
+        Self {
+            user,
+            amm,
+            amm_authority,
+            vaults,
+            user_token_accounts,
+            market_accounts,
+            accounts,
+        }
+    }
+
+    /// The SOL/USDC pool and market dumped into `tests/fixtures/raydium_amm_v4`, and a user
+    /// holding both of its tokens
+    fn from_fixtures() -> Self {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/raydium_amm_v4");
+        let entries = fs::read_dir(&dir)
+            .unwrap_or_else(|_| panic!("{} is missing, run scripts/dump_test_programs.sh", dir.display()));
+        let mut accounts: Vec<(Pubkey, Account)> = entries
+            .map(|entry| dumped_account(&fs::read_to_string(entry.unwrap().path()).unwrap()))
+            .collect();
+        let find = |key: Pubkey| &accounts.iter().find(|(address, _)| *address == key).unwrap().1.data;
+
+        let amm = AMM_V4_SOL_USDC;
+        let amm_info = find(amm);
+        let nonce = read_u64(amm_info, AMM_NONCE_OFFSET) as u8;
+        let amm_authority = Pubkey::create_program_address(&[b"amm authority", &[nonce]], &AMM_V4_PROGRAM_ID).unwrap();
+        let vaults = [AMM_COIN_VAULT_OFFSET, AMM_PC_VAULT_OFFSET].map(|offset| read_pubkey(amm_info, offset));
+        let mints = [AMM_COIN_MINT_OFFSET, AMM_PC_MINT_OFFSET].map(|offset| read_pubkey(amm_info, offset));
+        let market = read_pubkey(amm_info, AMM_MARKET_OFFSET);
+        let market_program = read_pubkey(amm_info, AMM_MARKET_PROGRAM_OFFSET);
+        let open_orders = read_pubkey(amm_info, AMM_OPEN_ORDERS_OFFSET);
+        let target_orders = read_pubkey(amm_info, AMM_TARGET_ORDERS_OFFSET);
+
+        let market_state = find(market);
+        let vault_signer_nonce = read_u64(market_state, MARKET_VAULT_SIGNER_NONCE_OFFSET);
+        let vault_signer =
+            Pubkey::create_program_address(&[market.as_ref(), &vault_signer_nonce.to_le_bytes()], &market_program)
+                .unwrap();
+        let market_accounts = [
+            open_orders,
+            target_orders,
+            market_program,
+            market,
+            read_pubkey(market_state, MARKET_BIDS_OFFSET),
+            read_pubkey(market_state, MARKET_ASKS_OFFSET),
+            read_pubkey(market_state, MARKET_EVENT_QUEUE_OFFSET),
+            read_pubkey(market_state, MARKET_COIN_VAULT_OFFSET),
+            read_pubkey(market_state, MARKET_PC_VAULT_OFFSET),
+            vault_signer,
+        ];
+
+        let user = Keypair::new();
+        let user_token_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for (address, mint) in user_token_accounts.into_iter().zip(mints) {
+            accounts.push((address, token_account(anchor_spl::token::ID, mint, user.pubkey(), BALANCE)));
+        }
+
+        Self {
+            user,
+            amm,
+            amm_authority,
+            vaults,
+            user_token_accounts,
+            market_accounts,
+            accounts,
+        }
+    }
+
+    /// The leg's accounts in `SwapAccounts` order, followed by its 9 or 10 remaining
+    /// accounts
+    fn leg_accounts(&self, with_target_orders: bool, a_to_b: bool) -> Vec<TestAccount> {
+        let (input, output) = by_direction(self.user_token_accounts, a_to_b);
+        let mut keys = vec![
+            (AMM_V4_PROGRAM_ID, true),
+            (self.amm, false),
+            (self.amm_authority, false),
+            (self.user.pubkey(), false),
+            (input, false),
+            (output, false),
+            (self.vaults[0], false),
+            (self.vaults[1], false),
+            (anchor_spl::token::ID, true),
+        ];
+        for (index, key) in self.market_accounts.into_iter().enumerate() {
+            if index != 1 || with_target_orders {
+                keys.push((key, index == 2));
+            }
+        }
+        test_accounts(&self.accounts, keys)
+    }
+
+    /// The vault balances the real pool's quote starts from
+    fn reserves(&self) -> [u64; 2] {
+        self.vaults.map(|vault| {
+            let (_, account) = self.accounts.iter().find(|(address, _)| *address == vault).unwrap();
+            token_amount(account)
+        })
+    }
+}
+
+/// An account in the `solana account --output json` format
+fn dumped_account(json: &str) -> (Pubkey, Account) {
+    let json: serde_json::Value = serde_json::from_str(json).unwrap();
+    let address = json["pubkey"].as_str().unwrap().parse().unwrap();
+    let account = &json["account"];
+    assert_eq!(account["data"][1], "base64");
+    (
+        address,
+        Account {
+            lamports: account["lamports"].as_u64().unwrap(),
+            data: STANDARD.decode(account["data"][0].as_str().unwrap()).unwrap(),
+            owner: account["owner"].as_str().unwrap().parse().unwrap(),
+            executable: account["executable"].as_bool().unwrap(),
+            rent_epoch: 0,
+        },
+    )
+}
+
+#[test]
+fn amm_v4_state_reads_raydium_offsets() {
+    let pool = AmmV4Pool::new();
+    let mut accounts = pool.leg_accounts(false, true);
+    let state = AmmV4State::load(&accounts[1].info()).unwrap();
+    assert_eq!(state.swap_fee_numerator, SWAP_FEE_NUMERATOR);
+    assert_eq!(state.swap_fee_denominator, SWAP_FEE_DENOMINATOR);
+    assert_eq!(state.need_take_pnl_coin, NEED_TAKE_PNL[0]);
+    assert_eq!(state.need_take_pnl_pc, NEED_TAKE_PNL[1]);
+    assert_eq!(state.coin_vault, pool.vaults[0]);
+    assert_eq!(state.pc_vault, pool.vaults[1]);
+    assert_eq!(RaydiumAmmV4.pool_vaults(&accounts[1].info()).unwrap(), (pool.vaults[0], pool.vaults[1]));
+}
+
+#[test]
+fn amm_v4_quotes_exclude_the_pnl_to_take() {
+    let pool = AmmV4Pool::new();
+    let reserves = [0, 1].map(|side| (RESERVES[side] - NEED_TAKE_PNL[side]) as u128);
+    for a_to_b in [true, false] {
+        let (reserve_in, reserve_out) = by_direction(reserves, a_to_b);
+        let expected =
+            constant_product_output(AMOUNT_IN, reserve_in, reserve_out, SWAP_FEE_NUMERATOR, SWAP_FEE_DENOMINATOR)
+                .unwrap();
+        let (vault_in, vault_out) = by_direction(RESERVES.map(u128::from), a_to_b);
+        let on_whole_vaults =
+            constant_product_output(AMOUNT_IN, vault_in, vault_out, SWAP_FEE_NUMERATOR, SWAP_FEE_DENOMINATOR)
+                .unwrap();
+        assert_ne!(expected, on_whole_vaults);
+
+        for with_target_orders in [false, true] {
+            let mut accounts = pool.leg_accounts(with_target_orders, a_to_b);
+            let quote = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| leg.quote(AMOUNT_IN).unwrap());
+            assert_eq!(quote, expected);
+        }
+    }
+}
+
+#[test]
+fn amm_v4_swap_base_in_takes_17_or_18_accounts() {
+    let pool = AmmV4Pool::new();
+    let [open_orders, target_orders, serum_program, market, bids, asks, event_queue, coin_vault, pc_vault, vault_signer] =
+        pool.market_accounts;
+
+    for a_to_b in [true, false] {
+        let (input, output) = by_direction(pool.user_token_accounts, a_to_b);
+        let mut expected = vec![
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new(pool.amm, false),
+            AccountMeta::new_readonly(pool.amm_authority, false),
+            AccountMeta::new(open_orders, false),
+            AccountMeta::new(pool.vaults[0], false),
+            AccountMeta::new(pool.vaults[1], false),
+            AccountMeta::new_readonly(serum_program, false),
+            AccountMeta::new(market, false),
+            AccountMeta::new(bids, false),
+            AccountMeta::new(asks, false),
+            AccountMeta::new(event_queue, false),
+            AccountMeta::new(coin_vault, false),
+            AccountMeta::new(pc_vault, false),
+            AccountMeta::new_readonly(vault_signer, false),
+            AccountMeta::new(input, false),
+            AccountMeta::new(output, false),
+            AccountMeta::new_readonly(pool.user.pubkey(), true),
+        ];
+        assert_eq!(expected.len(), 17);
+
+        for with_target_orders in [false, true] {
+            if with_target_orders {
+                expected.insert(4, AccountMeta::new(target_orders, false));
+                assert_eq!(expected.len(), 18);
+            }
+            let mut accounts = pool.leg_accounts(with_target_orders, a_to_b);
+            let ix = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| {
+                RaydiumAmmV4.build_swap_ix(&leg, AMOUNT_IN, 42).unwrap()
+            });
+            assert_eq!(ix.program_id, AMM_V4_PROGRAM_ID);
+            assert_eq!(ix.accounts, expected);
+            let mut data = vec![AMM_V4_SWAP_BASE_IN];
+            data.extend_from_slice(&AMOUNT_IN.to_le_bytes());
+            data.extend_from_slice(&42u64.to_le_bytes());
+            assert_eq!(ix.data, data);
+        }
+    }
+}
+
+#[test]
+fn amm_v4_rejects_other_layouts_and_vaults() {
+    let pool = AmmV4Pool::new();
+
+    // One market account short of the 17-account layout, or one past the 18-account one
+    let mut accounts = pool.leg_accounts(false, true);
+    accounts.pop();
+    let error = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| leg.quote(AMOUNT_IN).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+    let mut accounts = pool.leg_accounts(true, true);
+    accounts.push(TestAccount { key: Pubkey::new_unique(), owner: Pubkey::default(), lamports: 0, data: vec![] });
+    let error = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| leg.quote(AMOUNT_IN).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+
+    // Vaults of another pool holding the same mints
+    let mut accounts = pool.leg_accounts(false, true);
+    accounts[7].key = Pubkey::new_unique();
+    let error = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| leg.quote(AMOUNT_IN).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidPoolAccount.into());
+
+    // An `AmmInfo` owned by another program
+    let mut accounts = pool.leg_accounts(false, true);
+    accounts[1].owner = Pubkey::new_unique();
+    let error = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| leg.quote(AMOUNT_IN).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::DexPoolOwnerMismatch.into());
+}
+
+/// A CPMM pool with its config, observation account and vaults, and a user holding both
+/// of its tokens. Token 1 is a Token-2022 mint, so the swap's token programs follow its
+/// direction.
+struct CpmmPool {
+    user: Keypair,
+    pool: Pubkey,
+    authority: Pubkey,
+    amm_config: Pubkey,
+    observation_state: Pubkey,
+    mints: [Pubkey; 2],
+    token_programs: [Pubkey; 2],
+    vaults: [Pubkey; 2],
+    user_token_accounts: [Pubkey; 2],
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl CpmmPool {
+    fn new() -> Self {
+        let user = Keypair::new();
+        let (authority, auth_bump) =
+            Pubkey::find_program_address(&[b"vault_and_lp_mint_auth_seed"], &CPMM_PROGRAM_ID);
+        let [pool, amm_config, observation_state] = [(); 3].map(|_| Pubkey::new_unique());
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let token_programs = [anchor_spl::token::ID, anchor_spl::token_2022::ID];
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let user_token_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let mut data = vec![0u8; CPMM_POOL_LEN];
+        write(&mut data, 0, &CPMM_POOL_STATE_DISCRIMINATOR);
+        for (offset, key) in [
+            (CPMM_POOL_AMM_CONFIG_OFFSET, amm_config),
+            (CPMM_POOL_TOKEN_0_VAULT_OFFSET, vaults[0]),
+            (CPMM_POOL_TOKEN_1_VAULT_OFFSET, vaults[1]),
+            (CPMM_POOL_LP_MINT_OFFSET, Pubkey::new_unique()),
+            (CPMM_POOL_TOKEN_0_MINT_OFFSET, mints[0]),
+            (CPMM_POOL_TOKEN_1_MINT_OFFSET, mints[1]),
+            (CPMM_POOL_TOKEN_0_PROGRAM_OFFSET, token_programs[0]),
+            (CPMM_POOL_TOKEN_1_PROGRAM_OFFSET, token_programs[1]),
+            (CPMM_POOL_OBSERVATION_KEY_OFFSET, observation_state),
+        ] {
+            write(&mut data, offset, key.as_ref());
+        }
+        data[CPMM_POOL_AUTH_BUMP_OFFSET] = auth_bump;
+        // LP, token 0 and token 1 decimals
+        write(&mut data, CPMM_POOL_DECIMALS_OFFSET, &[9, 6, 6]);
+        write(&mut data, CPMM_POOL_PROTOCOL_FEES_TOKEN_0_OFFSET, &CPMM_FEES[0].to_le_bytes());
+        write(&mut data, CPMM_POOL_FUND_FEES_TOKEN_1_OFFSET, &CPMM_FEES[1].to_le_bytes());
+
+        let mut config = vec![0u8; CPMM_AMM_CONFIG_LEN];
+        write(&mut config, 0, &CPMM_AMM_CONFIG_DISCRIMINATOR);
+        write(&mut config, CPMM_CONFIG_TRADE_FEE_RATE_OFFSET, &CPMM_TRADE_FEE_RATE.to_le_bytes());
+        write(&mut config, CPMM_CONFIG_PROTOCOL_FEE_RATE_OFFSET, &120_000u64.to_le_bytes());
+        write(&mut config, CPMM_CONFIG_FUND_FEE_RATE_OFFSET, &40_000u64.to_le_bytes());
+
+        let mut observation = vec![0u8; CPMM_OBSERVATION_STATE_LEN];
+        write(&mut observation, 0, &hash(b"account:ObservationState").to_bytes()[..8]);
+        write(&mut observation, CPMM_OBSERVATION_POOL_ID_OFFSET, pool.as_ref());
+
+        let mut accounts = vec![
+            (pool, account(CPMM_PROGRAM_ID, data)),
+            (amm_config, account(CPMM_PROGRAM_ID, config)),
+            (observation_state, account(CPMM_PROGRAM_ID, observation)),
+        ];
+        for side in 0..2 {
+            let program = token_programs[side];
+            accounts.extend([
+                (mints[side], mint_account(program)),
+                (vaults[side], token_account(program, mints[side], authority, RESERVES[side])),
+                (user_token_accounts[side], token_account(program, mints[side], user.pubkey(), BALANCE)),
+            ]);
+        }
+
+        Self {
+            user,
+            pool,
+            authority,
+            amm_config,
+            observation_state,
+            mints,
+            token_programs,
+            vaults,
+            user_token_accounts,
+            accounts,
+        }
+    }
+
+    /// The leg's accounts in `SwapAccounts` order, followed by the six CPMM accounts
+    fn leg_accounts(&self, a_to_b: bool) -> Vec<TestAccount> {
+        let (input, output) = by_direction(self.user_token_accounts, a_to_b);
+        let keys = vec![
+            (CPMM_PROGRAM_ID, true),
+            (self.pool, false),
+            (self.authority, false),
+            (self.user.pubkey(), false),
+            (input, false),
+            (output, false),
+            (self.vaults[0], false),
+            (self.vaults[1], false),
+            (anchor_spl::token::ID, true),
+            (self.amm_config, false),
+            (self.observation_state, false),
+            (self.mints[0], false),
+            (self.mints[1], false),
+            (self.token_programs[0], true),
+            (self.token_programs[1], true),
+        ];
+        test_accounts(&self.accounts, keys)
+    }
+}
+
+#[test]
+fn cpmm_swap_base_input_follows_the_swap_direction() {
+    let pool = CpmmPool::new();
+    for a_to_b in [true, false] {
+        let (input, output) = by_direction(pool.user_token_accounts, a_to_b);
+        let (input_vault, output_vault) = by_direction(pool.vaults, a_to_b);
+        let (input_program, output_program) = by_direction(pool.token_programs, a_to_b);
+        let (input_mint, output_mint) = by_direction(pool.mints, a_to_b);
+        let expected = vec![
+            AccountMeta::new_readonly(pool.user.pubkey(), true),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new_readonly(pool.amm_config, false),
+            AccountMeta::new(pool.pool, false),
+            AccountMeta::new(input, false),
+            AccountMeta::new(output, false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new_readonly(input_program, false),
+            AccountMeta::new_readonly(output_program, false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(pool.observation_state, false),
+        ];
+
+        let mut accounts = pool.leg_accounts(a_to_b);
+        let ix = with_leg(DexKind::RaydiumCpmm, &mut accounts, |leg| {
+            RaydiumCpmm.build_swap_ix(&leg, AMOUNT_IN, 42).unwrap()
+        });
+        assert_eq!(ix.program_id, CPMM_PROGRAM_ID);
+        assert_eq!(ix.accounts, expected);
+        let mut data = CPMM_SWAP_BASE_INPUT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&AMOUNT_IN.to_le_bytes());
+        data.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(ix.data, data);
+    }
+}
+
+#[test]
+fn cpmm_rejects_other_layouts_and_configs() {
+    let pool = CpmmPool::new();
+
+    let mut accounts = pool.leg_accounts(true);
+    accounts.pop();
+    let error = with_leg(DexKind::RaydiumCpmm, &mut accounts, |leg| RaydiumCpmm.validate_accounts(&leg).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+
+    // Another config, whose fee the pool does not charge
+    let mut accounts = pool.leg_accounts(true);
+    accounts[9].key = Pubkey::new_unique();
+    let error = with_leg(DexKind::RaydiumCpmm, &mut accounts, |leg| RaydiumCpmm.validate_accounts(&leg).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidPoolAccount.into());
+}
+
+/// Sends `ix` signed by `user` and returns the balances of `token_accounts` after it
+async fn send_swap(
+    mut program_test: ProgramTest,
+    accounts: &[(Pubkey, Account)],
+    user: &Keypair,
+    ix: Instruction,
+    token_accounts: [Pubkey; 2],
+) -> [u64; 2] {
+    for (address, account) in accounts {
+        program_test.add_account(*address, account.clone());
+    }
+    let mut context = program_test.start_with_context().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, user],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut balances = [0; 2];
+    for (balance, address) in balances.iter_mut().zip(token_accounts) {
+        *balance = token_amount(&context.banks_client.get_account(address).await.unwrap().unwrap());
+    }
+    balances
+}
+
+/// Runs a CPMM leg's swap through the dumped CPMM program and checks the user's
+/// balances move exactly as quoted
+async fn cpmm_swap_moves_the_quoted_amounts(a_to_b: bool) {
+    let pool = CpmmPool::new();
+    let mut accounts = pool.leg_accounts(a_to_b);
+    let (quote, ix) = with_leg(DexKind::RaydiumCpmm, &mut accounts, |leg| {
+        (leg.quote(AMOUNT_IN).unwrap(), RaydiumCpmm.build_swap_ix(&leg, AMOUNT_IN, 0).unwrap())
+    });
+
+    let program_test = ProgramTest::new("raydium_cpmm", CPMM_PROGRAM_ID, None);
+    let balances = send_swap(program_test, &pool.accounts, &pool.user, ix, pool.user_token_accounts).await;
+    let (input, output) = by_direction(balances, a_to_b);
+    assert_eq!(BALANCE - input, AMOUNT_IN);
+    assert_eq!(output - BALANCE, quote);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/raydium_cpmm.so from scripts/dump_test_programs.sh"]
+async fn cpmm_swap_0_to_1_moves_the_quoted_amounts() {
+    cpmm_swap_moves_the_quoted_amounts(true).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/raydium_cpmm.so from scripts/dump_test_programs.sh"]
+async fn cpmm_swap_1_to_0_moves_the_quoted_amounts() {
+    cpmm_swap_moves_the_quoted_amounts(false).await;
+}
+
+/// Runs an AMM v4 leg's swap on the dumped SOL/USDC pool through the dumped AMM v4 and
+/// OpenBook programs, and checks the user's balances move exactly as quoted
+async fn amm_v4_swap_moves_the_quoted_amounts(with_target_orders: bool, a_to_b: bool) {
+    let pool = AmmV4Pool::from_fixtures();
+    // A thousandth of the input side's vault
+    let amount_in = by_direction(pool.reserves(), a_to_b).0 / 1_000;
+    let mut accounts = pool.leg_accounts(with_target_orders, a_to_b);
+    let (quote, ix) = with_leg(DexKind::RaydiumAmmV4, &mut accounts, |leg| {
+        (leg.quote(amount_in).unwrap(), RaydiumAmmV4.build_swap_ix(&leg, amount_in, 0).unwrap())
+    });
+    assert_eq!(ix.accounts.len(), if with_target_orders { 18 } else { 17 });
+
+    let mut program_test = ProgramTest::new("raydium_amm_v4", AMM_V4_PROGRAM_ID, None);
+    program_test.add_program("openbook", OPENBOOK_PROGRAM_ID, None);
+    let balances = send_swap(program_test, &pool.accounts, &pool.user, ix, pool.user_token_accounts).await;
+    let (input, output) = by_direction(balances, a_to_b);
+    assert_eq!(BALANCE - input, amount_in);
+    assert_eq!(output - BALANCE, quote);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/raydium_amm_v4.so and the pool from scripts/dump_test_programs.sh"]
+async fn amm_v4_swap_coin_to_pc_moves_the_quoted_amounts() {
+    amm_v4_swap_moves_the_quoted_amounts(false, true).await;
+    amm_v4_swap_moves_the_quoted_amounts(true, true).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/raydium_amm_v4.so and the pool from scripts/dump_test_programs.sh"]
+async fn amm_v4_swap_pc_to_coin_moves_the_quoted_amounts() {
+    amm_v4_swap_moves_the_quoted_amounts(false, false).await;
+    amm_v4_swap_moves_the_quoted_amounts(true, false).await;
+}
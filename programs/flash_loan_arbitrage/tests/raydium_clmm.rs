@@ -0,0 +1,421 @@
+//! Raydium CLMM pool, config and tick array accounts written in-test to Raydium's
+//! layouts: decoding them, quoting a leg across the tick arrays it is passed, and the
+//! `swap_v2` instruction the adapter builds
+
+use anchor_spl::token::spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use flash_loan_arbitrage_program::{
+    dex::{
+        clmm::{
+            quote_exact_input, raydium_sqrt_price_from_tick_index, ClmmProgram, SwapQuote, TickArrayView,
+            MIN_SQRT_PRICE_X64, RAYDIUM_MAX_SQRT_PRICE_X64,
+        },
+        raydium_clmm::{
+            RaydiumClmm, RaydiumClmmState, AMM_CONFIG_DISCRIMINATOR, POOL_STATE_DISCRIMINATOR,
+            SWAP_V2_DISCRIMINATOR, TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR, TICK_ARRAY_DISCRIMINATOR,
+            TICK_ARRAY_SIZE,
+        },
+        DexAdapter, DexKind, Leg, SwapAccounts, SwapLegParams,
+    },
+    errors::FlashLoanArbitrageError,
+};
+use solana_sdk::{
+    account_info::AccountInfo, bpf_loader, instruction::AccountMeta, program_pack::Pack, pubkey, pubkey::Pubkey,
+};
+
+const RAYDIUM_CLMM_PROGRAM_ID: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// `PoolState`, `AmmConfig`, `TickArrayState` and `TickArrayBitmapExtension` layouts
+const POOL_LEN: usize = 1544;
+const POOL_AMM_CONFIG_OFFSET: usize = 9;
+const POOL_TOKEN_MINT_0_OFFSET: usize = 73;
+const POOL_TOKEN_MINT_1_OFFSET: usize = 105;
+const POOL_TOKEN_VAULT_0_OFFSET: usize = 137;
+const POOL_TOKEN_VAULT_1_OFFSET: usize = 169;
+const POOL_OBSERVATION_KEY_OFFSET: usize = 201;
+const POOL_TICK_SPACING_OFFSET: usize = 235;
+const POOL_LIQUIDITY_OFFSET: usize = 237;
+const POOL_SQRT_PRICE_OFFSET: usize = 253;
+const POOL_TICK_CURRENT_OFFSET: usize = 269;
+const POOL_STATUS_OFFSET: usize = 389;
+const AMM_CONFIG_LEN: usize = 117;
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 47;
+const TICK_ARRAY_LEN: usize = 10240;
+const TICK_ARRAY_POOL_OFFSET: usize = 8;
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 40;
+const TICK_ARRAY_TICKS_OFFSET: usize = 44;
+const TICK_LEN: usize = 168;
+const TICK_LIQUIDITY_NET_OFFSET: usize = 4;
+const TICK_LIQUIDITY_GROSS_OFFSET: usize = 20;
+const BITMAP_EXTENSION_LEN: usize = 1832;
+
+const TICK_SPACING: u16 = 10;
+const TICK_CURRENT: i32 = 5;
+const LIQUIDITY: u128 = 1_000_000_000_000;
+const TRADE_FEE_RATE: u32 = 2_500;
+const TICKS_IN_ARRAY: i32 = TICK_ARRAY_SIZE as i32 * TICK_SPACING as i32;
+
+/// A tick array as the reference quote reads it
+struct TickArray {
+    start_tick_index: i32,
+    ticks: Vec<Option<i128>>,
+}
+
+impl TickArrayView for TickArray {
+    fn start_tick_index(&self) -> i32 {
+        self.start_tick_index
+    }
+
+    fn liquidity_net(&self, offset: usize) -> Option<i128> {
+        self.ticks.get(offset).copied().flatten()
+    }
+}
+
+/// One tick written to a tick array account
+struct Tick {
+    offset: usize,
+    liquidity_net: i128,
+    liquidity_gross: u128,
+}
+
+/// An account for an `AccountInfo` to borrow
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self { key, owner, lamports: 0, data }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey) -> Self {
+        let mut data = vec![0u8; TokenAccount::LEN];
+        let account = TokenAccount {
+            mint,
+            owner,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        account.pack_into_slice(&mut data);
+        Self::new(Pubkey::new_unique(), anchor_spl::token::ID, data)
+    }
+
+    fn mint(key: Pubkey) -> Self {
+        let mut data = vec![0u8; Mint::LEN];
+        let mint = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_into_slice(&mut data);
+        Self::new(key, anchor_spl::token::ID, data)
+    }
+
+    fn program(key: Pubkey) -> Self {
+        Self::new(key, bpf_loader::ID, vec![])
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn tick_array_data(pool: Pubkey, start_tick_index: i32, ticks: &[Tick]) -> Vec<u8> {
+    let mut data = vec![0u8; TICK_ARRAY_LEN];
+    write(&mut data, 0, &TICK_ARRAY_DISCRIMINATOR);
+    write(&mut data, TICK_ARRAY_POOL_OFFSET, pool.as_ref());
+    write(&mut data, TICK_ARRAY_START_TICK_INDEX_OFFSET, &start_tick_index.to_le_bytes());
+    for tick in ticks {
+        let at = TICK_ARRAY_TICKS_OFFSET + tick.offset * TICK_LEN;
+        let tick_index = start_tick_index + tick.offset as i32 * TICK_SPACING as i32;
+        write(&mut data, at, &tick_index.to_le_bytes());
+        write(&mut data, at + TICK_LIQUIDITY_NET_OFFSET, &tick.liquidity_net.to_le_bytes());
+        write(&mut data, at + TICK_LIQUIDITY_GROSS_OFFSET, &tick.liquidity_gross.to_le_bytes());
+    }
+    data
+}
+
+/// A pool a little above tick 5 with initialized ticks at 0 and -1100, and tick arrays
+/// starting at 0 and -1200: the array starting at -600 holds no initialized ticks, so
+/// Raydium never creates it. Tick -610 has liquidity net but no liquidity gross left,
+/// so it is not initialized.
+fn tick_arrays() -> [(i32, Vec<Tick>); 2] {
+    [
+        (
+            0,
+            vec![Tick {
+                offset: 0,
+                liquidity_net: 400_000_000_000,
+                liquidity_gross: 400_000_000_000,
+            }],
+        ),
+        (
+            -2 * TICKS_IN_ARRAY,
+            vec![
+                Tick {
+                    offset: 59,
+                    liquidity_net: 300_000_000_000,
+                    liquidity_gross: 0,
+                },
+                Tick {
+                    offset: 10,
+                    liquidity_net: -100_000_000_000,
+                    liquidity_gross: 100_000_000_000,
+                },
+            ],
+        ),
+    ]
+}
+
+/// The tick arrays as the reference quote reads them
+fn reference_tick_arrays() -> Vec<TickArray> {
+    tick_arrays()
+        .into_iter()
+        .map(|(start_tick_index, ticks)| {
+            let mut liquidity_net = vec![None; TICK_ARRAY_SIZE];
+            for tick in ticks.iter().filter(|tick| tick.liquidity_gross != 0) {
+                liquidity_net[tick.offset] = Some(tick.liquidity_net);
+            }
+            TickArray {
+                start_tick_index,
+                ticks: liquidity_net,
+            }
+        })
+        .collect()
+}
+
+fn sqrt_price() -> u128 {
+    let lower = raydium_sqrt_price_from_tick_index(TICK_CURRENT).unwrap();
+    let upper = raydium_sqrt_price_from_tick_index(TICK_CURRENT + 1).unwrap();
+    lower + (upper - lower) / 3
+}
+
+/// The accounts of a leg selling token 0 or token 1, in `SwapAccounts` order with the
+/// Raydium CLMM accounts last: the bitmap extension leads the tick arrays
+fn leg_accounts(token_0_input: bool, status: u8) -> Vec<TestAccount> {
+    let (token_0_mint, token_1_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (input_mint, output_mint) = if token_0_input {
+        (token_0_mint, token_1_mint)
+    } else {
+        (token_1_mint, token_0_mint)
+    };
+    let pool_key = Pubkey::new_unique();
+    let (amm_config, observation_state, user) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let token_0_vault = TestAccount::token_account(token_0_mint, pool_key);
+    let token_1_vault = TestAccount::token_account(token_1_mint, pool_key);
+
+    let mut pool = vec![0u8; POOL_LEN];
+    write(&mut pool, 0, &POOL_STATE_DISCRIMINATOR);
+    for (offset, key) in [
+        (POOL_AMM_CONFIG_OFFSET, amm_config),
+        (POOL_TOKEN_MINT_0_OFFSET, token_0_mint),
+        (POOL_TOKEN_MINT_1_OFFSET, token_1_mint),
+        (POOL_TOKEN_VAULT_0_OFFSET, token_0_vault.key),
+        (POOL_TOKEN_VAULT_1_OFFSET, token_1_vault.key),
+        (POOL_OBSERVATION_KEY_OFFSET, observation_state),
+    ] {
+        write(&mut pool, offset, key.as_ref());
+    }
+    write(&mut pool, POOL_TICK_SPACING_OFFSET, &TICK_SPACING.to_le_bytes());
+    write(&mut pool, POOL_LIQUIDITY_OFFSET, &LIQUIDITY.to_le_bytes());
+    write(&mut pool, POOL_SQRT_PRICE_OFFSET, &sqrt_price().to_le_bytes());
+    write(&mut pool, POOL_TICK_CURRENT_OFFSET, &TICK_CURRENT.to_le_bytes());
+    pool[POOL_STATUS_OFFSET] = status;
+
+    let mut config = vec![0u8; AMM_CONFIG_LEN];
+    write(&mut config, 0, &AMM_CONFIG_DISCRIMINATOR);
+    write(&mut config, AMM_CONFIG_TRADE_FEE_RATE_OFFSET, &TRADE_FEE_RATE.to_le_bytes());
+    let mut bitmap_extension = vec![0u8; BITMAP_EXTENSION_LEN];
+    write(&mut bitmap_extension, 0, &TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR);
+
+    let mut accounts = vec![
+        TestAccount::program(RAYDIUM_CLMM_PROGRAM_ID),
+        TestAccount::new(pool_key, RAYDIUM_CLMM_PROGRAM_ID, pool),
+        TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]),
+        TestAccount::new(user, Pubkey::default(), vec![]),
+        TestAccount::token_account(input_mint, user),
+        TestAccount::token_account(output_mint, user),
+        token_0_vault,
+        token_1_vault,
+        TestAccount::program(anchor_spl::token::ID),
+        TestAccount::new(amm_config, RAYDIUM_CLMM_PROGRAM_ID, config),
+        TestAccount::new(observation_state, RAYDIUM_CLMM_PROGRAM_ID, vec![0u8; 8]),
+        TestAccount::mint(token_0_mint),
+        TestAccount::mint(token_1_mint),
+        TestAccount::program(anchor_spl::token::ID),
+        TestAccount::program(anchor_spl::token_2022::ID),
+        TestAccount::program(MEMO_PROGRAM_ID),
+        TestAccount::new(Pubkey::new_unique(), RAYDIUM_CLMM_PROGRAM_ID, bitmap_extension),
+    ];
+    for (start, ticks) in tick_arrays() {
+        accounts.push(TestAccount::new(
+            Pubkey::new_unique(),
+            RAYDIUM_CLMM_PROGRAM_ID,
+            tick_array_data(pool_key, start, &ticks),
+        ));
+    }
+    accounts
+}
+
+/// Runs `f` on the leg `Leg::new` describes from `accounts`
+fn with_leg<T>(accounts: &mut [TestAccount], sqrt_price_limit: u128, f: impl FnOnce(Leg) -> T) -> T {
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    let params = SwapLegParams {
+        dex: DexKind::RaydiumClmm,
+        remaining_accounts_len: (infos.len() - 9) as u8,
+        sqrt_price_limit,
+    };
+    let leg = Leg::new(
+        &params,
+        SwapAccounts {
+            dex_program: &infos[0],
+            pool: &infos[1],
+            pool_authority: &infos[2],
+            user_authority: &infos[3],
+            input_token_account: &infos[4],
+            output_token_account: &infos[5],
+            pool_token_a_account: &infos[6],
+            pool_token_b_account: &infos[7],
+            token_program: &infos[8],
+            remaining_accounts: &infos[9..],
+        },
+    )
+    .unwrap();
+    f(leg)
+}
+
+fn reference_quote(state: &RaydiumClmmState, amount: u64) -> SwapQuote {
+    quote_exact_input(&state.clmm_pool(TRADE_FEE_RATE), &reference_tick_arrays(), amount, true, 0).unwrap()
+}
+
+#[test]
+fn decodes_pool_state() {
+    let mut accounts = leg_accounts(true, 0);
+    let (vault_0, vault_1) = (accounts[6].key, accounts[7].key);
+    let (amm_config, observation_state) = (accounts[9].key, accounts[10].key);
+    let (mint_0, mint_1) = (accounts[11].key, accounts[12].key);
+    let pool = accounts[1].info();
+
+    let state = RaydiumClmmState::load(&pool).unwrap();
+    assert_eq!(state.amm_config, amm_config);
+    assert_eq!(state.observation_key, observation_state);
+    assert_eq!((state.token_mint_0, state.token_mint_1), (mint_0, mint_1));
+    assert_eq!((state.token_vault_0, state.token_vault_1), (vault_0, vault_1));
+    assert_eq!(state.tick_spacing, TICK_SPACING);
+    assert_eq!(state.liquidity, LIQUIDITY);
+    assert_eq!(state.sqrt_price, sqrt_price());
+    assert_eq!(state.tick_current, TICK_CURRENT);
+    assert!(!state.swap_disabled());
+
+    let clmm_pool = state.clmm_pool(TRADE_FEE_RATE);
+    assert_eq!(clmm_pool.program, ClmmProgram::RaydiumClmm);
+    assert_eq!(clmm_pool.fee_rate, TRADE_FEE_RATE);
+    assert_eq!(clmm_pool.ticks_per_array, 60);
+    assert_eq!(RaydiumClmm.pool_vaults(&pool).unwrap(), (vault_0, vault_1));
+}
+
+#[test]
+fn quote_walks_the_tick_arrays_passed() {
+    // Far enough to cross ticks 0 and -1100 and the uninitialized -610 between them
+    let amount = 36_000_000_000;
+    let mut accounts = leg_accounts(true, 0);
+    let (quote, state) = with_leg(&mut accounts, 0, |leg| {
+        let state = RaydiumClmmState::load(leg.accounts.pool).unwrap();
+        (RaydiumClmm.quote_swap(&leg, amount).unwrap(), state)
+    });
+
+    assert_eq!(quote, reference_quote(&state, amount));
+    assert_eq!(quote.amount_in, amount);
+    assert!(quote.end_sqrt_price < raydium_sqrt_price_from_tick_index(-1_100).unwrap());
+    with_leg(&mut accounts, 0, |leg| assert_eq!(leg.quote(amount).unwrap(), quote.amount_out));
+
+    // Past the last initialized tick the swap needs an array the leg does not carry
+    let error = with_leg(&mut accounts, 0, |leg| leg.quote(400_000_000_000).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+}
+
+#[test]
+fn swap_v2_accounts_follow_the_swap_direction() {
+    for token_0_input in [true, false] {
+        let mut accounts = leg_accounts(token_0_input, 0);
+        let key = |index: usize| accounts[index].key;
+        let (input_vault, output_vault) = if token_0_input { (key(6), key(7)) } else { (key(7), key(6)) };
+        let (input_mint, output_mint) = if token_0_input { (key(11), key(12)) } else { (key(12), key(11)) };
+        let expected_accounts = vec![
+            AccountMeta::new_readonly(key(3), true),
+            AccountMeta::new_readonly(key(9), false),
+            AccountMeta::new(key(1), false),
+            AccountMeta::new(key(4), false),
+            AccountMeta::new(key(5), false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new(key(10), false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token_2022::ID, false),
+            AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(key(16), false),
+            AccountMeta::new(key(17), false),
+            AccountMeta::new(key(18), false),
+        ];
+        let default_limit = if token_0_input {
+            MIN_SQRT_PRICE_X64 + 1
+        } else {
+            RAYDIUM_MAX_SQRT_PRICE_X64 - 1
+        };
+
+        for (sqrt_price_limit, expected_limit) in [(0, default_limit), (sqrt_price(), sqrt_price())] {
+            let ix = with_leg(&mut accounts, sqrt_price_limit, |leg| {
+                assert_eq!(leg.a_to_b, token_0_input);
+                RaydiumClmm.build_swap_ix(&leg, 1_000, 990).unwrap()
+            });
+            assert_eq!(ix.program_id, RAYDIUM_CLMM_PROGRAM_ID);
+            assert_eq!(ix.accounts, expected_accounts);
+
+            let mut data = SWAP_V2_DISCRIMINATOR.to_vec();
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.extend_from_slice(&990u64.to_le_bytes());
+            data.extend_from_slice(&expected_limit.to_le_bytes());
+            data.push(1);
+            assert_eq!(ix.data, data);
+        }
+    }
+}
+
+#[test]
+fn pools_that_do_not_match_are_rejected() {
+    // Swaps disabled in the pool's status
+    let mut accounts = leg_accounts(true, 1 << 4);
+    let error = with_leg(&mut accounts, 0, |leg| RaydiumClmm.validate_accounts(&leg).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidPoolAccount.into());
+    // Other status bits leave swaps enabled
+    let mut accounts = leg_accounts(true, 0b1111);
+    with_leg(&mut accounts, 0, |leg| RaydiumClmm.validate_accounts(&leg).unwrap());
+
+    // Another pool's config
+    let mut accounts = leg_accounts(true, 0);
+    accounts[9].key = Pubkey::new_unique();
+    let error = with_leg(&mut accounts, 0, |leg| RaydiumClmm.validate_accounts(&leg).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidPoolAccount.into());
+
+    // A tick array of another pool
+    let mut accounts = leg_accounts(true, 0);
+    let other_pool = Pubkey::new_unique();
+    write(&mut accounts[17].data, TICK_ARRAY_POOL_OFFSET, other_pool.as_ref());
+    let error = with_leg(&mut accounts, 0, |leg| RaydiumClmm.quote(&leg, 1_000).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+
+    // No tick arrays at all
+    let mut accounts = leg_accounts(true, 0);
+    accounts.truncate(16);
+    let error = with_leg(&mut accounts, 0, |leg| RaydiumClmm.validate_accounts(&leg).unwrap_err());
+    assert_eq!(error, FlashLoanArbitrageError::InvalidDexAccounts.into());
+}
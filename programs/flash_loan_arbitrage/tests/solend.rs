@@ -0,0 +1,199 @@
+//! Solend's borrow and repay are top-level instructions the arbitrage finds through
+//! the instructions sysvar, matched against the reserve it loads
+//!
+//! The ignored tests run the same reserve and lending market through Solend's own
+//! program, dumped by `scripts/dump_test_programs.sh`, in place of the stand-in.
+
+mod common;
+
+use common::{
+    assert_error, program_test, program_test_with_solend_program, read_u64, token_balance, Arbitrage, RESERVE_SUPPLY,
+};
+use flash_loan_arbitrage_program::errors::FlashLoanArbitrageError;
+use flash_loan_arbitrage_program::lending::solend::calculate_flash_loan_fees;
+use solana_sdk::{instruction::InstructionError, pubkey::Pubkey, transaction::TransactionError};
+
+const LOAN_AMOUNT: u64 = 1_000_000_000;
+const WAD: u64 = 1_000_000_000_000_000_000;
+// 0.3%
+const FEE_WAD: u64 = 3_000_000_000_000_000;
+
+// The liquidity Solend's `Reserve` has on hand and has lent out
+const RESERVE_LIQUIDITY_AVAILABLE_AMOUNT_OFFSET: usize = 171;
+const RESERVE_LIQUIDITY_BORROWED_AMOUNT_WADS_OFFSET: usize = 179;
+
+#[tokio::test]
+async fn borrow_of_another_amount_is_not_the_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT * 2),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::MissingFlashBorrowInstruction);
+}
+
+#[tokio::test]
+async fn borrow_after_the_arbitrage_is_not_the_loan() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // The borrow matching the loan comes after the arbitrage, which only looks back
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT * 2),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.repay(LOAN_AMOUNT, 2),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::MissingFlashBorrowInstruction);
+}
+
+#[tokio::test]
+async fn missing_repay_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::MissingFlashRepayInstruction);
+}
+
+#[tokio::test]
+async fn repay_of_another_borrow_is_rejected() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 1),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::MissingFlashRepayInstruction);
+}
+
+#[tokio::test]
+async fn fee_receiver_must_be_the_reserves() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let mut accounts = arbitrage.accounts();
+    accounts.lending_fee_account = Pubkey::new_unique();
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(accounts, arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::InvalidLendingAccounts);
+}
+
+#[tokio::test]
+async fn reserve_must_belong_to_the_lender() {
+    let mut program_test = program_test();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // An account the lender does not own in the reserve's place
+    let mut accounts = arbitrage.accounts();
+    accounts.loan_reserve_account = arbitrage.reserve.liquidity_supply;
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(accounts, arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    assert_error(result, 1, FlashLoanArbitrageError::LendingPoolOwnerMismatch);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/solend.so from scripts/dump_test_programs.sh"]
+async fn solend_program_lends_around_the_arbitrage() {
+    let mut program_test = program_test_with_solend_program();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+        arbitrage.repay(LOAN_AMOUNT, 0),
+    ];
+    arbitrage.send(&mut context, &instructions).await.unwrap();
+
+    // Solend took the fee the arbitrage quoted and its books show nothing lent out
+    let (origination_fee, host_fee) = calculate_flash_loan_fees(LOAN_AMOUNT, FEE_WAD, 0).unwrap();
+    assert_eq!(host_fee, 0);
+    assert_eq!(token_balance(&mut context, arbitrage.reserve.liquidity_supply).await, RESERVE_SUPPLY);
+    assert_eq!(token_balance(&mut context, arbitrage.reserve.fee_receiver).await, origination_fee);
+    assert!(token_balance(&mut context, arbitrage.loan_token_account).await > 0);
+    let reserve = context.banks_client.get_account(arbitrage.reserve.reserve).await.unwrap().unwrap();
+    assert_eq!(read_u64(&reserve.data, RESERVE_LIQUIDITY_AVAILABLE_AMOUNT_OFFSET), RESERVE_SUPPLY);
+    let borrowed_wads = &reserve.data[RESERVE_LIQUIDITY_BORROWED_AMOUNT_WADS_OFFSET..][..16];
+    assert_eq!(borrowed_wads, [0; 16]);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/solend.so from scripts/dump_test_programs.sh"]
+async fn solend_program_refuses_a_borrow_without_its_repay() {
+    let mut program_test = program_test_with_solend_program();
+    let arbitrage = Arbitrage::new(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+
+    // Unlike the stand-in, Solend looks ahead for the repay before lending
+    let instructions = [
+        arbitrage.borrow(LOAN_AMOUNT),
+        arbitrage.instruction(arbitrage.accounts(), arbitrage.args(LOAN_AMOUNT, 0)),
+    ];
+    let result = arbitrage.send(&mut context, &instructions).await;
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(0, InstructionError::Custom(_)) => {}
+        other => panic!("expected Solend to fail the borrow, got {other:?}"),
+    }
+}
+
+#[test]
+fn fees_on_whole_wads_are_exact() {
+    assert_eq!(calculate_flash_loan_fees(LOAN_AMOUNT, FEE_WAD, 0).unwrap(), (3_000_000, 0));
+    assert_eq!(calculate_flash_loan_fees(LOAN_AMOUNT, FEE_WAD, 20).unwrap(), (2_400_000, 600_000));
+}
+
+#[test]
+fn fees_round_up_at_fractional_wads() {
+    // 3.003 tokens, which rounding half up would have made 3
+    assert_eq!(calculate_flash_loan_fees(1_001, FEE_WAD, 0).unwrap(), (4, 0));
+    // 3000.003 tokens, of which the host's 20% is 600.0006
+    assert_eq!(calculate_flash_loan_fees(1_000_001, FEE_WAD, 0).unwrap(), (3_001, 0));
+    assert_eq!(calculate_flash_loan_fees(1_000_001, FEE_WAD, 20).unwrap(), (2_400, 601));
+}
+
+#[test]
+fn fees_at_boundary_rates() {
+    // The smallest rate still charges the minimum fee, two tokens with a host fee
+    assert_eq!(calculate_flash_loan_fees(LOAN_AMOUNT, 1, 0).unwrap(), (1, 0));
+    assert_eq!(calculate_flash_loan_fees(LOAN_AMOUNT, 1, 20).unwrap(), (1, 1));
+    assert_eq!(calculate_flash_loan_fees(100, FEE_WAD, 0).unwrap(), (1, 0));
+    // Just under 100% rounds up to the whole amount
+    assert_eq!(calculate_flash_loan_fees(10, WAD - 1, 0).unwrap(), (10, 0));
+    assert_eq!(
+        calculate_flash_loan_fees(10, WAD, 0).unwrap_err(),
+        FlashLoanArbitrageError::LoanAmountTooSmall.into()
+    );
+    // A zero rate is free and u64::MAX turns flash loans off
+    assert_eq!(calculate_flash_loan_fees(LOAN_AMOUNT, 0, 20).unwrap(), (0, 0));
+    assert_eq!(
+        calculate_flash_loan_fees(LOAN_AMOUNT, u64::MAX, 0).unwrap_err(),
+        FlashLoanArbitrageError::FlashLoanInitFailed.into()
+    );
+}
@@ -0,0 +1,104 @@
+//! Splitting a leg's input by weight, and the weights picked from constant-product pools
+
+use flash_loan_arbitrage_program::dex::math::constant_product_output;
+use flash_loan_arbitrage_program::dex::split::{optimize_split_weights, split_amount, WEIGHT_DENOMINATOR};
+use proptest::prelude::*;
+
+// 0.25%
+const FEE_NUMERATOR: u64 = 25;
+const FEE_DENOMINATOR: u64 = 10_000;
+
+/// Output of `amount_in` through the pool holding `reserves` of the input and output
+fn quote(pools: &[(u64, u64)], pool: usize, amount_in: u64) -> Option<u64> {
+    let (reserve_in, reserve_out) = pools[pool];
+    constant_product_output(amount_in, reserve_in as u128, reserve_out as u128, FEE_NUMERATOR, FEE_DENOMINATOR)
+}
+
+/// Weights that add up to `WEIGHT_DENOMINATOR`, one per pool
+fn weights() -> impl Strategy<Value = Vec<u16>> {
+    prop::collection::vec(0u32..1_000, 1..=6).prop_filter_map("all zero", |raw| {
+        let total: u32 = raw.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut weights: Vec<u16> = raw
+            .iter()
+            .map(|raw| (raw * WEIGHT_DENOMINATOR as u32 / total) as u16)
+            .collect();
+        let assigned: u16 = weights.iter().sum();
+        weights[0] += WEIGHT_DENOMINATOR as u16 - assigned;
+        Some(weights)
+    })
+}
+
+fn pools() -> impl Strategy<Value = Vec<(u64, u64)>> {
+    prop::collection::vec((1_000_000u64..1_000_000_000_000, 1_000_000u64..1_000_000_000_000), 1..=4)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    /// The parts add up to the amount, and every pool but the last gets its weight's
+    /// share rounded down
+    #[test]
+    fn parts_add_up_to_the_amount(amount in any::<u64>(), weights in weights()) {
+        let parts: Vec<u64> = (0..weights.len()).map(|index| split_amount(amount, &weights, index).unwrap()).collect();
+        prop_assert_eq!(parts.iter().map(|part| *part as u128).sum::<u128>(), amount as u128);
+        for (part, weight) in parts.iter().zip(&weights).take(weights.len() - 1) {
+            prop_assert_eq!(*part as u128, amount as u128 * *weight as u128 / WEIGHT_DENOMINATOR as u128);
+        }
+    }
+
+    /// Whatever the resolution, the weights handed out add up to `WEIGHT_DENOMINATOR`
+    #[test]
+    fn optimized_weights_are_normalised(amount in 1u64..1_000_000_000_000, pools in pools(), steps in 1u16..=200) {
+        let mut weights = vec![u16::MAX; pools.len()];
+        optimize_split_weights(amount, &mut weights, steps, |pool, amount_in| quote(&pools, pool, amount_in)).unwrap();
+        prop_assert_eq!(weights.iter().map(|weight| *weight as u64).sum::<u64>(), WEIGHT_DENOMINATOR);
+    }
+
+    /// The split never delivers less than sending everything through any one pool,
+    /// and returns the output of the weights it picked
+    #[test]
+    fn split_output_is_at_least_any_single_pool(amount in 1u64..1_000_000_000_000, pools in pools(), steps in 1u16..=100) {
+        let mut weights = vec![0; pools.len()];
+        let amount_out =
+            optimize_split_weights(amount, &mut weights, steps, |pool, amount_in| quote(&pools, pool, amount_in)).unwrap();
+
+        let split_out: u64 = (0..pools.len())
+            .map(|pool| quote(&pools, pool, split_amount(amount, &weights, pool).unwrap()).unwrap())
+            .sum();
+        prop_assert_eq!(amount_out, split_out);
+        for pool in 0..pools.len() {
+            prop_assert!(amount_out >= quote(&pools, pool, amount).unwrap());
+        }
+    }
+}
+
+#[test]
+fn identical_pools_share_the_input_evenly() {
+    let pools = [(1_000_000_000, 2_000_000_000); 4];
+    let mut weights = [0; 4];
+    let amount_out =
+        optimize_split_weights(100_000_000, &mut weights, 100, |pool, amount_in| quote(&pools, pool, amount_in)).unwrap();
+
+    assert_eq!(weights, [2_500; 4]);
+    assert_eq!(amount_out, 4 * quote(&pools, 0, 25_000_000).unwrap());
+    assert!(amount_out > quote(&pools, 0, 100_000_000).unwrap());
+}
+
+#[test]
+fn pool_that_cannot_quote_gets_no_weight() {
+    let pools = [(1_000_000_000, 2_000_000_000); 2];
+    let mut weights = [0; 2];
+    let amount_out = optimize_split_weights(100_000_000, &mut weights, 50, |pool, amount_in| {
+        if pool == 0 {
+            None
+        } else {
+            quote(&pools, pool, amount_in)
+        }
+    });
+
+    assert_eq!(weights, [0, WEIGHT_DENOMINATOR as u16]);
+    assert_eq!(amount_out, quote(&pools, 1, 100_000_000));
+}
@@ -0,0 +1,511 @@
+//! Decodes a Whirlpool account in the `solana account --output json` format from
+//! `tests/fixtures`. The fixture is a SOL/USDC pool at 150 USDC per SOL written to
+//! Orca's `Whirlpool` layout, not a copy of a mainnet account.
+//!
+//! Also builds `swap` and `swap_v2` legs on a pool, its tick arrays and vaults written to
+//! Orca's layouts at their real addresses. The ignored tests run those swaps through the
+//! Whirlpool program dumped by `scripts/dump_test_programs.sh` and check that they move
+//! exactly the quoted amounts.
+
+use anchor_spl::token::spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flash_loan_arbitrage_program::dex::{
+    clmm::{self, SwapQuote, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64},
+    whirlpool::{Whirlpool, WhirlpoolState, TICK_ARRAY_DISCRIMINATOR, TICK_ARRAY_SIZE, WHIRLPOOL_DISCRIMINATOR},
+    DexAdapter, DexKind, Leg, SwapAccounts, SwapLegParams,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const FIXTURE: &str = include_str!("../../../tests/fixtures/whirlpool_sol_usdc.json");
+const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// The fixture's address, owner and data
+fn fixture() -> (Pubkey, Pubkey, Vec<u8>) {
+    let json: serde_json::Value = serde_json::from_str(FIXTURE).unwrap();
+    let address = json["pubkey"].as_str().unwrap().parse().unwrap();
+    let account = &json["account"];
+    let owner = account["owner"].as_str().unwrap().parse().unwrap();
+    assert_eq!(account["data"][1], "base64");
+    let data = STANDARD.decode(account["data"][0].as_str().unwrap()).unwrap();
+    assert_eq!(data.len() as u64, account["space"].as_u64().unwrap());
+    (address, owner, data)
+}
+
+#[test]
+fn decodes_whirlpool_fixture() {
+    let (address, owner, mut data) = fixture();
+    assert_eq!(owner, WHIRLPOOL_PROGRAM_ID);
+    let mut lamports = 0;
+    let account = AccountInfo::new(&address, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+    let state = WhirlpoolState::load(&account).unwrap();
+    assert_eq!(state.sqrt_price, 7_144_393_258_922_745_604);
+    assert_eq!(state.liquidity, 2_718_281_828_459_045);
+    assert_eq!(state.tick_current_index, -18_973);
+    assert_eq!(state.tick_spacing, 64);
+    assert_eq!(state.fee_rate, 3_000);
+    assert_eq!(state.token_vault_a, pubkey!("FCcEmk2VLnBsnbASF1k35uTHcay13FWmavUQSQPZyZ3s"));
+    assert_eq!(state.token_vault_b, pubkey!("9iE2PQzHMWqwjNzs2sCA1PNoqwgpiyWmXta5275vJyud"));
+
+    // The current tick is the one the sqrt price lies in
+    let tick = state.tick_current_index;
+    assert!(clmm::sqrt_price_from_tick_index(tick).unwrap() <= state.sqrt_price);
+    assert!(state.sqrt_price < clmm::sqrt_price_from_tick_index(tick + 1).unwrap());
+
+    // (sqrt_price / 2^64)^2 is the raw price, 150 USDC per SOL after decimals
+    let sqrt_price = state.sqrt_price as f64 / 2f64.powi(64);
+    let usdc_per_sol = sqrt_price * sqrt_price * 1e9 / 1e6;
+    assert!((usdc_per_sol - 150.0).abs() < 1e-6);
+}
+
+#[test]
+fn other_accounts_are_not_whirlpools() {
+    let (address, owner, mut data) = fixture();
+    data[0] ^= 1;
+    let mut lamports = 0;
+    let account = AccountInfo::new(&address, false, false, &mut lamports, &mut data, &owner, false, 0);
+    assert!(WhirlpoolState::load(&account).is_err());
+}
+
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// `Whirlpool` and `TickArray` layouts
+const WHIRLPOOL_LEN: usize = 653;
+const WHIRLPOOL_CONFIG_OFFSET: usize = 8;
+const WHIRLPOOL_BUMP_OFFSET: usize = 40;
+const WHIRLPOOL_TICK_SPACING_OFFSET: usize = 41;
+const WHIRLPOOL_TICK_SPACING_SEED_OFFSET: usize = 43;
+const WHIRLPOOL_FEE_RATE_OFFSET: usize = 45;
+const WHIRLPOOL_PROTOCOL_FEE_RATE_OFFSET: usize = 47;
+const WHIRLPOOL_LIQUIDITY_OFFSET: usize = 49;
+const WHIRLPOOL_SQRT_PRICE_OFFSET: usize = 65;
+const WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const WHIRLPOOL_TOKEN_MINT_A_OFFSET: usize = 101;
+const WHIRLPOOL_TOKEN_VAULT_A_OFFSET: usize = 133;
+const WHIRLPOOL_TOKEN_MINT_B_OFFSET: usize = 181;
+const WHIRLPOOL_TOKEN_VAULT_B_OFFSET: usize = 213;
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 8;
+const TICK_ARRAY_TICKS_OFFSET: usize = 12;
+const TICK_LEN: usize = 113;
+const TICK_LIQUIDITY_NET_OFFSET: usize = 1;
+const TICK_LIQUIDITY_GROSS_OFFSET: usize = 17;
+const TICK_ARRAY_WHIRLPOOL_OFFSET: usize = TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE * TICK_LEN;
+const TICK_ARRAY_LEN: usize = TICK_ARRAY_WHIRLPOOL_OFFSET + 32;
+
+const TICK_SPACING: u16 = 64;
+const TICKS_IN_ARRAY: i32 = TICK_ARRAY_SIZE as i32 * TICK_SPACING as i32;
+const FEE_RATE: u16 = 3_000;
+const TICK_CURRENT: i32 = 2_821;
+/// Two positions around the current tick: (lower tick, upper tick, liquidity)
+const POSITIONS: [(i32, i32, u128); 2] = [(1_536, 4_096, 600_000_000_000), (2_176, 3_456, 400_000_000_000)];
+const BALANCE: u64 = 1_000_000_000_000_000;
+/// Enough of either token to cross the inner position's edge but not the outer one's
+const AMOUNT_IN: u64 = 40_000_000_000;
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()).max(LAMPORTS_PER_SOL),
+        data,
+        owner,
+        ..Account::default()
+    }
+}
+
+fn mint_account() -> Account {
+    let mut data = vec![0u8; Mint::LEN];
+    Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: u64::MAX / 2,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    account(anchor_spl::token::ID, data)
+}
+
+fn token_account(mint: Pubkey, owner: Pubkey) -> Account {
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount {
+        mint,
+        owner,
+        amount: BALANCE,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }
+    .pack_into_slice(&mut data);
+    account(anchor_spl::token::ID, data)
+}
+
+fn find_address(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &WHIRLPOOL_PROGRAM_ID)
+}
+
+fn sqrt_price() -> u128 {
+    let lower = clmm::sqrt_price_from_tick_index(TICK_CURRENT).unwrap();
+    let upper = clmm::sqrt_price_from_tick_index(TICK_CURRENT + 1).unwrap();
+    lower + (upper - lower) / 2
+}
+
+/// A Whirlpool with the tick arrays either side of the current one, and a user holding
+/// both of its tokens
+struct Pool {
+    user: Keypair,
+    whirlpool: Pubkey,
+    mints: [Pubkey; 2],
+    vaults: [Pubkey; 2],
+    user_token_accounts: [Pubkey; 2],
+    oracle: Pubkey,
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl Pool {
+    fn new() -> Self {
+        let user = Keypair::new();
+        // Orca orders a pool's mints by address
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let config = Pubkey::new_unique();
+        let tick_spacing_seed = TICK_SPACING.to_le_bytes();
+        let (whirlpool, bump) = find_address(&[
+            b"whirlpool",
+            config.as_ref(),
+            mints[0].as_ref(),
+            mints[1].as_ref(),
+            &tick_spacing_seed,
+        ]);
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let user_token_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (oracle, _) = find_address(&[b"oracle", whirlpool.as_ref()]);
+
+        let mut data = vec![0u8; WHIRLPOOL_LEN];
+        write(&mut data, 0, &WHIRLPOOL_DISCRIMINATOR);
+        write(&mut data, WHIRLPOOL_CONFIG_OFFSET, config.as_ref());
+        data[WHIRLPOOL_BUMP_OFFSET] = bump;
+        write(&mut data, WHIRLPOOL_TICK_SPACING_OFFSET, &tick_spacing_seed);
+        write(&mut data, WHIRLPOOL_TICK_SPACING_SEED_OFFSET, &tick_spacing_seed);
+        write(&mut data, WHIRLPOOL_FEE_RATE_OFFSET, &FEE_RATE.to_le_bytes());
+        write(&mut data, WHIRLPOOL_PROTOCOL_FEE_RATE_OFFSET, &300u16.to_le_bytes());
+        let liquidity: u128 = POSITIONS.iter().map(|position| position.2).sum();
+        write(&mut data, WHIRLPOOL_LIQUIDITY_OFFSET, &liquidity.to_le_bytes());
+        write(&mut data, WHIRLPOOL_SQRT_PRICE_OFFSET, &sqrt_price().to_le_bytes());
+        write(&mut data, WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET, &TICK_CURRENT.to_le_bytes());
+        write(&mut data, WHIRLPOOL_TOKEN_MINT_A_OFFSET, mints[0].as_ref());
+        write(&mut data, WHIRLPOOL_TOKEN_VAULT_A_OFFSET, vaults[0].as_ref());
+        write(&mut data, WHIRLPOOL_TOKEN_MINT_B_OFFSET, mints[1].as_ref());
+        write(&mut data, WHIRLPOOL_TOKEN_VAULT_B_OFFSET, vaults[1].as_ref());
+
+        let mut accounts = vec![
+            (whirlpool, account(WHIRLPOOL_PROGRAM_ID, data)),
+            (mints[0], mint_account()),
+            (mints[1], mint_account()),
+            (vaults[0], token_account(mints[0], whirlpool)),
+            (vaults[1], token_account(mints[1], whirlpool)),
+            (user_token_accounts[0], token_account(mints[0], user.pubkey())),
+            (user_token_accounts[1], token_account(mints[1], user.pubkey())),
+            (user.pubkey(), account(Pubkey::default(), vec![])),
+        ];
+        for slot in -2..=2 {
+            let start = slot * TICKS_IN_ARRAY;
+            accounts.push((Self::tick_array(whirlpool, start), tick_array_account(whirlpool, start)));
+        }
+
+        Self {
+            user,
+            whirlpool,
+            mints,
+            vaults,
+            user_token_accounts,
+            oracle,
+            accounts,
+        }
+    }
+
+    fn tick_array(whirlpool: Pubkey, start_tick_index: i32) -> Pubkey {
+        find_address(&[b"tick_array", whirlpool.as_ref(), start_tick_index.to_string().as_bytes()]).0
+    }
+
+    /// The three tick arrays a swap from the current tick passes, in swap order
+    fn tick_arrays(&self, a_to_b: bool) -> [Pubkey; 3] {
+        let step = if a_to_b { -TICKS_IN_ARRAY } else { TICKS_IN_ARRAY };
+        [0, 1, 2].map(|slot| Self::tick_array(self.whirlpool, slot * step))
+    }
+
+    /// The leg's accounts in `SwapAccounts` order, followed by its remaining accounts
+    fn leg_accounts(&self, use_swap_v2: bool, a_to_b: bool) -> Vec<TestAccount> {
+        let (input, output) = if a_to_b {
+            (self.user_token_accounts[0], self.user_token_accounts[1])
+        } else {
+            (self.user_token_accounts[1], self.user_token_accounts[0])
+        };
+        let mut keys = vec![
+            (WHIRLPOOL_PROGRAM_ID, true),
+            (self.whirlpool, false),
+            (Pubkey::default(), false),
+            (self.user.pubkey(), false),
+            (input, false),
+            (output, false),
+            (self.vaults[0], false),
+            (self.vaults[1], false),
+            (anchor_spl::token::ID, true),
+        ];
+        keys.extend(self.tick_arrays(a_to_b).map(|tick_array| (tick_array, false)));
+        keys.push((self.oracle, false));
+        if use_swap_v2 {
+            keys.extend([
+                (self.mints[0], false),
+                (self.mints[1], false),
+                (anchor_spl::token::ID, true),
+                (anchor_spl::token::ID, true),
+                (MEMO_PROGRAM_ID, true),
+            ]);
+        }
+
+        keys.into_iter()
+            .map(|(key, executable)| {
+                let account = self.accounts.iter().find(|(address, _)| *address == key);
+                let (owner, data) = match account {
+                    Some((_, account)) => (account.owner, account.data.clone()),
+                    None if executable => (bpf_loader_upgradeable::ID, vec![]),
+                    // The oracle only exists for pools with adaptive fees
+                    None => (Pubkey::default(), vec![]),
+                };
+                TestAccount { key, owner, lamports: 0, data }
+            })
+            .collect()
+    }
+}
+
+fn tick_array_account(whirlpool: Pubkey, start_tick_index: i32) -> Account {
+    let mut data = vec![0u8; TICK_ARRAY_LEN];
+    write(&mut data, 0, &TICK_ARRAY_DISCRIMINATOR);
+    write(&mut data, TICK_ARRAY_START_TICK_INDEX_OFFSET, &start_tick_index.to_le_bytes());
+    write(&mut data, TICK_ARRAY_WHIRLPOOL_OFFSET, whirlpool.as_ref());
+    for (lower, upper, liquidity) in POSITIONS {
+        for (tick, liquidity_net) in [(lower, liquidity as i128), (upper, -(liquidity as i128))] {
+            let offset = tick - start_tick_index;
+            if !(0..TICKS_IN_ARRAY).contains(&offset) {
+                continue;
+            }
+            let at = TICK_ARRAY_TICKS_OFFSET + (offset / TICK_SPACING as i32) as usize * TICK_LEN;
+            data[at] = 1;
+            write(&mut data, at + TICK_LIQUIDITY_NET_OFFSET, &liquidity_net.to_le_bytes());
+            write(&mut data, at + TICK_LIQUIDITY_GROSS_OFFSET, &liquidity.to_le_bytes());
+        }
+    }
+    account(WHIRLPOOL_PROGRAM_ID, data)
+}
+
+/// An account for an `AccountInfo` to borrow
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+/// Quotes `amount` on the leg and builds its swap instruction with no minimum output
+fn quote_and_build(
+    pool: &Pool,
+    use_swap_v2: bool,
+    a_to_b: bool,
+    amount: u64,
+    sqrt_price_limit: u128,
+) -> (SwapQuote, Instruction) {
+    let mut accounts = pool.leg_accounts(use_swap_v2, a_to_b);
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    let params = SwapLegParams {
+        dex: if use_swap_v2 { DexKind::WhirlpoolV2 } else { DexKind::Whirlpool },
+        remaining_accounts_len: (infos.len() - 9) as u8,
+        sqrt_price_limit,
+    };
+    let leg = Leg::new(
+        &params,
+        SwapAccounts {
+            dex_program: &infos[0],
+            pool: &infos[1],
+            pool_authority: &infos[2],
+            user_authority: &infos[3],
+            input_token_account: &infos[4],
+            output_token_account: &infos[5],
+            pool_token_a_account: &infos[6],
+            pool_token_b_account: &infos[7],
+            token_program: &infos[8],
+            remaining_accounts: &infos[9..],
+        },
+    )
+    .unwrap();
+    assert_eq!(leg.a_to_b, a_to_b);
+    let adapter = Whirlpool { use_swap_v2 };
+    let quote = adapter.quote_swap(&leg, amount).unwrap();
+    (quote, adapter.build_swap_ix(&leg, amount, 0).unwrap())
+}
+
+#[test]
+fn swap_instructions_follow_orca_account_order() {
+    let pool = Pool::new();
+    let user = pool.user.pubkey();
+    let [token_a, token_b] = pool.user_token_accounts;
+    let [vault_a, vault_b] = pool.vaults;
+
+    for a_to_b in [true, false] {
+        let [tick_array_0, tick_array_1, tick_array_2] = pool.tick_arrays(a_to_b);
+        let pool_accounts = [
+            AccountMeta::new(pool.whirlpool, false),
+            AccountMeta::new(token_a, false),
+            AccountMeta::new(vault_a, false),
+            AccountMeta::new(token_b, false),
+            AccountMeta::new(vault_b, false),
+            AccountMeta::new(tick_array_0, false),
+            AccountMeta::new(tick_array_1, false),
+            AccountMeta::new(tick_array_2, false),
+            AccountMeta::new(pool.oracle, false),
+        ];
+        let mut swap = vec![
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(user, true),
+        ];
+        swap.extend_from_slice(&pool_accounts);
+        let mut swap_v2 = vec![
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+            AccountMeta::new_readonly(user, true),
+            pool_accounts[0].clone(),
+            AccountMeta::new_readonly(pool.mints[0], false),
+            AccountMeta::new_readonly(pool.mints[1], false),
+        ];
+        swap_v2.extend_from_slice(&pool_accounts[1..]);
+
+        for (use_swap_v2, expected_accounts) in [(false, &swap), (true, &swap_v2)] {
+            let default_limit = if a_to_b { MIN_SQRT_PRICE_X64 } else { MAX_SQRT_PRICE_X64 };
+            for (sqrt_price_limit, expected_limit) in [(0, default_limit), (sqrt_price(), sqrt_price())] {
+                let (_, ix) = quote_and_build(&pool, use_swap_v2, a_to_b, AMOUNT_IN, sqrt_price_limit);
+                assert_eq!(ix.program_id, WHIRLPOOL_PROGRAM_ID);
+                assert_eq!(&ix.accounts, expected_accounts);
+                // amount, other_amount_threshold, sqrt_price_limit, amount_specified_is_input, a_to_b
+                assert_eq!(ix.data[8..16], AMOUNT_IN.to_le_bytes());
+                assert_eq!(ix.data[16..24], 0u64.to_le_bytes());
+                assert_eq!(ix.data[24..40], expected_limit.to_le_bytes());
+                assert_eq!(ix.data[40..42], [1, a_to_b as u8]);
+                // swap_v2 ends with no transfer hook accounts
+                assert_eq!(ix.data.len(), if use_swap_v2 { 43 } else { 42 });
+            }
+        }
+    }
+}
+
+#[test]
+fn quotes_cross_the_inner_position() {
+    let pool = Pool::new();
+    let (a_to_b, _) = quote_and_build(&pool, false, true, AMOUNT_IN, 0);
+    assert_eq!(a_to_b.amount_in, AMOUNT_IN);
+    assert!(a_to_b.end_sqrt_price < clmm::sqrt_price_from_tick_index(POSITIONS[1].0).unwrap());
+    let (b_to_a, _) = quote_and_build(&pool, false, false, AMOUNT_IN, 0);
+    assert_eq!(b_to_a.amount_in, AMOUNT_IN);
+    assert!(b_to_a.end_sqrt_price > clmm::sqrt_price_from_tick_index(POSITIONS[1].1).unwrap());
+
+    // A limit short of the edge stops the swap there with input left over
+    let limit = clmm::sqrt_price_from_tick_index(2_500).unwrap();
+    let (limited, _) = quote_and_build(&pool, false, true, AMOUNT_IN, limit);
+    assert_eq!(limited.end_sqrt_price, limit);
+    assert!(limited.amount_in < AMOUNT_IN);
+}
+
+/// Runs the leg's swap through the dumped Whirlpool program and checks the user's
+/// balances and the pool's price move exactly as quoted
+async fn swap_moves_the_quoted_amounts(use_swap_v2: bool, a_to_b: bool, sqrt_price_limit: u128) {
+    let pool = Pool::new();
+    let mut program_test = ProgramTest::new("whirlpool", WHIRLPOOL_PROGRAM_ID, None);
+    for (address, account) in &pool.accounts {
+        program_test.add_account(*address, account.clone());
+    }
+    let mut context = program_test.start_with_context().await;
+
+    let (quote, ix) = quote_and_build(&pool, use_swap_v2, a_to_b, AMOUNT_IN, sqrt_price_limit);
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool.user],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut balances = [0; 2];
+    for (balance, address) in balances.iter_mut().zip(pool.user_token_accounts) {
+        let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+        *balance = TokenAccount::unpack(&account.data).unwrap().amount;
+    }
+    let (input, output) = if a_to_b { (balances[0], balances[1]) } else { (balances[1], balances[0]) };
+    assert_eq!(BALANCE - input, quote.amount_in);
+    assert_eq!(output - BALANCE, quote.amount_out);
+
+    let mut whirlpool = context.banks_client.get_account(pool.whirlpool).await.unwrap().unwrap();
+    let mut lamports = 0;
+    let info = AccountInfo::new(
+        &pool.whirlpool,
+        false,
+        false,
+        &mut lamports,
+        &mut whirlpool.data,
+        &whirlpool.owner,
+        false,
+        0,
+    );
+    assert_eq!(WhirlpoolState::load(&info).unwrap().sqrt_price, quote.end_sqrt_price);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/whirlpool.so from scripts/dump_test_programs.sh"]
+async fn swap_a_to_b_moves_the_quoted_amounts() {
+    swap_moves_the_quoted_amounts(false, true, 0).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/whirlpool.so from scripts/dump_test_programs.sh"]
+async fn swap_b_to_a_moves_the_quoted_amounts() {
+    swap_moves_the_quoted_amounts(false, false, 0).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/whirlpool.so from scripts/dump_test_programs.sh"]
+async fn swap_v2_a_to_b_moves_the_quoted_amounts() {
+    swap_moves_the_quoted_amounts(true, true, 0).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/whirlpool.so from scripts/dump_test_programs.sh"]
+async fn swap_v2_b_to_a_moves_the_quoted_amounts() {
+    swap_moves_the_quoted_amounts(true, false, 0).await;
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/whirlpool.so from scripts/dump_test_programs.sh"]
+async fn swaps_stop_at_the_price_limit() {
+    swap_moves_the_quoted_amounts(false, true, clmm::sqrt_price_from_tick_index(2_500).unwrap()).await;
+    swap_moves_the_quoted_amounts(true, false, clmm::sqrt_price_from_tick_index(3_200).unwrap()).await;
+}
@@ -0,0 +1,20 @@
+#!/bin/bash
+set -e  # Exit immediately if a command exits with a non-zero status
+
+# Builds MarginFi v2 from source into programs/flash_loan_arbitrage/tests/fixtures/marginfi.so,
+# where the ignored MarginFi program tests load it. The `mainnet-beta` feature gives the
+# build MarginFi's mainnet program id, which the tests and the flash loan builders use.
+# Needs the Solana toolchain's `cargo build-sbf`; set MARGINFI_REF to build another ref.
+
+FIXTURES="$(dirname "$0")/../programs/flash_loan_arbitrage/tests/fixtures"
+MARGINFI_REF="${MARGINFI_REF:-main}"
+SOURCE="$(mktemp -d)"
+trap 'rm -rf "$SOURCE"' EXIT
+mkdir -p "$FIXTURES"
+
+echo "Cloning marginfi-v2 at $MARGINFI_REF..."
+git clone --quiet --depth 1 --branch "$MARGINFI_REF" https://github.com/mrgnlabs/marginfi-v2 "$SOURCE"
+
+echo "Building marginfi..."
+cargo build-sbf --manifest-path "$SOURCE/programs/marginfi/Cargo.toml" --features mainnet-beta
+cp "$SOURCE/target/deploy/marginfi.so" "$FIXTURES/marginfi.so"
@@ -0,0 +1,77 @@
+#!/bin/bash
+set -e  # Exit immediately if a command exits with a non-zero status
+
+# Dumps the mainnet programs the ignored program tests run against into
+# programs/flash_loan_arbitrage/tests/fixtures, where solana-program-test looks for
+# `<name>.so`, along with the Raydium AMM v4 pool and OpenBook market accounts those
+# tests swap on and the MarginFi bank they borrow from. MarginFi itself is built from
+# source by scripts/build_marginfi.sh. Run the tests afterwards with `cargo test -- --ignored`.
+
+FIXTURES="$(dirname "$0")/../programs/flash_loan_arbitrage/tests/fixtures"
+CLUSTER="${CLUSTER:-mainnet-beta}"
+mkdir -p "$FIXTURES"
+
+dump() {
+    echo "Dumping $1 ($2)..."
+    solana program dump --url "$CLUSTER" "$2" "$FIXTURES/$1.so"
+}
+
+dump whirlpool whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc
+dump solend So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo
+dump raydium_cpmm CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C
+dump raydium_amm_v4 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8
+dump openbook srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX
+
+# Dumps an account as `solana account` JSON into the given fixtures directory
+dump_account() {
+    echo "Dumping account $2..."
+    mkdir -p "$1"
+    solana account --url "$CLUSTER" --output json --output-file "$1/$2.json" "$2" > /dev/null
+}
+
+# Prints the base58 keys at the given offsets of an account dumped into the given directory
+read_keys() {
+    python3 - "$1/$2.json" "${@:3}" <<'PYTHON'
+import base64, json, sys
+
+ALPHABET = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+
+def base58(key):
+    number = int.from_bytes(key, "big")
+    encoded = ""
+    while number:
+        number, digit = divmod(number, 58)
+        encoded = ALPHABET[digit] + encoded
+    return "1" * (len(key) - len(key.lstrip(b"\0"))) + encoded
+
+data = base64.b64decode(json.load(open(sys.argv[1]))["account"]["data"][0])
+for offset in sys.argv[2:]:
+    print(base58(data[int(offset):int(offset) + 32]))
+PYTHON
+}
+
+# The SOL/USDC AMM v4 pool, and the accounts its swap reads
+AMM_V4_POOL=58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2
+POOL_FIXTURES="$FIXTURES/raydium_amm_v4"
+
+dump_account "$POOL_FIXTURES" "$AMM_V4_POOL"
+# Coin and pc vaults, coin and pc mints, open orders, market and target orders of the `AmmInfo`
+AMM_ACCOUNTS=$(read_keys "$POOL_FIXTURES" "$AMM_V4_POOL" 336 368 400 432 496 528 592)
+for account in $AMM_ACCOUNTS; do
+    dump_account "$POOL_FIXTURES" "$account"
+done
+# Bids, asks, event queue and coin and pc vaults of the OpenBook `MarketState`
+MARKET=$(echo "$AMM_ACCOUNTS" | sed -n 6p)
+for account in $(read_keys "$POOL_FIXTURES" "$MARKET" 285 317 253 117 165); do
+    dump_account "$POOL_FIXTURES" "$account"
+done
+
+# The USDC bank of the main MarginFi group, with its mint, group and liquidity vault
+MARGINFI_BANK="${MARGINFI_BANK:-2s37akK2eyBbp8DZgCm7RtsaEz8eJP3Nxd4urLHQv7yB}"
+BANK_FIXTURES="$FIXTURES/marginfi"
+
+dump_account "$BANK_FIXTURES" "$MARGINFI_BANK"
+# Mint, group and liquidity vault of the `Bank`
+for account in $(read_keys "$BANK_FIXTURES" "$MARGINFI_BANK" 8 41 112); do
+    dump_account "$BANK_FIXTURES" "$account"
+done
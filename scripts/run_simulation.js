@@ -97,7 +97,6 @@ async function main() {
       dexBOutputTokenAccount: loanTokenAccount.publicKey,
       dexBTokenAAccount: dexBTokenAAccount.publicKey,
       dexBTokenBAccount: dexBTokenBAccount.publicKey,
-      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
     };
     
     // We'll do a client-side simulation since we can't create the real accounts
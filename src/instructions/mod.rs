@@ -1,3 +0,0 @@
-pub mod flash_loan;
-
-pub use flash_loan::*; 
\ No newline at end of file
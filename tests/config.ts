@@ -0,0 +1,281 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { FlashLoanArbitrage } from '../target/types/flash_loan_arbitrage';
+import { PublicKey, Keypair, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { expect } from 'chai';
+
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111');
+
+describe('config', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.FlashLoanArbitrage as Program<FlashLoanArbitrage>;
+
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from('config')], program.programId);
+  const roleAddress = (role: string, key: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from('operator'), Buffer.from(role), key.toBuffer()], program.programId)[0];
+  const adminRole = roleAddress('admin', provider.wallet.publicKey);
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+
+  const params = {
+    minLoanAmount: new anchor.BN(1_000),
+    maxLoanAmount: new anchor.BN('1000000000000'),
+    minProfitThreshold: new anchor.BN(1_000),
+    defaultSlippageBps: 100,
+    maxSlippageBps: 500,
+    maxQuoteAgeSlots: new anchor.BN(10),
+    transactionGuard: false,
+    allowCpi: true,
+    oracleMaxDeviationBps: 200,
+    oracleMaxConfidenceBps: 100,
+    oracleMaxAgeSecs: new anchor.BN(60),
+  };
+
+  // Returns the events a confirmed transaction emitted
+  async function eventsOf(signature: string) {
+    await provider.connection.confirmTransaction(signature, 'confirmed');
+    const tx = await provider.connection.getTransaction(signature, { commitment: 'confirmed' });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    return Array.from(parser.parseLogs(tx.meta.logMessages));
+  }
+
+  it('Should initialize the config and the first admin role for the upgrade authority', async () => {
+    const signature = await program.methods
+      .initializeConfig(params)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+        program: program.programId,
+        programData,
+        adminRole,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.admin.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+    expect(config.paused).to.equal(false);
+    expect(config.minLoanAmount.toNumber()).to.equal(1_000);
+    expect(config.defaultSlippageBps).to.equal(100);
+    expect(config.maxSlippageBps).to.equal(500);
+
+    const role = await program.account.operator.fetch(adminRole);
+    expect(role.operator.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+    expect(role.role).to.deep.equal({ admin: {} });
+
+    const events = await eventsOf(signature);
+    expect(events.map((event) => event.name)).to.deep.equal(['ConfigUpdated', 'OperatorUpdated']);
+    expect(events[0].data.paused).to.equal(false);
+  });
+
+  it('Should update the limits and emit ConfigUpdated', async () => {
+    const signature = await program.methods
+      .updateConfig({ ...params, minProfitThreshold: new anchor.BN(5_000), maxQuoteAgeSlots: new anchor.BN(5) }, false)
+      .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.minProfitThreshold.toNumber()).to.equal(5_000);
+    expect(config.maxQuoteAgeSlots.toNumber()).to.equal(5);
+
+    const [event] = await eventsOf(signature);
+    expect(event.name).to.equal('ConfigUpdated');
+    expect(event.data.minProfitThreshold.toNumber()).to.equal(5_000);
+  });
+
+  it('Should turn on the transaction guard and forbid CPI', async () => {
+    const signature = await program.methods
+      .updateConfig({ ...params, transactionGuard: true, allowCpi: false }, false)
+      .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.transactionGuard).to.equal(true);
+    expect(config.allowCpi).to.equal(false);
+    expect((await eventsOf(signature))[0].data.transactionGuard).to.equal(true);
+  });
+
+  it('Should pause and unpause the program', async () => {
+    let signature = await program.methods
+      .updateConfig(params, true)
+      .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+      .rpc();
+    expect((await program.account.config.fetch(configPda)).paused).to.equal(true);
+    expect((await eventsOf(signature))[0].data.paused).to.equal(true);
+
+    signature = await program.methods
+      .updateConfig(params, false)
+      .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+      .rpc();
+    expect((await program.account.config.fetch(configPda)).paused).to.equal(false);
+    expect((await eventsOf(signature))[0].data.paused).to.equal(false);
+  });
+
+  it('Should reject updates from keys without the admin role', async () => {
+    const intruder = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(intruder.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(signature);
+
+    try {
+      await program.methods
+        .updateConfig(params, true)
+        .accounts({ admin: intruder.publicKey, adminRole: roleAddress('admin', intruder.publicKey), config: configPda })
+        .signers([intruder])
+        .rpc();
+      expect.fail('update_config should reject a signer without the admin role');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('AccountNotInitialized');
+    }
+    expect((await program.account.config.fetch(configPda)).paused).to.equal(false);
+  });
+
+  it('Should reject inconsistent limits', async () => {
+    try {
+      await program.methods
+        .updateConfig({ ...params, minLoanAmount: new anchor.BN('2000000000000') }, false)
+        .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+        .rpc();
+      expect.fail('update_config should reject a minimum loan above the maximum');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('InvalidConfig');
+    }
+  });
+
+  it('Should reject a default slippage above the maximum', async () => {
+    try {
+      await program.methods
+        .updateConfig({ ...params, defaultSlippageBps: 600 }, false)
+        .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+        .rpc();
+      expect.fail('update_config should reject a default slippage above max_slippage_bps');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('InvalidConfig');
+    }
+  });
+
+  it('Should reject an oracle band above 100%', async () => {
+    try {
+      await program.methods
+        .updateConfig({ ...params, oracleMaxDeviationBps: 10_001 }, false)
+        .accounts({ admin: provider.wallet.publicKey, adminRole, config: configPda })
+        .rpc();
+      expect.fail('update_config should reject an oracle deviation band above 10000 bps');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('InvalidConfig');
+    }
+  });
+
+  describe('program registry', () => {
+    const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from('program_registry')], program.programId);
+    const whirlpoolProgram = new PublicKey('whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc');
+    const whirlpoolKind = { dex: { 0: { whirlpool: {} } } };
+
+    const registryAccounts = () => ({
+      admin: provider.wallet.publicKey,
+      adminRole,
+      programRegistry: registryPda,
+    });
+
+    it('Should initialize an empty registry', async () => {
+      await program.methods
+        .initializeProgramRegistry()
+        .accounts({ ...registryAccounts(), systemProgram: anchor.web3.SystemProgram.programId })
+        .rpc();
+
+      const registry = await program.account.programRegistry.fetch(registryPda);
+      expect(registry.programs).to.have.length(0);
+    });
+
+    it('Should approve a program for an adapter and emit ProgramRegistryUpdated', async () => {
+      const signature = await program.methods
+        .approveProgram(whirlpoolProgram, whirlpoolKind)
+        .accounts(registryAccounts())
+        .rpc();
+
+      const registry = await program.account.programRegistry.fetch(registryPda);
+      expect(registry.programs).to.have.length(1);
+      expect(registry.programs[0].programId.toBase58()).to.equal(whirlpoolProgram.toBase58());
+
+      const [event] = await eventsOf(signature);
+      expect(event.name).to.equal('ProgramRegistryUpdated');
+      expect(event.data.approved).to.equal(true);
+    });
+
+    it('Should reject registry changes from keys without the admin role', async () => {
+      const intruder = Keypair.generate();
+      try {
+        await program.methods
+          .approveProgram(Keypair.generate().publicKey, whirlpoolKind)
+          .accounts({ ...registryAccounts(), admin: intruder.publicKey, adminRole: roleAddress('admin', intruder.publicKey) })
+          .signers([intruder])
+          .rpc();
+        expect.fail('approve_program should reject a signer without the admin role');
+      } catch (e) {
+        expect(e.error.errorCode.code).to.equal('AccountNotInitialized');
+      }
+    });
+
+    it('Should approve a tip account for the transaction guard', async () => {
+      const tipAccount = Keypair.generate().publicKey;
+      await program.methods
+        .approveProgram(tipAccount, { tip: {} } as any)
+        .accounts(registryAccounts())
+        .rpc();
+
+      const registry = await program.account.programRegistry.fetch(registryPda);
+      const entry = registry.programs.find((approved) => approved.programId.equals(tipAccount));
+      expect(entry.kind).to.deep.equal({ tip: {} });
+
+      await program.methods
+        .revokeProgram(tipAccount, { tip: {} } as any)
+        .accounts(registryAccounts())
+        .rpc();
+    });
+
+    it('Should revoke a program', async () => {
+      const signature = await program.methods
+        .revokeProgram(whirlpoolProgram, whirlpoolKind)
+        .accounts(registryAccounts())
+        .rpc();
+
+      const registry = await program.account.programRegistry.fetch(registryPda);
+      expect(registry.programs).to.have.length(0);
+      expect((await eventsOf(signature))[0].data.approved).to.equal(false);
+    });
+
+    it('Should fail to revoke a program that is not approved', async () => {
+      try {
+        await program.methods
+          .revokeProgram(whirlpoolProgram, whirlpoolKind)
+          .accounts(registryAccounts())
+          .rpc();
+        expect.fail('revoke_program should reject a program that is not approved');
+      } catch (e) {
+        expect(e.error.errorCode.code).to.equal('ProgramNotApproved');
+      }
+    });
+  });
+
+  describe('vault', () => {
+    const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from('vault')], program.programId);
+
+    it('Should initialize the vault', async () => {
+      await program.methods
+        .initializeVault()
+        .accounts({
+          admin: provider.wallet.publicKey,
+          adminRole,
+          vault: vaultPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const vault = await program.account.vault.fetch(vaultPda);
+      expect(vault.bump).to.be.a('number');
+    });
+  });
+});
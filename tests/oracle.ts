@@ -0,0 +1,125 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { FlashLoanArbitrage } from '../target/types/flash_loan_arbitrage';
+import { createMint } from '@solana/spl-token';
+import { PublicKey, Keypair, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { expect } from 'chai';
+
+// Oracle accounts built for the tests and loaded by the local validator, see Anchor.toml.
+// The Pyth account holds a fully verified SOL/USD price of 150 ± 0.075, the partial one
+// the same price with a partially verified update, and the Switchboard account a USDC/USD
+// result of 1 with a standard deviation of 0.0001.
+const PYTH_SOL_USD = new PublicKey('8x5HvtANFixVkLPi2aJPcQkk6iafptnGk9UcrWUtG1cY');
+const PYTH_SOL_USD_PARTIAL = new PublicKey('D7nmLxzaBEusVkE72T4GQSwq1rFPQmBTW44wzEHUJMKc');
+const SWITCHBOARD_USDC_USD = new PublicKey('KttCqPPXdpyL3Kp2tzt4Ddb4Ct6AeoDcEZhPo9v7oav');
+
+// Runs after tests/config.ts, which creates the config and gives the wallet the admin role
+describe('oracle registry', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.FlashLoanArbitrage as Program<FlashLoanArbitrage>;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  const roleAddress = (role: string, key: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from('operator'), Buffer.from(role), key.toBuffer()], program.programId)[0];
+  const adminRole = roleAddress('admin', provider.wallet.publicKey);
+  const entryAddress = (mint: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from('oracle'), mint.toBuffer()], program.programId)[0];
+
+  function registerOracle(mint: PublicKey, oracle: PublicKey, kind: object) {
+    return program.methods
+      .registerOracle(kind as any)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        adminRole,
+        mint,
+        oracle,
+        oracleEntry: entryAddress(mint),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  let solMint: PublicKey;
+  let usdcMint: PublicKey;
+
+  before(async () => {
+    solMint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+    usdcMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+  });
+
+  it('Should register a Pyth pull feed with the mint decimals', async () => {
+    await registerOracle(solMint, PYTH_SOL_USD, { pythPull: {} });
+
+    const entry = await program.account.oracleEntry.fetch(entryAddress(solMint));
+    expect(entry.mint.toBase58()).to.equal(solMint.toBase58());
+    expect(entry.oracle.toBase58()).to.equal(PYTH_SOL_USD.toBase58());
+    expect(entry.kind).to.deep.equal({ pythPull: {} });
+    expect(entry.decimals).to.equal(9);
+  });
+
+  it('Should register a Switchboard On-Demand feed', async () => {
+    await registerOracle(usdcMint, SWITCHBOARD_USDC_USD, { switchboardOnDemand: {} });
+
+    const entry = await program.account.oracleEntry.fetch(entryAddress(usdcMint));
+    expect(entry.kind).to.deep.equal({ switchboardOnDemand: {} });
+    expect(entry.decimals).to.equal(6);
+  });
+
+  it('Should reject a partially verified Pyth update', async () => {
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+    await expectError(registerOracle(mint, PYTH_SOL_USD_PARTIAL, { pythPull: {} }), 'InvalidOracleAccount');
+  });
+
+  it('Should reject a feed registered as the wrong kind', async () => {
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+    await expectError(registerOracle(mint, PYTH_SOL_USD, { switchboardOnDemand: {} }), 'InvalidOracleAccount');
+  });
+
+  it('Should reject an account the oracle program does not own', async () => {
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+    await expectError(registerOracle(mint, provider.wallet.publicKey, { pythPull: {} }), 'InvalidOracleAccount');
+  });
+
+  it('Should not let a non-admin register an oracle', async () => {
+    const outsider = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(outsider.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(signature);
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+
+    await expectError(
+      program.methods
+        .registerOracle({ pythPull: {} } as any)
+        .accounts({
+          admin: outsider.publicKey,
+          adminRole: roleAddress('admin', outsider.publicKey),
+          mint,
+          oracle: PYTH_SOL_USD,
+          oracleEntry: entryAddress(mint),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([outsider])
+        .rpc(),
+      'AccountNotInitialized'
+    );
+  });
+
+  it('Should deregister an oracle and return its rent', async () => {
+    await program.methods
+      .deregisterOracle()
+      .accounts({ admin: provider.wallet.publicKey, adminRole, oracleEntry: entryAddress(usdcMint) })
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(entryAddress(usdcMint))).to.equal(null);
+  });
+});
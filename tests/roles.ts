@@ -0,0 +1,233 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { FlashLoanArbitrage } from '../target/types/flash_loan_arbitrage';
+import { TOKEN_PROGRAM_ID, createMint, createAccount, mintTo, getAccount } from '@solana/spl-token';
+import { PublicKey, Keypair, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { expect } from 'chai';
+
+// Runs after tests/config.ts, which gives the wallet the admin role and creates the vault
+describe('roles', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.FlashLoanArbitrage as Program<FlashLoanArbitrage>;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from('config')], program.programId);
+  const [vaultPda] = PublicKey.findProgramAddressSync([Buffer.from('vault')], program.programId);
+  const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from('program_registry')], program.programId);
+  const SOLEND_PROGRAM = new PublicKey('So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo');
+  const roleAddress = (role: string, key: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from('operator'), Buffer.from(role), key.toBuffer()], program.programId)[0];
+
+  const admin = provider.wallet.publicKey;
+  const executor = Keypair.generate();
+  const withdrawer = Keypair.generate();
+
+  let vaultTokenAccount: PublicKey;
+  let destination: PublicKey;
+  let mint: PublicKey;
+  let profitLedger: PublicKey;
+
+  const params = {
+    minLoanAmount: new anchor.BN(1_000),
+    maxLoanAmount: new anchor.BN('1000000000000'),
+    minProfitThreshold: new anchor.BN(1_000),
+    defaultSlippageBps: 100,
+    maxSlippageBps: 500,
+    maxQuoteAgeSlots: new anchor.BN(10),
+    transactionGuard: false,
+    allowCpi: true,
+    oracleMaxDeviationBps: 200,
+    oracleMaxConfidenceBps: 100,
+    oracleMaxAgeSecs: new anchor.BN(60),
+  };
+
+  function addOperator(operator: PublicKey, role: object) {
+    return program.methods
+      .addOperator(operator, role as any)
+      .accounts({
+        admin,
+        adminRole: roleAddress('admin', admin),
+        operatorRole: roleAddress(Object.keys(role)[0], operator),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  // Calls update_config, an admin instruction, signed by `signer` with `role` as its admin role
+  function updateConfigAs(signer: Keypair, role: PublicKey) {
+    return program.methods
+      .updateConfig(params, false)
+      .accounts({ admin: signer.publicKey, adminRole: role, config: configPda })
+      .signers([signer])
+      .rpc();
+  }
+
+  // Calls withdraw_profit, a withdrawer instruction, signed by `signer` with `role` as its withdrawer role
+  function withdrawProfitAs(signer: Keypair, role: PublicKey, amount: number) {
+    return program.methods
+      .withdrawProfit(new anchor.BN(amount))
+      .accounts({
+        withdrawer: signer.publicKey,
+        withdrawerRole: role,
+        vault: vaultPda,
+        vaultTokenAccount,
+        profitLedger,
+        destinationTokenAccount: destination,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  // Calls flash_loan_route signed by `signer` with `role` as its executor role. The role is
+  // checked before the route is looked at, so the route itself is left empty.
+  async function flashLoanRouteAs(signer: Keypair, role: PublicKey) {
+    const loanTokenAccount = await createAccount(provider.connection, payer, mint, signer.publicKey, Keypair.generate());
+    const slot = await provider.connection.getSlot();
+    return program.methods
+      .flashLoanRoute(new anchor.BN(1_000), new anchor.BN(0), { solend: {} } as any, [], null, {
+        validUntilSlot: new anchor.BN(slot + 100),
+        quoteSlot: new anchor.BN(slot),
+      })
+      .accounts({
+        base: {
+          authority: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        config: configPda,
+        programRegistry: registryPda,
+        vault: null,
+        executorRole: role,
+        profitLedger: null,
+        lendingProgram: SOLEND_PROGRAM,
+        loanTokenAccount,
+        loanMint: mint,
+        repaymentTokenAccount: null,
+        loanReserveAccount: Keypair.generate().publicKey,
+        lendingFeeAccount: Keypair.generate().publicKey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function expectError(call: Promise<unknown>, code: string) {
+    try {
+      await call;
+      expect.fail(`expected ${code}`);
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal(code);
+    }
+  }
+
+  before(async () => {
+    for (const keypair of [executor, withdrawer]) {
+      const signature = await provider.connection.requestAirdrop(keypair.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(signature);
+    }
+
+    mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    vaultTokenAccount = await createAccount(provider.connection, payer, mint, vaultPda, Keypair.generate());
+    destination = await createAccount(provider.connection, payer, mint, withdrawer.publicKey);
+    await mintTo(provider.connection, payer, mint, vaultTokenAccount, payer, 100);
+
+    [profitLedger] = PublicKey.findProgramAddressSync([Buffer.from('profit_ledger'), mint.toBuffer()], program.programId);
+    await program.methods
+      .initializeProfitLedger()
+      .accounts({
+        admin,
+        adminRole: roleAddress('admin', admin),
+        mint,
+        profitLedger,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  it('Should grant the executor and withdrawer roles', async () => {
+    await addOperator(executor.publicKey, { executor: {} });
+    await addOperator(withdrawer.publicKey, { withdrawer: {} });
+
+    const role = await program.account.operator.fetch(roleAddress('executor', executor.publicKey));
+    expect(role.operator.toBase58()).to.equal(executor.publicKey.toBase58());
+    expect(role.role).to.deep.equal({ executor: {} });
+  });
+
+  it('Should reject the executor and withdrawer from admin instructions', async () => {
+    await expectError(updateConfigAs(executor, roleAddress('executor', executor.publicKey)), 'ConstraintSeeds');
+    await expectError(updateConfigAs(withdrawer, roleAddress('withdrawer', withdrawer.publicKey)), 'ConstraintSeeds');
+  });
+
+  it('Should reject the admin and executor from withdrawer instructions', async () => {
+    await expectError(withdrawProfitAs(payer, roleAddress('admin', admin), 1), 'ConstraintSeeds');
+    await expectError(withdrawProfitAs(executor, roleAddress('executor', executor.publicKey), 1), 'ConstraintSeeds');
+  });
+
+  it('Should not let the withdrawer take the capital the vault trades with', async () => {
+    // The 100 minted to the vault is capital, not profit a trade has credited
+    await expectError(
+      withdrawProfitAs(withdrawer, roleAddress('withdrawer', withdrawer.publicKey), 40),
+      'WithdrawalExceedsProfit'
+    );
+    expect(Number((await getAccount(provider.connection, vaultTokenAccount)).amount)).to.equal(100);
+    expect((await program.account.profitLedger.fetch(profitLedger)).accruedProfit.toNumber()).to.equal(0);
+  });
+
+  it('Should reject the admin and withdrawer from the arbitrage', async () => {
+    // Their own role accounts are for other roles
+    await expectError(flashLoanRouteAs(payer, roleAddress('admin', admin)), 'ConstraintSeeds');
+    await expectError(flashLoanRouteAs(withdrawer, roleAddress('withdrawer', withdrawer.publicKey)), 'ConstraintSeeds');
+
+    // And neither holds an executor role
+    await expectError(flashLoanRouteAs(payer, roleAddress('executor', admin)), 'AccountNotInitialized');
+    await expectError(
+      flashLoanRouteAs(withdrawer, roleAddress('executor', withdrawer.publicKey)),
+      'AccountNotInitialized'
+    );
+  });
+
+  it('Should reject the executor and withdrawer from granting roles', async () => {
+    for (const [signer, role] of [
+      [executor, 'executor'],
+      [withdrawer, 'withdrawer'],
+    ] as [Keypair, string][]) {
+      await expectError(
+        program.methods
+          .addOperator(signer.publicKey, { admin: {} } as any)
+          .accounts({
+            admin: signer.publicKey,
+            adminRole: roleAddress(role, signer.publicKey),
+            operatorRole: roleAddress('admin', signer.publicKey),
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([signer])
+          .rpc(),
+        'ConstraintSeeds'
+      );
+    }
+  });
+
+  it('Should not let an admin revoke their own admin role', async () => {
+    await expectError(
+      program.methods
+        .removeOperator(admin, { admin: {} } as any)
+        .accounts({ admin, adminRole: roleAddress('admin', admin), operatorRole: roleAddress('admin', admin) })
+        .rpc(),
+      'CannotRemoveOwnAdminRole'
+    );
+  });
+
+  it('Should revoke a role and close its account', async () => {
+    const executorRole = roleAddress('executor', executor.publicKey);
+    await program.methods
+      .removeOperator(executor.publicKey, { executor: {} } as any)
+      .accounts({ admin, adminRole: roleAddress('admin', admin), operatorRole: executorRole })
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(executorRole)).to.equal(null);
+  });
+});
@@ -0,0 +1,275 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { FlashLoanArbitrage } from '../target/types/flash_loan_arbitrage';
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  createMint,
+  createAccount,
+  mintTo,
+  transfer,
+  getAccount,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+} from '@solana/spl-token';
+import { PublicKey, Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction, sendAndConfirmTransaction } from '@solana/web3.js';
+import { expect } from 'chai';
+
+// Runs after tests/config.ts, which creates the config and gives the wallet the admin role
+describe('strategy', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.FlashLoanArbitrage as Program<FlashLoanArbitrage>;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from('config')], program.programId);
+  const roleAddress = (role: string, key: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from('operator'), Buffer.from(role), key.toBuffer()], program.programId)[0];
+
+  let mint: PublicKey;
+  let strategyPda: PublicKey;
+  let assetsPda: PublicKey;
+  let shareMintPda: PublicKey;
+  let treasury: PublicKey;
+
+  // A funded depositor with a token account and a share account
+  async function newDepositor(amount: number) {
+    const keypair = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(keypair.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(signature);
+    const tokenAccount = await createAccount(provider.connection, payer, mint, keypair.publicKey);
+    const shareAccount = await createAccount(provider.connection, payer, shareMintPda, keypair.publicKey);
+    await mintTo(provider.connection, payer, mint, tokenAccount, payer, amount);
+    return { keypair, tokenAccount, shareAccount };
+  }
+
+  function deposit(depositor: Awaited<ReturnType<typeof newDepositor>>, amount: number) {
+    return program.methods
+      .deposit(new anchor.BN(amount))
+      .accounts({
+        depositor: depositor.keypair.publicKey,
+        config: configPda,
+        strategy: strategyPda,
+        mint,
+        assetsTokenAccount: assetsPda,
+        shareMint: shareMintPda,
+        depositorTokenAccount: depositor.tokenAccount,
+        depositorShareAccount: depositor.shareAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([depositor.keypair])
+      .rpc();
+  }
+
+  function withdraw(depositor: Awaited<ReturnType<typeof newDepositor>>, shares: number) {
+    return program.methods
+      .withdraw(new anchor.BN(shares))
+      .accounts({
+        depositor: depositor.keypair.publicKey,
+        strategy: strategyPda,
+        mint,
+        assetsTokenAccount: assetsPda,
+        shareMint: shareMintPda,
+        depositorShareAccount: depositor.shareAccount,
+        depositorTokenAccount: depositor.tokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([depositor.keypair])
+      .rpc();
+  }
+
+  const balance = async (account: PublicKey) => Number((await getAccount(provider.connection, account)).amount);
+
+  before(async () => {
+    mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    [strategyPda] = PublicKey.findProgramAddressSync([Buffer.from('strategy'), mint.toBuffer()], program.programId);
+    [assetsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('strategy_assets'), strategyPda.toBuffer()],
+      program.programId
+    );
+    [shareMintPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('strategy_shares'), strategyPda.toBuffer()],
+      program.programId
+    );
+    treasury = await createAccount(provider.connection, payer, mint, payer.publicKey);
+  });
+
+  it('Should initialize a strategy with its assets account and share mint', async () => {
+    await program.methods
+      .initializeStrategy(1_000)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        adminRole: roleAddress('admin', provider.wallet.publicKey),
+        mint,
+        strategy: strategyPda,
+        assetsTokenAccount: assetsPda,
+        shareMint: shareMintPda,
+        treasuryTokenAccount: treasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.performanceFeeBps).to.equal(1_000);
+    expect(strategy.totalAssets.toNumber()).to.equal(0);
+    expect(strategy.shareMint.toBase58()).to.equal(shareMintPda.toBase58());
+  });
+
+  it('Should not let a first depositor inflate the share price by donating', async () => {
+    const attacker = await newDepositor(1_000_000_001);
+    const victim = await newDepositor(1_000_000);
+
+    // Deposit one base unit, then send a large amount straight to the assets account
+    await deposit(attacker, 1);
+    expect(await balance(attacker.shareAccount)).to.equal(1);
+    await transfer(provider.connection, payer, attacker.tokenAccount, assetsPda, attacker.keypair, 1_000_000_000);
+
+    // The donation is not part of the strategy's assets, so the victim pays the same price
+    await deposit(victim, 1_000_000);
+    expect(await balance(victim.shareAccount)).to.equal(1_000_000);
+
+    // And the attacker's share still redeems for what it was worth
+    await withdraw(attacker, 1);
+    expect(await balance(attacker.tokenAccount)).to.equal(1);
+
+    await withdraw(victim, 1_000_000);
+    expect(await balance(victim.tokenAccount)).to.equal(1_000_000);
+  });
+
+  it('Should round shares and redemptions down and reject amounts that round to zero', async () => {
+    const depositor = await newDepositor(10);
+    try {
+      await deposit(depositor, 0);
+      expect.fail('deposit should reject an amount that mints no shares');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('StrategyAmountTooSmall');
+    }
+
+    await deposit(depositor, 7);
+    const shares = await balance(depositor.shareAccount);
+    const strategy = await program.account.strategy.fetch(strategyPda);
+
+    // A redemption never pays out more than the deposit it came from
+    await withdraw(depositor, shares);
+    expect(await balance(depositor.tokenAccount)).to.be.at.most(10);
+    expect((await program.account.strategy.fetch(strategyPda)).totalAssets.toNumber()).to.be.at.least(
+      strategy.totalAssets.toNumber() - 7
+    );
+
+    try {
+      await withdraw(depositor, 0);
+      expect.fail('withdraw should reject shares that redeem for nothing');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('StrategyAmountTooSmall');
+    }
+  });
+
+  it('Should only let a withdrawer collect fees', async () => {
+    const intruder = Keypair.generate();
+    try {
+      await program.methods
+        .collectFees()
+        .accounts({
+          withdrawer: intruder.publicKey,
+          withdrawerRole: roleAddress('withdrawer', intruder.publicKey),
+          strategy: strategyPda,
+          mint,
+          assetsTokenAccount: assetsPda,
+          treasuryTokenAccount: treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([intruder])
+        .rpc();
+      expect.fail('collect_fees should reject a signer without the withdrawer role');
+    } catch (e) {
+      expect(e.error.errorCode.code).to.equal('AccountNotInitialized');
+    }
+  });
+
+  describe('with a Token-2022 transfer fee', () => {
+    // 1% of every transfer is withheld from the recipient
+    const TRANSFER_FEE_BPS = 100;
+
+    it('Should mint shares for the assets received after the transfer fee', async () => {
+      const feeMint = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: payer.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            payer.publicKey,
+            payer.publicKey,
+            TRANSFER_FEE_BPS,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(feeMint.publicKey, 6, payer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [payer, feeMint]
+      );
+
+      const mint = feeMint.publicKey;
+      const [strategy] = PublicKey.findProgramAddressSync([Buffer.from('strategy'), mint.toBuffer()], program.programId);
+      const [assets] = PublicKey.findProgramAddressSync([Buffer.from('strategy_assets'), strategy.toBuffer()], program.programId);
+      const [shareMint] = PublicKey.findProgramAddressSync([Buffer.from('strategy_shares'), strategy.toBuffer()], program.programId);
+      const feeTreasury = await createAccount(provider.connection, payer, mint, payer.publicKey, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+
+      await program.methods
+        .initializeStrategy(1_000)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          adminRole: roleAddress('admin', provider.wallet.publicKey),
+          mint,
+          strategy,
+          assetsTokenAccount: assets,
+          shareMint,
+          treasuryTokenAccount: feeTreasury,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const depositor = Keypair.generate();
+      const signature = await provider.connection.requestAirdrop(depositor.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(signature);
+      const tokenAccount = await createAccount(provider.connection, payer, mint, depositor.publicKey, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+      const shareAccount = await createAccount(provider.connection, payer, shareMint, depositor.publicKey, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+      await mintTo(provider.connection, payer, mint, tokenAccount, payer, 1_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+      await program.methods
+        .deposit(new anchor.BN(1_000_000))
+        .accounts({
+          depositor: depositor.publicKey,
+          config: configPda,
+          strategy,
+          mint,
+          assetsTokenAccount: assets,
+          shareMint,
+          depositorTokenAccount: tokenAccount,
+          depositorShareAccount: shareAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([depositor])
+        .rpc();
+
+      // The assets account receives 990_000, and only that backs the shares
+      const received = Number((await getAccount(provider.connection, assets, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+      expect(received).to.equal(990_000);
+      expect((await program.account.strategy.fetch(strategy)).totalAssets.toNumber()).to.equal(received);
+      const shares = Number((await getAccount(provider.connection, shareAccount, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+      expect(shares).to.equal(990_000);
+    });
+  });
+});